@@ -0,0 +1,367 @@
+use crate::atlas::Atlas;
+use crate::error::Error;
+use crate::sprite::Sprite;
+use sdl2::render::WindowCanvas;
+use std::time::Duration;
+
+// Frames shorter than this would make `update` spin, so they are shown at least this long
+const MIN_FRAME_DURATION: Duration = Duration::from_millis(1);
+// Faster speeds, infinity included, are clamped to this so scaled time can't overflow a Duration
+const MAX_SPEED: f32 = 1000.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Playback {
+    // First to last frame, then start over
+    Loop,
+    // First to last frame, then stay on the last one
+    Once,
+    // First to last frame and back, then start over
+    PingPong,
+    // Last to first frame, then start over
+    Reverse,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationEvent {
+    FrameChanged(usize),
+    // A cycle completed and the animation starts over
+    Looped,
+    // A `Once` animation reached its last frame
+    Finished,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnimationFrame {
+    pub name: String,
+    pub duration: Duration,
+}
+
+impl AnimationFrame {
+    pub fn new(name: &str, duration: Duration) -> Self {
+        AnimationFrame {
+            name: name.to_string(),
+            duration: duration.max(MIN_FRAME_DURATION),
+        }
+    }
+}
+
+// Plays named frames of a sprite's atlas driven by elapsed time, so playback speed doesn't
+// depend on how often we render.
+//
+// Events emitted by the last `update` can be taken via `drain_events`.
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<AnimationFrame>,
+    playback: Playback,
+    speed: f32,
+    index: usize,
+    forward: bool,
+    elapsed: Duration,
+    finished: bool,
+    events: Vec<AnimationEvent>,
+}
+
+impl Animation {
+    // Panics if there are no frames.
+    pub fn new(frames: Vec<AnimationFrame>) -> Self {
+        assert!(!frames.is_empty(), "animation needs at least one frame");
+        Animation {
+            frames,
+            playback: Playback::Loop,
+            speed: 1.0,
+            index: 0,
+            forward: true,
+            elapsed: Duration::from_secs(0),
+            finished: false,
+            events: Vec::new(),
+        }
+    }
+
+    // Shows each of the named frames for the same duration.
+    pub fn uniform<'n, I>(names: I, frame_duration: Duration) -> Self
+    where
+        I: IntoIterator<Item = &'n str>,
+    {
+        let frames = names
+            .into_iter()
+            .map(|name| AnimationFrame::new(name, frame_duration))
+            .collect();
+        Animation::new(frames)
+    }
+
+    // Plays all frames of the atlas in the order they were declared.
+    pub fn from_atlas(atlas: &Atlas, frame_duration: Duration) -> Result<Self, Error> {
+        if atlas.is_empty() {
+            return Err(Error::Atlas {
+                path: None,
+                message: "cannot animate an atlas without frames".to_string(),
+            });
+        }
+        Ok(Animation::uniform(atlas.frame_names(), frame_duration))
+    }
+
+    pub fn playback(mut self, playback: Playback) -> Self {
+        self.set_playback(playback);
+        self
+    }
+
+    // Multiplies elapsed time, i.e. `2.0` plays twice as fast and `0.0` pauses. Negative speeds
+    // and NaN pause as well.
+    pub fn speed(mut self, speed: f32) -> Self {
+        self.set_speed(speed);
+        self
+    }
+
+    // Restarts the animation in the new mode
+    pub fn set_playback(&mut self, playback: Playback) {
+        self.playback = playback;
+        self.reset();
+    }
+
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = if speed.is_nan() {
+            0.0
+        } else {
+            speed.clamp(0.0, MAX_SPEED)
+        };
+    }
+
+    pub fn current_playback(&self) -> Playback {
+        self.playback
+    }
+
+    pub fn current_speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn reset(&mut self) {
+        self.index = match self.playback {
+            Playback::Reverse => self.frames.len() - 1,
+            _ => 0,
+        };
+        self.forward = self.playback != Playback::Reverse;
+        self.elapsed = Duration::from_secs(0);
+        self.finished = false;
+    }
+
+    pub fn update(&mut self, dt: Duration) {
+        self.events.clear();
+        if self.finished {
+            return;
+        }
+        self.elapsed += dt.mul_f32(self.speed);
+        while !self.finished && self.elapsed >= self.frames[self.index].duration {
+            self.elapsed -= self.frames[self.index].duration;
+            self.advance();
+        }
+    }
+
+    fn advance(&mut self) {
+        let last = self.frames.len() - 1;
+        let previous = self.index;
+        match self.playback {
+            Playback::Loop if self.index == last => {
+                self.index = 0;
+                self.events.push(AnimationEvent::Looped);
+            }
+            Playback::Reverse if self.index == 0 => {
+                self.index = last;
+                self.events.push(AnimationEvent::Looped);
+            }
+            Playback::Once if self.index == last => {
+                self.finished = true;
+                self.elapsed = Duration::from_secs(0);
+                self.events.push(AnimationEvent::Finished);
+            }
+            Playback::PingPong if last == 0 => {
+                self.events.push(AnimationEvent::Looped);
+            }
+            Playback::PingPong => {
+                if self.forward && self.index == last {
+                    self.forward = false;
+                } else if !self.forward && self.index == 0 {
+                    self.forward = true;
+                }
+                self.index = if self.forward {
+                    self.index + 1
+                } else {
+                    self.index - 1
+                };
+                if self.index == 0 {
+                    self.events.push(AnimationEvent::Looped);
+                }
+            }
+            Playback::Reverse => self.index -= 1,
+            Playback::Loop | Playback::Once => self.index += 1,
+        }
+        if self.index != previous {
+            self.events.push(AnimationEvent::FrameChanged(self.index));
+        }
+    }
+
+    pub fn drain_events(&mut self) -> impl Iterator<Item = AnimationEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn frames(&self) -> &[AnimationFrame] {
+        &self.frames
+    }
+
+    pub fn current_index(&self) -> usize {
+        self.index
+    }
+
+    pub fn current_frame(&self) -> &str {
+        &self.frames[self.index].name
+    }
+
+    // Renders the current frame, the sprite needs an atlas containing all frames.
+    pub fn render(
+        &self,
+        sprite: &Sprite,
+        canvas: &mut WindowCanvas,
+        x: i32,
+        y: i32,
+    ) -> Result<(), Error> {
+        sprite.render(canvas, x, y, self.current_frame())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn animation(playback: Playback) -> Animation {
+        Animation::uniform(vec!["a", "b", "c"], FRAME).playback(playback)
+    }
+
+    // Frame index after each update of one frame duration
+    fn indices(animation: &mut Animation, updates: usize) -> Vec<usize> {
+        (0..updates)
+            .map(|_| {
+                animation.update(FRAME);
+                animation.current_index()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn loop_playback() {
+        let mut animation = animation(Playback::Loop);
+        assert_eq!(animation.current_frame(), "a");
+        assert_eq!(indices(&mut animation, 5), [1, 2, 0, 1, 2]);
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn once_playback() {
+        let mut animation = animation(Playback::Once);
+        assert_eq!(indices(&mut animation, 2), [1, 2]);
+        assert!(!animation.is_finished());
+        animation.update(FRAME);
+        assert!(animation.is_finished());
+        assert_eq!(
+            animation.drain_events().collect::<Vec<_>>(),
+            [AnimationEvent::Finished]
+        );
+        animation.update(FRAME * 10);
+        assert_eq!(animation.current_frame(), "c");
+        assert_eq!(animation.drain_events().count(), 0);
+
+        animation.reset();
+        assert!(!animation.is_finished());
+        assert_eq!(animation.current_index(), 0);
+    }
+
+    #[test]
+    fn ping_pong_playback() {
+        let mut animation = animation(Playback::PingPong);
+        assert_eq!(indices(&mut animation, 6), [1, 2, 1, 0, 1, 2]);
+
+        let mut single = Animation::uniform(vec!["a"], FRAME).playback(Playback::PingPong);
+        single.update(FRAME);
+        assert_eq!(single.current_index(), 0);
+        assert_eq!(
+            single.drain_events().collect::<Vec<_>>(),
+            [AnimationEvent::Looped]
+        );
+    }
+
+    #[test]
+    fn reverse_playback() {
+        let mut animation = animation(Playback::Reverse);
+        assert_eq!(animation.current_frame(), "c");
+        assert_eq!(indices(&mut animation, 4), [1, 0, 2, 1]);
+    }
+
+    #[test]
+    fn events() {
+        let mut animation = animation(Playback::Loop);
+        animation.update(FRAME / 2);
+        assert_eq!(animation.drain_events().count(), 0);
+        animation.update(FRAME / 2);
+        assert_eq!(
+            animation.drain_events().collect::<Vec<_>>(),
+            [AnimationEvent::FrameChanged(1)]
+        );
+        // One long update passes several frames and reports each change
+        animation.update(FRAME * 2);
+        assert_eq!(
+            animation.drain_events().collect::<Vec<_>>(),
+            [
+                AnimationEvent::FrameChanged(2),
+                AnimationEvent::Looped,
+                AnimationEvent::FrameChanged(0)
+            ]
+        );
+        // Events of the previous update are gone even if they weren't drained
+        animation.update(FRAME);
+        animation.update(FRAME / 2);
+        assert_eq!(animation.drain_events().count(), 0);
+    }
+
+    #[test]
+    fn speed() {
+        let mut animation = animation(Playback::Loop).speed(2.0);
+        animation.update(FRAME / 2);
+        assert_eq!(animation.current_index(), 1);
+
+        animation.set_speed(0.0);
+        animation.update(FRAME * 10);
+        assert_eq!(animation.current_index(), 1);
+
+        animation.set_speed(0.5);
+        animation.update(FRAME);
+        assert_eq!(animation.current_index(), 1);
+        animation.update(FRAME);
+        assert_eq!(animation.current_index(), 2);
+    }
+
+    #[test]
+    fn invalid_speed() {
+        let mut animation = animation(Playback::Loop);
+        animation.set_speed(-1.0);
+        assert_eq!(animation.current_speed(), 0.0);
+        animation.set_speed(f32::NAN);
+        assert_eq!(animation.current_speed(), 0.0);
+        animation.update(FRAME);
+        assert_eq!(animation.current_index(), 0);
+
+        animation.set_speed(f32::INFINITY);
+        assert_eq!(animation.current_speed(), MAX_SPEED);
+        animation.update(FRAME);
+        assert_eq!(animation.current_index(), 1000 % 3);
+    }
+
+    #[test]
+    fn min_frame_duration() {
+        let frame = AnimationFrame::new("a", Duration::from_secs(0));
+        assert_eq!(frame.duration, MIN_FRAME_DURATION);
+    }
+}
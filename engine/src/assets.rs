@@ -0,0 +1,265 @@
+use crate::atlas::Atlas;
+use crate::error::{ensure_exists, Error};
+#[cfg(not(feature = "image"))]
+use crate::sprite::load_media;
+#[cfg(feature = "image")]
+use crate::sprite::{LoadOptions, Sprite};
+#[cfg(feature = "ttf")]
+use crate::text::FontOptions;
+use sdl2::audio::AudioSpecWAV;
+#[cfg(feature = "image")]
+use sdl2::image::LoadTexture;
+#[cfg(feature = "mixer")]
+use sdl2::mixer::{Chunk, Music};
+use sdl2::render::{Texture, TextureCreator};
+#[cfg(feature = "ttf")]
+use sdl2::ttf::{Font, Sdl2TtfContext};
+use sdl2::video::WindowContext;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+const ASSETS_DIR: &str = "assets";
+
+// Registry which loads each asset once and hands out shared handles to it afterwards.
+//
+// Keys are paths relative to the root, i.e. `"14_animated_sprites/foo.png"`.
+// The root defaults to the `assets` folder of the crate cargo is running, and to the one in the
+// current directory when launched without cargo. Use `root` to point it elsewhere.
+pub struct Assets<'a> {
+    root: PathBuf,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    #[cfg(feature = "ttf")]
+    ttf_context: Option<&'a Sdl2TtfContext>,
+    textures: RefCell<HashMap<PathBuf, Rc<Texture<'a>>>>,
+    #[cfg(feature = "image")]
+    sprites: RefCell<HashMap<(PathBuf, LoadOptions), Rc<Sprite<'a>>>>,
+    #[cfg(feature = "ttf")]
+    fonts: RefCell<HashMap<(PathBuf, u16, FontOptions), Rc<Font<'a, 'static>>>>,
+    sounds: RefCell<HashMap<PathBuf, Rc<AudioSpecWAV>>>,
+    #[cfg(feature = "mixer")]
+    chunks: RefCell<HashMap<PathBuf, Rc<Chunk>>>,
+    #[cfg(feature = "mixer")]
+    music: RefCell<HashMap<PathBuf, Rc<Music<'static>>>>,
+    atlases: RefCell<HashMap<PathBuf, Rc<Atlas>>>,
+}
+
+impl<'a> Assets<'a> {
+    pub fn new(texture_creator: &'a TextureCreator<WindowContext>) -> Self {
+        Assets {
+            root: default_root(),
+            texture_creator,
+            #[cfg(feature = "ttf")]
+            ttf_context: None,
+            textures: RefCell::new(HashMap::new()),
+            #[cfg(feature = "image")]
+            sprites: RefCell::new(HashMap::new()),
+            #[cfg(feature = "ttf")]
+            fonts: RefCell::new(HashMap::new()),
+            sounds: RefCell::new(HashMap::new()),
+            #[cfg(feature = "mixer")]
+            chunks: RefCell::new(HashMap::new()),
+            #[cfg(feature = "mixer")]
+            music: RefCell::new(HashMap::new()),
+            atlases: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn root<P: AsRef<Path>>(mut self, root: P) -> Self {
+        self.root = root.as_ref().to_path_buf();
+        self
+    }
+
+    // Fonts can only be loaded once TTF was initialized
+    #[cfg(feature = "ttf")]
+    pub fn with_ttf(mut self, ttf_context: &'a Sdl2TtfContext) -> Self {
+        self.ttf_context = Some(ttf_context);
+        self
+    }
+
+    pub fn root_dir(&self) -> &Path {
+        &self.root
+    }
+
+    // Full path of the asset with the given key, absolute keys are returned as is.
+    pub fn path<P: AsRef<Path>>(&self, key: P) -> PathBuf {
+        self.root.join(key)
+    }
+
+    // Loads a BMP, or with the `image` feature any supported image, into a texture.
+    pub fn texture<P: AsRef<Path>>(&self, key: P) -> Result<Rc<Texture<'a>>, Error> {
+        let path = self.path(key);
+        if let Some(texture) = self.textures.borrow().get(&path) {
+            return Ok(Rc::clone(texture));
+        }
+        let texture = Rc::new(self.load_texture(&path)?);
+        self.textures.borrow_mut().insert(path, Rc::clone(&texture));
+        Ok(texture)
+    }
+
+    #[cfg(feature = "image")]
+    fn load_texture(&self, path: &Path) -> Result<Texture<'a>, Error> {
+        ensure_exists(path)?;
+        self.texture_creator
+            .load_texture(path)
+            .map_err(|err| Error::image_decode(path, err))
+    }
+
+    #[cfg(not(feature = "image"))]
+    fn load_texture(&self, path: &Path) -> Result<Texture<'a>, Error> {
+        load_media(path, self.texture_creator)
+    }
+
+    #[cfg(feature = "image")]
+    pub fn sprite<P: AsRef<Path>>(&self, key: P) -> Result<Rc<Sprite<'a>>, Error> {
+        self.sprite_with_options(key, &LoadOptions::default())
+    }
+
+    // The same image loaded with different options results in separate sprites.
+    // A sprite sheet descriptor next to the image, i.e. `foo.json` or `foo.toml` for `foo.png`,
+    // is attached as the sprite's atlas so frames can be rendered by name.
+    #[cfg(feature = "image")]
+    pub fn sprite_with_options<P: AsRef<Path>>(
+        &self,
+        key: P,
+        options: &LoadOptions,
+    ) -> Result<Rc<Sprite<'a>>, Error> {
+        let cache_key = (self.path(key), *options);
+        if let Some(sprite) = self.sprites.borrow().get(&cache_key) {
+            return Ok(Rc::clone(sprite));
+        }
+        let path = &cache_key.0;
+        let mut sprite = Sprite::load_from_file_with_options(path, options, self.texture_creator)?;
+        if let Some(atlas_path) = atlas_path_for(path) {
+            let atlas = self.atlas(&atlas_path)?;
+            sprite = sprite.with_atlas(atlas).map_err(|err| match err {
+                Error::Atlas {
+                    path: None,
+                    message,
+                } => Error::Atlas {
+                    path: Some(atlas_path),
+                    message,
+                },
+                err => err,
+            })?;
+        }
+        let sprite = Rc::new(sprite);
+        self.sprites
+            .borrow_mut()
+            .insert(cache_key, Rc::clone(&sprite));
+        Ok(sprite)
+    }
+
+    #[cfg(feature = "ttf")]
+    pub fn font<P: AsRef<Path>>(
+        &self,
+        key: P,
+        point_size: u16,
+    ) -> Result<Rc<Font<'a, 'static>>, Error> {
+        self.font_with_options(key, point_size, &FontOptions::default())
+    }
+
+    // The same font file with different options is loaded and cached separately, since SDL_ttf
+    // keeps style, outline, kerning and hinting per loaded font.
+    #[cfg(feature = "ttf")]
+    pub fn font_with_options<P: AsRef<Path>>(
+        &self,
+        key: P,
+        point_size: u16,
+        options: &FontOptions,
+    ) -> Result<Rc<Font<'a, 'static>>, Error> {
+        let cache_key = (self.path(key), point_size, *options);
+        if let Some(font) = self.fonts.borrow().get(&cache_key) {
+            return Ok(Rc::clone(font));
+        }
+        let path = &cache_key.0;
+        let ttf_context = self.ttf_context.ok_or_else(|| {
+            Error::ttf(
+                "TTF_OpenFont",
+                Some(path),
+                "TTF not initialized, see Assets::with_ttf",
+            )
+        })?;
+        ensure_exists(path)?;
+        let mut font = ttf_context
+            .load_font(path, point_size)
+            .map_err(|err| Error::ttf("TTF_OpenFont", Some(path), err))?;
+        options.apply(&mut font);
+        let font = Rc::new(font);
+        self.fonts.borrow_mut().insert(cache_key, Rc::clone(&font));
+        Ok(font)
+    }
+
+    pub fn atlas<P: AsRef<Path>>(&self, key: P) -> Result<Rc<Atlas>, Error> {
+        let path = self.path(key);
+        if let Some(atlas) = self.atlases.borrow().get(&path) {
+            return Ok(Rc::clone(atlas));
+        }
+        let atlas = Rc::new(Atlas::load(&path)?);
+        self.atlases.borrow_mut().insert(path, Rc::clone(&atlas));
+        Ok(atlas)
+    }
+
+    // Decoded WAV data which still needs to be converted to the spec of the audio device.
+    pub fn sound<P: AsRef<Path>>(&self, key: P) -> Result<Rc<AudioSpecWAV>, Error> {
+        let path = self.path(key);
+        if let Some(sound) = self.sounds.borrow().get(&path) {
+            return Ok(Rc::clone(sound));
+        }
+        ensure_exists(&path)?;
+        let sound = AudioSpecWAV::load_wav(&path)
+            .map_err(|err| Error::audio_conversion(Some(&path), err))?;
+        let sound = Rc::new(sound);
+        self.sounds.borrow_mut().insert(path, Rc::clone(&sound));
+        Ok(sound)
+    }
+
+    // Sound effect in any format SDL_mixer supports, converted to the spec of the audio device.
+    // Can only be loaded once the device was opened via `sdl2::mixer::open_audio`.
+    #[cfg(feature = "mixer")]
+    pub fn chunk<P: AsRef<Path>>(&self, key: P) -> Result<Rc<Chunk>, Error> {
+        let path = self.path(key);
+        if let Some(chunk) = self.chunks.borrow().get(&path) {
+            return Ok(Rc::clone(chunk));
+        }
+        ensure_exists(&path)?;
+        let chunk =
+            Chunk::from_file(&path).map_err(|err| Error::mixer("Mix_LoadWAV", Some(&path), err))?;
+        let chunk = Rc::new(chunk);
+        self.chunks.borrow_mut().insert(path, Rc::clone(&chunk));
+        Ok(chunk)
+    }
+
+    // Music streamed by SDL_mixer while it plays, i.e. OGG, MP3 or MIDI.
+    #[cfg(feature = "mixer")]
+    pub fn music<P: AsRef<Path>>(&self, key: P) -> Result<Rc<Music<'static>>, Error> {
+        let path = self.path(key);
+        if let Some(music) = self.music.borrow().get(&path) {
+            return Ok(Rc::clone(music));
+        }
+        ensure_exists(&path)?;
+        let music =
+            Music::from_file(&path).map_err(|err| Error::mixer("Mix_LoadMUS", Some(&path), err))?;
+        let music = Rc::new(music);
+        self.music.borrow_mut().insert(path, Rc::clone(&music));
+        Ok(music)
+    }
+}
+
+// `CARGO_MANIFEST_DIR` is only set when launched via cargo, i.e. `cargo run` or `cargo test`
+fn default_root() -> PathBuf {
+    match env::var_os("CARGO_MANIFEST_DIR") {
+        Some(manifest_dir) => PathBuf::from(manifest_dir).join(ASSETS_DIR),
+        None => PathBuf::from(ASSETS_DIR),
+    }
+}
+
+#[cfg(feature = "image")]
+fn atlas_path_for(image_path: &Path) -> Option<PathBuf> {
+    ["json", "toml"]
+        .iter()
+        .map(|ext| image_path.with_extension(ext))
+        .find(|path| path.exists())
+}
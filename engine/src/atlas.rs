@@ -0,0 +1,396 @@
+use crate::error::Error;
+use sdl2::rect::Rect;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Named clip rects into a sprite sheet, loaded from a descriptor file next to the image.
+//
+// Supported are the JSON TexturePacker exports in both hash and array layout, and a TOML grid
+// of equally sized frames:
+//
+//   frame_width = 64
+//   frame_height = 205
+//   columns = 4
+//   rows = 1
+//   names = ["walk-0", "walk-1", "walk-2", "walk-3"]   # optional, defaults to "0", "1", ..
+//   margin = 0                                        # optional, around the grid
+//   spacing = 0                                       # optional, between frames
+//
+// Frames keep the order they were declared in, grid frames go row by row.
+#[derive(Clone, Debug, Default)]
+pub struct Atlas {
+    frames: Vec<Frame>,
+    index: HashMap<String, usize>,
+    size: Option<(u32, u32)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub name: String,
+    pub rect: Rect,
+}
+
+impl Atlas {
+    pub fn new() -> Self {
+        Atlas::default()
+    }
+
+    // Picks the format from the file extension, `.json` or `.toml`.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let src = fs::read_to_string(path).map_err(|err| {
+            if !path.exists() {
+                Error::MissingAsset {
+                    path: path.to_path_buf(),
+                }
+            } else {
+                Error::io(path, err)
+            }
+        })?;
+        let atlas = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Atlas::from_texture_packer_json(&src),
+            Some("toml") => Atlas::from_toml_grid(&src),
+            _ => Err("unknown atlas format, expected .json or .toml".to_string()),
+        };
+        atlas.map_err(|message| Error::Atlas {
+            path: Some(path.to_path_buf()),
+            message,
+        })
+    }
+
+    pub fn from_texture_packer_json(json: &str) -> Result<Self, String> {
+        let sheet: TexturePackerSheet =
+            serde_json::from_str(json).map_err(|err| err.to_string())?;
+        let frames: Vec<(String, TexturePackerFrame)> = match sheet.frames {
+            // Going through `Value` keeps the frames in the order of the file
+            TexturePackerFrames::Hash(frames) => frames
+                .into_iter()
+                .map(|(name, frame)| {
+                    serde_json::from_value(frame)
+                        .map(|frame| (name.clone(), frame))
+                        .map_err(|err| format!("frame {:?}: {}", name, err))
+                })
+                .collect::<Result<_, _>>()?,
+            TexturePackerFrames::Array(frames) => frames
+                .into_iter()
+                .map(|frame| (frame.filename, frame.frame))
+                .collect(),
+        };
+
+        let mut atlas = Atlas::new();
+        for (name, frame) in frames {
+            if frame.rotated {
+                return Err(format!("frame {:?} is rotated which isn't supported", name));
+            }
+            let rect = frame.frame;
+            atlas.add_frame(&name, Rect::new(rect.x, rect.y, rect.w, rect.h))?;
+        }
+        if let Some(meta) = sheet.meta {
+            atlas.size = meta.size.map(|size| (size.w, size.h));
+        }
+        if let Some((width, height)) = atlas.size {
+            atlas.validate(width, height)?;
+        }
+        Ok(atlas)
+    }
+
+    pub fn from_toml_grid(toml: &str) -> Result<Self, String> {
+        let grid: Grid = toml::from_str(toml).map_err(|err| err.to_string())?;
+        if grid.frame_width == 0 || grid.frame_height == 0 {
+            return Err("frame_width and frame_height need to be larger than 0".to_string());
+        }
+        let count = (grid.columns * grid.rows) as usize;
+        if let Some(names) = &grid.names {
+            if names.len() != count {
+                return Err(format!(
+                    "expected {} names for a {}x{} grid, found {}",
+                    count,
+                    grid.columns,
+                    grid.rows,
+                    names.len()
+                ));
+            }
+        }
+
+        let mut atlas = Atlas::new();
+        for idx in 0..count {
+            let col = idx as u32 % grid.columns;
+            let row = idx as u32 / grid.columns;
+            let x = grid.margin + col * (grid.frame_width + grid.spacing);
+            let y = grid.margin + row * (grid.frame_height + grid.spacing);
+            let name = match &grid.names {
+                Some(names) => names[idx].clone(),
+                None => idx.to_string(),
+            };
+            let rect = Rect::new(x as i32, y as i32, grid.frame_width, grid.frame_height);
+            atlas.add_frame(&name, rect)?;
+        }
+        Ok(atlas)
+    }
+
+    pub fn add_frame(&mut self, name: &str, rect: Rect) -> Result<(), String> {
+        if self.index.contains_key(name) {
+            return Err(format!("duplicate frame {:?}", name));
+        }
+        self.index.insert(name.to_string(), self.frames.len());
+        self.frames.push(Frame {
+            name: name.to_string(),
+            rect,
+        });
+        Ok(())
+    }
+
+    pub fn frame(&self, name: &str) -> Option<Rect> {
+        self.index.get(name).map(|&idx| self.frames[idx].rect)
+    }
+
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn frame_names(&self) -> impl Iterator<Item = &str> {
+        self.frames.iter().map(|frame| frame.name.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // Size of the texture the descriptor was exported for, if it says so
+    pub fn size(&self) -> Option<(u32, u32)> {
+        self.size
+    }
+
+    // Ensures every frame lies inside a texture of the given size.
+    pub fn validate(&self, width: u32, height: u32) -> Result<(), String> {
+        let bounds = Rect::new(0, 0, width, height);
+        for frame in &self.frames {
+            let rect = frame.rect;
+            let inside = rect.x() >= 0
+                && rect.y() >= 0
+                && rect.right() <= bounds.right()
+                && rect.bottom() <= bounds.bottom();
+            if !inside {
+                return Err(format!(
+                    "frame {:?} at ({}, {}) {}x{} exceeds the {}x{} texture",
+                    frame.name,
+                    rect.x(),
+                    rect.y(),
+                    rect.width(),
+                    rect.height(),
+                    width,
+                    height
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct TexturePackerSheet {
+    frames: TexturePackerFrames,
+    meta: Option<TexturePackerMeta>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TexturePackerFrames {
+    Hash(serde_json::Map<String, serde_json::Value>),
+    Array(Vec<TexturePackerArrayFrame>),
+}
+
+#[derive(Deserialize)]
+struct TexturePackerArrayFrame {
+    filename: String,
+    #[serde(flatten)]
+    frame: TexturePackerFrame,
+}
+
+#[derive(Deserialize)]
+struct TexturePackerFrame {
+    frame: TexturePackerRect,
+    #[serde(default)]
+    rotated: bool,
+}
+
+#[derive(Deserialize)]
+struct TexturePackerRect {
+    x: i32,
+    y: i32,
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+struct TexturePackerMeta {
+    size: Option<TexturePackerSize>,
+}
+
+#[derive(Deserialize)]
+struct TexturePackerSize {
+    w: u32,
+    h: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Grid {
+    frame_width: u32,
+    frame_height: u32,
+    columns: u32,
+    rows: u32,
+    names: Option<Vec<String>>,
+    #[serde(default)]
+    margin: u32,
+    #[serde(default)]
+    spacing: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn texture_packer_hash() -> Result<(), String> {
+        let atlas = Atlas::from_texture_packer_json(
+            r#"{
+                "frames": {
+                    "walk-1": { "frame": { "x": 64, "y": 0, "w": 64, "h": 205 } },
+                    "walk-0": { "frame": { "x": 0, "y": 0, "w": 64, "h": 205 }, "rotated": false }
+                },
+                "meta": { "size": { "w": 128, "h": 205 } }
+            }"#,
+        )?;
+        assert_eq!(
+            atlas.frame_names().collect::<Vec<_>>(),
+            ["walk-1", "walk-0"]
+        );
+        assert_eq!(atlas.frame("walk-0"), Some(Rect::new(0, 0, 64, 205)));
+        assert_eq!(atlas.frame("walk-1"), Some(Rect::new(64, 0, 64, 205)));
+        assert_eq!(atlas.frame("walk-2"), None);
+        assert_eq!(atlas.size(), Some((128, 205)));
+        Ok(())
+    }
+
+    #[test]
+    fn texture_packer_array() -> Result<(), String> {
+        let atlas = Atlas::from_texture_packer_json(
+            r#"{
+                "frames": [
+                    { "filename": "idle", "frame": { "x": 0, "y": 0, "w": 32, "h": 32 } },
+                    { "filename": "jump", "frame": { "x": 32, "y": 0, "w": 32, "h": 48 } }
+                ]
+            }"#,
+        )?;
+        assert_eq!(
+            atlas.frames(),
+            [
+                Frame {
+                    name: "idle".to_string(),
+                    rect: Rect::new(0, 0, 32, 32)
+                },
+                Frame {
+                    name: "jump".to_string(),
+                    rect: Rect::new(32, 0, 32, 48)
+                },
+            ]
+        );
+        assert_eq!(atlas.size(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn toml_grid() -> Result<(), String> {
+        let atlas = Atlas::from_toml_grid(
+            r#"
+            frame_width = 10
+            frame_height = 20
+            columns = 3
+            rows = 2
+            margin = 1
+            spacing = 2
+            "#,
+        )?;
+        assert_eq!(atlas.len(), 6);
+        assert_eq!(atlas.frame("0"), Some(Rect::new(1, 1, 10, 20)));
+        assert_eq!(atlas.frame("2"), Some(Rect::new(25, 1, 10, 20)));
+        assert_eq!(atlas.frame("3"), Some(Rect::new(1, 23, 10, 20)));
+        assert_eq!(atlas.frame("6"), None);
+
+        let atlas = Atlas::from_toml_grid(
+            r#"
+            frame_width = 64
+            frame_height = 205
+            columns = 2
+            rows = 1
+            names = ["walk-0", "walk-1"]
+            "#,
+        )?;
+        assert_eq!(atlas.frame("walk-1"), Some(Rect::new(64, 0, 64, 205)));
+        assert_eq!(atlas.frame("1"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn validate() -> Result<(), String> {
+        let mut atlas = Atlas::new();
+        atlas.add_frame("inside", Rect::new(0, 0, 64, 64))?;
+        atlas.validate(64, 64)?;
+        atlas.add_frame("outside", Rect::new(32, 0, 64, 64))?;
+        assert_eq!(
+            atlas.validate(64, 64),
+            Err("frame \"outside\" at (32, 0) 64x64 exceeds the 64x64 texture".to_string())
+        );
+        assert_eq!(
+            atlas.add_frame("inside", Rect::new(0, 0, 1, 1)),
+            Err("duplicate frame \"inside\"".to_string())
+        );
+
+        // Descriptors that state the texture size are validated against it
+        let outside = Atlas::from_texture_packer_json(
+            r#"{
+                "frames": { "big": { "frame": { "x": 0, "y": 0, "w": 65, "h": 64 } } },
+                "meta": { "size": { "w": 64, "h": 64 } }
+            }"#,
+        );
+        assert!(outside.unwrap_err().contains("\"big\""));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_descriptors() {
+        for json in [
+            "{ \"frames\": ",
+            "{ \"meta\": {} }",
+            r#"{ "frames": { "a": { "frame": { "x": 0, "y": 0, "w": 1 } } } }"#,
+            r#"{ "frames": { "a": { "frame": { "x": 0, "y": 0, "w": 1, "h": 1 }, "rotated": true } } }"#,
+            r#"{ "frames": [
+                { "filename": "a", "frame": { "x": 0, "y": 0, "w": 1, "h": 1 } },
+                { "filename": "a", "frame": { "x": 1, "y": 0, "w": 1, "h": 1 } }
+            ] }"#,
+        ]
+        .iter()
+        {
+            assert!(Atlas::from_texture_packer_json(json).is_err(), "{}", json);
+        }
+
+        for toml in [
+            "frame_width = 10\nframe_height = 10\ncolumns = 2",
+            "frame_width = 0\nframe_height = 10\ncolumns = 1\nrows = 1",
+            "frame_width = 10\nframe_height = 10\ncolumns = 2\nrows = 1\nnames = [\"a\"]",
+            "frame_width = 10\nframe_height = 10\ncolumns = 1\nrows = 1\npadding = 1",
+            "frame_width = 10\nframe_height = 10\ncolumns = 2\nrows = 1\nnames = [\"a\", \"a\"]",
+            "frame_width = ",
+        ]
+        .iter()
+        {
+            assert!(Atlas::from_toml_grid(toml).is_err(), "{}", toml);
+        }
+    }
+}
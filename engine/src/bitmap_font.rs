@@ -0,0 +1,660 @@
+use crate::draw::DrawParams;
+use crate::error::{ensure_exists, Error};
+use crate::layout::TextLayout;
+use crate::sprite::{LoadOptions, Sprite};
+use crate::text_renderer::TextRenderer;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// Pixel font drawn from glyphs of one or more page images, so it stays crisp where TrueType
+// rendering would blur it.
+//
+// Loaded from AngelCode BMFont `.fnt` files, text or binary, or from a TOML descriptor of a
+// character sheet with equally sized cells:
+//
+//   image = "grid.png"
+//   cell_width = 18
+//   cell_height = 24
+//   chars = " !-.:0123456789?ABCDEFGHIJKLMNOPQRSTUVWXYZ"   # cell by cell, row by row
+//   line_height = 24                                       # optional, defaults to cell_height
+//
+// Glyphs are tinted via the color modulation of their page sprite, so pages should be white.
+// Characters the font doesn't have are skipped.
+pub struct BitmapFont<'a> {
+    pages: Vec<Sprite<'a>>,
+    glyphs: HashMap<char, BitmapGlyph>,
+    kerning: HashMap<(char, char), i32>,
+    line_height: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitmapGlyph {
+    pub page: usize,
+    // Part of the page showing the glyph, `None` for glyphs without pixels like space
+    pub rect: Option<Rect>,
+    // Where to draw the glyph relative to the pen
+    pub x_offset: i32,
+    pub y_offset: i32,
+    // How far to move the pen for the next glyph
+    pub advance: i32,
+}
+
+impl<'a> BitmapFont<'a> {
+    // Every char is a cell of the sheet, going row by row.
+    pub fn from_grid(
+        sheet: Sprite<'a>,
+        cell_width: u32,
+        cell_height: u32,
+        chars: &str,
+    ) -> Result<Self, Error> {
+        if cell_width == 0 || cell_height == 0 {
+            return Err(bitmap_font_error(
+                "cell_width and cell_height need to be larger than 0",
+            ));
+        }
+        let columns = sheet.width() / cell_width;
+        let cells = (columns * (sheet.height() / cell_height)) as usize;
+        let count = chars.chars().count();
+        if count > cells {
+            return Err(bitmap_font_error(format!(
+                "{} chars don't fit the {} cells of the {}x{} sheet",
+                count,
+                cells,
+                sheet.width(),
+                sheet.height()
+            )));
+        }
+
+        let mut glyphs = HashMap::new();
+        for (idx, ch) in chars.chars().enumerate() {
+            let col = idx as u32 % columns;
+            let row = idx as u32 / columns;
+            let rect = Rect::new(
+                (col * cell_width) as i32,
+                (row * cell_height) as i32,
+                cell_width,
+                cell_height,
+            );
+            let glyph = BitmapGlyph {
+                page: 0,
+                rect: Some(rect),
+                x_offset: 0,
+                y_offset: 0,
+                advance: cell_width as i32,
+            };
+            if glyphs.insert(ch, glyph).is_some() {
+                return Err(bitmap_font_error(format!("duplicate char {:?}", ch)));
+            }
+        }
+        Ok(BitmapFont {
+            pages: vec![sheet],
+            glyphs,
+            kerning: HashMap::new(),
+            line_height: cell_height as i32,
+        })
+    }
+
+    // Picks the format from the file extension, `.fnt` or `.toml`.
+    // Page images are resolved relative to the descriptor.
+    pub fn load(
+        path: &Path,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Error> {
+        ensure_exists(path)?;
+        let src = fs::read(path).map_err(|err| Error::io(path, err))?;
+        let with_path = |message| Error::BitmapFont {
+            path: Some(path.to_path_buf()),
+            message,
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let load_page = |file: &str| {
+            // BMFont pages and sheets come with alpha, so there is no color key to apply
+            let options = LoadOptions::new().detect_alpha(true);
+            Sprite::load_from_file_with_options(&dir.join(file), &options, texture_creator)
+        };
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("fnt") => {
+                let descriptor = if src.starts_with(b"BMF") {
+                    BmFont::from_binary(&src)
+                } else {
+                    String::from_utf8(src)
+                        .map_err(|err| err.to_string())
+                        .and_then(|src| BmFont::from_text(&src))
+                }
+                .map_err(with_path)?;
+                let pages = descriptor
+                    .pages
+                    .iter()
+                    .map(|file| load_page(file))
+                    .collect::<Result<Vec<_>, _>>()?;
+                BitmapFont::from_bmfont(descriptor, pages).map_err(with_path)
+            }
+            Some("toml") => {
+                let src = String::from_utf8(src).map_err(|err| with_path(err.to_string()))?;
+                let grid: GridFont =
+                    toml::from_str(&src).map_err(|err| with_path(err.to_string()))?;
+                let sheet = load_page(&grid.image)?;
+                let mut font =
+                    BitmapFont::from_grid(sheet, grid.cell_width, grid.cell_height, &grid.chars)
+                        .map_err(|err| match err {
+                            Error::BitmapFont {
+                                path: None,
+                                message,
+                            } => with_path(message),
+                            err => err,
+                        })?;
+                if let Some(line_height) = grid.line_height {
+                    font.line_height = line_height;
+                }
+                Ok(font)
+            }
+            _ => Err(with_path(
+                "unknown bitmap font format, expected .fnt or .toml".to_string(),
+            )),
+        }
+    }
+
+    fn from_bmfont(descriptor: BmFont, pages: Vec<Sprite<'a>>) -> Result<Self, String> {
+        let mut glyphs = HashMap::new();
+        for desc in descriptor.chars {
+            let ch = match char::from_u32(desc.id) {
+                Some(ch) => ch,
+                None => continue,
+            };
+            let page = pages
+                .get(desc.page)
+                .ok_or_else(|| format!("char {} refers to missing page {}", desc.id, desc.page))?;
+            let rect = if desc.width == 0 || desc.height == 0 {
+                None
+            } else {
+                let rect = Rect::new(desc.x, desc.y, desc.width, desc.height);
+                let inside = rect.x() >= 0
+                    && rect.y() >= 0
+                    && rect.right() <= page.width() as i32
+                    && rect.bottom() <= page.height() as i32;
+                if !inside {
+                    return Err(format!(
+                        "char {} exceeds the {}x{} page {}",
+                        desc.id,
+                        page.width(),
+                        page.height(),
+                        desc.page
+                    ));
+                }
+                Some(rect)
+            };
+            glyphs.insert(
+                ch,
+                BitmapGlyph {
+                    page: desc.page,
+                    rect,
+                    x_offset: desc.x_offset,
+                    y_offset: desc.y_offset,
+                    advance: desc.advance,
+                },
+            );
+        }
+
+        let kerning = descriptor
+            .kernings
+            .into_iter()
+            .filter_map(|(first, second, amount)| {
+                Some(((char::from_u32(first)?, char::from_u32(second)?), amount))
+            })
+            .collect();
+        Ok(BitmapFont {
+            pages,
+            glyphs,
+            kerning,
+            line_height: descriptor.line_height,
+        })
+    }
+
+    pub fn glyph(&self, ch: char) -> Option<&BitmapGlyph> {
+        self.glyphs.get(&ch)
+    }
+
+    // Adjustment of the pen between the two chars
+    pub fn kerning(&self, first: char, second: char) -> i32 {
+        self.kerning.get(&(first, second)).copied().unwrap_or(0)
+    }
+
+    pub fn pages(&self) -> &[Sprite<'a>] {
+        &self.pages
+    }
+}
+
+impl<'a> TextRenderer for BitmapFont<'a> {
+    fn font_height(&self) -> i32 {
+        self.line_height
+    }
+
+    fn line_height(&self) -> i32 {
+        self.line_height
+    }
+
+    fn text_width(&self, text: &str) -> Result<u32, Error> {
+        let mut width = 0;
+        let mut previous = None;
+        for ch in text.chars() {
+            if let Some(glyph) = self.glyphs.get(&ch) {
+                width += glyph.advance;
+                if let Some(previous) = previous {
+                    width += self.kerning(previous, ch);
+                }
+                previous = Some(ch);
+            }
+        }
+        Ok(width.max(0) as u32)
+    }
+
+    fn draw_layout(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        layout: &TextLayout,
+        color: Color,
+    ) -> Result<(), Error> {
+        let tint = Color::RGB(color.r, color.g, color.b);
+        for positioned in layout.glyphs() {
+            let glyph = match self.glyphs.get(&positioned.ch) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+            if let Some(rect) = glyph.rect {
+                let params = DrawParams::at(
+                    (positioned.rect.x() + glyph.x_offset) as f32,
+                    (positioned.rect.y() + glyph.y_offset) as f32,
+                )
+                .clip(rect)
+                .tint(tint)
+                .alpha(color.a);
+                self.pages[glyph.page].render_ex(canvas, &params)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn bitmap_font_error(message: impl ToString) -> Error {
+    Error::BitmapFont {
+        path: None,
+        message: message.to_string(),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GridFont {
+    image: String,
+    cell_width: u32,
+    cell_height: u32,
+    chars: String,
+    line_height: Option<i32>,
+}
+
+// What we need of a BMFont descriptor, see http://www.angelcode.com/products/bmfont/doc/file_format.html
+#[derive(Debug, Default)]
+struct BmFont {
+    line_height: i32,
+    pages: Vec<String>,
+    chars: Vec<BmChar>,
+    kernings: Vec<(u32, u32, i32)>,
+}
+
+#[derive(Debug)]
+struct BmChar {
+    id: u32,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    x_offset: i32,
+    y_offset: i32,
+    advance: i32,
+    page: usize,
+}
+
+impl BmFont {
+    // Lines of a tag followed by key=value pairs, values with spaces are quoted
+    fn from_text(src: &str) -> Result<Self, String> {
+        let mut font = BmFont::default();
+        for (line_idx, line) in src.lines().enumerate() {
+            let mut tokens = tokenize(line).into_iter();
+            let tag = match tokens.next() {
+                Some(tag) => tag,
+                None => continue,
+            };
+            let attributes = Attributes {
+                line: line_idx + 1,
+                values: tokens
+                    .filter_map(|token| {
+                        let mut parts = token.splitn(2, '=');
+                        Some((parts.next()?.to_string(), parts.next()?.to_string()))
+                    })
+                    .collect(),
+            };
+            match tag.as_str() {
+                "common" => font.line_height = attributes.number("lineHeight")?,
+                "page" => {
+                    let id: usize = attributes.number("id")?;
+                    if font.pages.len() <= id {
+                        font.pages.resize(id + 1, String::new());
+                    }
+                    font.pages[id] = attributes.get("file")?.to_string();
+                }
+                "char" => font.chars.push(BmChar {
+                    // -1 marks the glyph for missing chars, which becomes an invalid char
+                    id: attributes.number::<i64>("id")? as u32,
+                    x: attributes.number("x")?,
+                    y: attributes.number("y")?,
+                    width: attributes.number("width")?,
+                    height: attributes.number("height")?,
+                    x_offset: attributes.number("xoffset")?,
+                    y_offset: attributes.number("yoffset")?,
+                    advance: attributes.number("xadvance")?,
+                    page: attributes.number("page")?,
+                }),
+                "kerning" => font.kernings.push((
+                    attributes.number("first")?,
+                    attributes.number("second")?,
+                    attributes.number("amount")?,
+                )),
+                _ => {}
+            }
+        }
+        font.validate()?;
+        Ok(font)
+    }
+
+    // Version 3 of the binary format: "BMF", the version and blocks of a type byte, a u32 size
+    // and the data, all little endian
+    fn from_binary(src: &[u8]) -> Result<Self, String> {
+        match src.get(3) {
+            Some(3) => {}
+            Some(version) => return Err(format!("unsupported binary version {}", version)),
+            None => return Err("truncated header".to_string()),
+        }
+        let mut font = BmFont::default();
+        let mut rest = &src[4..];
+        while !rest.is_empty() {
+            if rest.len() < 5 {
+                return Err("truncated block header".to_string());
+            }
+            let block_type = rest[0];
+            let size = u32::from_le_bytes(bytes(&rest[1..5])) as usize;
+            let data = rest
+                .get(5..5 + size)
+                .ok_or_else(|| format!("truncated block {}", block_type))?;
+            rest = &rest[5 + size..];
+
+            match block_type {
+                2 => {
+                    let line_height = data.get(0..2).ok_or("truncated common block")?;
+                    font.line_height = u16::from_le_bytes(bytes(line_height)) as i32;
+                }
+                3 => {
+                    font.pages = data
+                        .split(|&byte| byte == 0)
+                        .filter(|name| !name.is_empty())
+                        .map(|name| String::from_utf8_lossy(name).into_owned())
+                        .collect();
+                }
+                4 => {
+                    for char_data in data.chunks(20) {
+                        if char_data.len() < 20 {
+                            return Err("truncated chars block".to_string());
+                        }
+                        let u16_at = |at: usize| u16::from_le_bytes(bytes(&char_data[at..]));
+                        let i16_at = |at: usize| i16::from_le_bytes(bytes(&char_data[at..]));
+                        font.chars.push(BmChar {
+                            id: u32::from_le_bytes(bytes(char_data)),
+                            x: u16_at(4) as i32,
+                            y: u16_at(6) as i32,
+                            width: u16_at(8) as u32,
+                            height: u16_at(10) as u32,
+                            x_offset: i16_at(12) as i32,
+                            y_offset: i16_at(14) as i32,
+                            advance: i16_at(16) as i32,
+                            page: char_data[18] as usize,
+                        });
+                    }
+                }
+                5 => {
+                    for pair in data.chunks(10) {
+                        if pair.len() < 10 {
+                            return Err("truncated kerning block".to_string());
+                        }
+                        font.kernings.push((
+                            u32::from_le_bytes(bytes(pair)),
+                            u32::from_le_bytes(bytes(&pair[4..])),
+                            i16::from_le_bytes(bytes(&pair[8..])) as i32,
+                        ));
+                    }
+                }
+                // Info block
+                1 => {}
+                _ => return Err(format!("unknown block type {}", block_type)),
+            }
+        }
+        font.validate()?;
+        Ok(font)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.line_height <= 0 {
+            return Err("missing or invalid line height".to_string());
+        }
+        if self.pages.is_empty() || self.pages.iter().any(String::is_empty) {
+            return Err("missing page files".to_string());
+        }
+        Ok(())
+    }
+}
+
+// Splits at spaces outside of quotes and drops the quotes
+fn tokenize(line: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut token = String::new();
+    let mut quoted = false;
+    for ch in line.chars() {
+        match ch {
+            '"' => quoted = !quoted,
+            ch if ch.is_whitespace() && !quoted => {
+                if !token.is_empty() {
+                    tokens.push(std::mem::take(&mut token));
+                }
+            }
+            ch => token.push(ch),
+        }
+    }
+    if !token.is_empty() {
+        tokens.push(token);
+    }
+    tokens
+}
+
+struct Attributes {
+    line: usize,
+    values: HashMap<String, String>,
+}
+
+impl Attributes {
+    fn get(&self, key: &str) -> Result<&str, String> {
+        self.values
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| format!("line {}: missing {}", self.line, key))
+    }
+
+    fn number<T: FromStr>(&self, key: &str) -> Result<T, String> {
+        let value = self.get(key)?;
+        value
+            .parse()
+            .map_err(|_| format!("line {}: invalid {} {:?}", self.line, key, value))
+    }
+}
+
+// First N bytes of the slice, callers make sure it is long enough
+fn bytes<const N: usize>(src: &[u8]) -> [u8; N] {
+    src[..N].try_into().expect("slice too short")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = r#"info face="Pixel Sans" size=21 charset="" unicode=1
+common lineHeight=27 base=21 scaleW=256 scaleH=128 pages=2
+
+page id=1 file="pixel 1.png"
+page id=0 file="pixel_0.png"
+chars count=3
+char id=-1   x=0     y=0     width=9     height=9     xoffset=0     yoffset=0     xadvance=9     page=0  chnl=15
+char id=65   x=10    y=0     width=14    height=21    xoffset=-1    yoffset=2     xadvance=15    page=1  chnl=15
+char id=86   x=30    y=0     width=14    height=21    xoffset=0     yoffset=2     xadvance=15    page=0  chnl=15
+kernings count=1
+kerning first=65  second=86  amount=-3
+"#;
+
+    fn block(block_type: u8, data: &[u8]) -> Vec<u8> {
+        let mut block = vec![block_type];
+        block.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        block.extend_from_slice(data);
+        block
+    }
+
+    fn char_data(id: u32, x: u16, advance: i16, page: u8) -> Vec<u8> {
+        let mut data = id.to_le_bytes().to_vec();
+        for value in &[x, 0, 14, 21] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in &[-1i16, 2, advance] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&[page, 15]);
+        data
+    }
+
+    fn binary(blocks: &[Vec<u8>]) -> Vec<u8> {
+        let mut src = b"BMF\x03".to_vec();
+        for block in blocks {
+            src.extend_from_slice(block);
+        }
+        src
+    }
+
+    fn binary_blocks() -> Vec<Vec<u8>> {
+        let mut kerning = 65u32.to_le_bytes().to_vec();
+        kerning.extend_from_slice(&86u32.to_le_bytes());
+        kerning.extend_from_slice(&(-3i16).to_le_bytes());
+        vec![
+            block(1, b"\x15\x00\x00\x00Pixel\x00"),
+            block(2, &[27, 0, 21, 0]),
+            block(3, b"pixel_0.png\x00pixel_1.png\x00"),
+            block(
+                4,
+                &[char_data(65, 10, 15, 1), char_data(86, 30, 15, 0)].concat(),
+            ),
+            block(5, &kerning),
+        ]
+    }
+
+    #[test]
+    fn text() {
+        let font = BmFont::from_text(TEXT).unwrap();
+        assert_eq!(font.line_height, 27);
+        assert_eq!(font.pages, ["pixel_0.png", "pixel 1.png"]);
+        let ids: Vec<_> = font.chars.iter().map(|ch| ch.id).collect();
+        assert_eq!(ids, [u32::MAX, 65, 86]);
+        let a = &font.chars[1];
+        assert_eq!((a.x, a.width, a.height), (10, 14, 21));
+        assert_eq!((a.x_offset, a.y_offset, a.advance, a.page), (-1, 2, 15, 1));
+        assert_eq!(font.kernings, [(65, 86, -3)]);
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize(r#"info  face="Pixel Sans" charset="" size=21"#),
+            ["info", "face=Pixel Sans", "charset=", "size=21"]
+        );
+        assert_eq!(tokenize("  "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn invalid_text() {
+        let without = |line: &str| TEXT.replace(line, "");
+        for (src, message) in &[
+            (
+                without("common lineHeight=27"),
+                "missing or invalid line height",
+            ),
+            (
+                without("page id=0 file=\"pixel_0.png\""),
+                "missing page files",
+            ),
+            (TEXT.replace("id=65", "id=A"), "line 8: invalid id \"A\""),
+            (TEXT.replace("xadvance=9 ", ""), "line 7: missing xadvance"),
+            (
+                TEXT.replace("amount=-3", "amount="),
+                "line 11: invalid amount \"\"",
+            ),
+        ] {
+            assert_eq!(BmFont::from_text(src).unwrap_err(), *message);
+        }
+    }
+
+    #[test]
+    fn binary_format() {
+        let font = BmFont::from_binary(&binary(&binary_blocks())).unwrap();
+        assert_eq!(font.line_height, 27);
+        assert_eq!(font.pages, ["pixel_0.png", "pixel_1.png"]);
+        let ids: Vec<_> = font.chars.iter().map(|ch| ch.id).collect();
+        assert_eq!(ids, [65, 86]);
+        let a = &font.chars[0];
+        assert_eq!((a.x, a.width, a.height), (10, 14, 21));
+        assert_eq!((a.x_offset, a.y_offset, a.advance, a.page), (-1, 2, 15, 1));
+        assert_eq!(font.kernings, [(65, 86, -3)]);
+    }
+
+    #[test]
+    fn invalid_binary() {
+        let blocks = binary_blocks();
+        let mut truncated = binary(&blocks);
+        truncated.truncate(truncated.len() - 1);
+        let mut short_chars = blocks.clone();
+        short_chars[3] = block(4, &char_data(65, 10, 15, 0)[..19]);
+        let mut short_kerning = blocks.clone();
+        short_kerning[4] = block(5, &[0; 9]);
+        let mut unknown = blocks.clone();
+        unknown.push(block(9, &[]));
+        let mut without_pages = blocks.clone();
+        without_pages.remove(2);
+
+        for (src, message) in &[
+            (b"BMF".to_vec(), "truncated header"),
+            (b"BMF\x02".to_vec(), "unsupported binary version 2"),
+            (truncated, "truncated block 5"),
+            (
+                [binary(&blocks), vec![2, 0]].concat(),
+                "truncated block header",
+            ),
+            (binary(&short_chars), "truncated chars block"),
+            (binary(&short_kerning), "truncated kerning block"),
+            (binary(&[block(2, &[27])]), "truncated common block"),
+            (binary(&unknown), "unknown block type 9"),
+            (binary(&without_pages), "missing page files"),
+        ] {
+            assert_eq!(BmFont::from_binary(src).unwrap_err(), *message);
+        }
+    }
+}
@@ -0,0 +1,468 @@
+use crate::draw::DrawParams;
+use crate::error::Error;
+use crate::sprite::Sprite;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::MouseButton;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// What a button currently shows
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ButtonState {
+    Normal,
+    Hovered,
+    // Held down with the mouse inside or via the keyboard while focused
+    Pressed,
+    // Clicked, until the mouse leaves or presses again
+    Released,
+    Disabled,
+}
+
+// Frames of the button's sprite atlas shown for each state.
+//
+// States without a frame of their own fall back to a similar one, released to hovered and all
+// others to normal. The focused frame replaces the normal and hovered ones while the button has
+// keyboard focus.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ButtonSkin {
+    normal: String,
+    frames: HashMap<ButtonState, String>,
+    focused: Option<String>,
+}
+
+impl ButtonSkin {
+    pub fn new(normal: &str) -> Self {
+        ButtonSkin {
+            normal: normal.to_string(),
+            frames: HashMap::new(),
+            focused: None,
+        }
+    }
+
+    pub fn frame(mut self, state: ButtonState, frame: &str) -> Self {
+        self.frames.insert(state, frame.to_string());
+        self
+    }
+
+    pub fn focused(mut self, frame: &str) -> Self {
+        self.focused = Some(frame.to_string());
+        self
+    }
+
+    // Name of the frame shown in the state
+    pub fn frame_name(&self, state: ButtonState, focused: bool) -> &str {
+        if let (true, ButtonState::Normal | ButtonState::Hovered, Some(frame)) =
+            (focused, state, &self.focused)
+        {
+            return frame;
+        }
+        let fallback = match state {
+            ButtonState::Released => ButtonState::Hovered,
+            _ => ButtonState::Normal,
+        };
+        self.frames
+            .get(&state)
+            .or_else(|| self.frames.get(&fallback))
+            .unwrap_or(&self.normal)
+    }
+}
+
+// A clickable button rendering the frame of its skin matching its state.
+//
+// Hover and presses are tracked from the events passed to `handle_event`, including the mouse
+// position they carry, so the button reflects each event as it is handled. A click is a left
+// press and release both inside the button, or enter or space released while it has keyboard
+// focus. Disabled buttons ignore all input.
+pub struct Button<'a> {
+    sprite: Rc<Sprite<'a>>,
+    skin: ButtonSkin,
+    input: ButtonInput,
+    on_click: Option<Box<dyn FnMut() + 'a>>,
+}
+
+impl<'a> Button<'a> {
+    pub fn new(sprite: Rc<Sprite<'a>>, skin: ButtonSkin, rect: Rect) -> Self {
+        Button {
+            sprite,
+            skin,
+            input: ButtonInput::new(rect),
+            on_click: None,
+        }
+    }
+
+    // Called on each click, in addition to `handle_event` returning true
+    pub fn on_click<F: FnMut() + 'a>(mut self, on_click: F) -> Self {
+        self.on_click = Some(Box::new(on_click));
+        self
+    }
+
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.set_disabled(disabled);
+        self
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.input.rect
+    }
+
+    pub fn set_rect(&mut self, rect: Rect) {
+        self.input.rect = rect;
+    }
+
+    pub fn skin(&self) -> &ButtonSkin {
+        &self.skin
+    }
+
+    pub fn set_skin(&mut self, skin: ButtonSkin) {
+        self.skin = skin;
+    }
+
+    pub fn state(&self) -> ButtonState {
+        self.input.state()
+    }
+
+    pub fn is_hovered(&self) -> bool {
+        self.input.hovered
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.input.disabled
+    }
+
+    // Disabling lets go of presses in progress and keyboard focus
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.input.set_disabled(disabled);
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.input.focused
+    }
+
+    // Disabled buttons can't be focused
+    pub fn focus(&mut self) {
+        self.input.focus();
+    }
+
+    pub fn blur(&mut self) {
+        self.input.blur();
+    }
+
+    // Updates the state with the event and returns true if it clicked the button
+    pub fn handle_event(&mut self, event: &Event) -> bool {
+        let clicked = self.input.handle_event(event);
+        if clicked {
+            if let Some(on_click) = self.on_click.as_mut() {
+                on_click();
+            }
+        }
+        clicked
+    }
+
+    // Renders the frame of the current state stretched to the button
+    pub fn render(&self, canvas: &mut WindowCanvas) -> Result<(), Error> {
+        let frame = self.skin.frame_name(self.state(), self.input.focused);
+        let clip = self.sprite.frame(frame)?;
+        let rect = self.input.rect;
+        let params = DrawParams::at(rect.x() as f32, rect.y() as f32)
+            .clip(clip)
+            .scale(
+                rect.width() as f32 / clip.width() as f32,
+                rect.height() as f32 / clip.height() as f32,
+            );
+        self.sprite.render_ex(canvas, &params)
+    }
+}
+
+// Hover, presses and focus of a button, tracked apart from its sprite
+struct ButtonInput {
+    rect: Rect,
+    hovered: bool,
+    // The left mouse button went down inside and wasn't released yet
+    pressed: bool,
+    released: bool,
+    // Key that went down while the button was focused, clicks once it is released
+    activation_key: Option<Keycode>,
+    focused: bool,
+    disabled: bool,
+}
+
+impl ButtonInput {
+    fn new(rect: Rect) -> Self {
+        ButtonInput {
+            rect,
+            hovered: false,
+            pressed: false,
+            released: false,
+            activation_key: None,
+            focused: false,
+            disabled: false,
+        }
+    }
+
+    fn state(&self) -> ButtonState {
+        if self.disabled {
+            ButtonState::Disabled
+        } else if (self.pressed && self.hovered) || self.activation_key.is_some() {
+            ButtonState::Pressed
+        } else if self.released && self.hovered {
+            ButtonState::Released
+        } else if self.hovered {
+            ButtonState::Hovered
+        } else {
+            ButtonState::Normal
+        }
+    }
+
+    fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+        if disabled {
+            self.pressed = false;
+            self.released = false;
+            self.blur();
+        }
+    }
+
+    fn focus(&mut self) {
+        self.focused = !self.disabled;
+    }
+
+    fn blur(&mut self) {
+        self.focused = false;
+        self.activation_key = None;
+    }
+
+    // Returns true if the event clicked the button
+    fn handle_event(&mut self, event: &Event) -> bool {
+        match event {
+            Event::MouseMotion { x, y, .. } => {
+                self.hover(*x, *y);
+                false
+            }
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => {
+                self.hover(*x, *y);
+                self.released = false;
+                if self.hovered && !self.disabled {
+                    self.pressed = true;
+                    self.focus();
+                } else {
+                    self.blur();
+                }
+                false
+            }
+            Event::MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => {
+                self.hover(*x, *y);
+                let clicked = self.pressed && self.hovered;
+                self.pressed = false;
+                self.released = clicked;
+                clicked
+            }
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat: false,
+                ..
+            } if self.focused && self.activation_key.is_none() && is_activation_key(*keycode) => {
+                self.activation_key = Some(*keycode);
+                false
+            }
+            Event::KeyUp {
+                keycode: Some(keycode),
+                ..
+            } if self.activation_key == Some(*keycode) => {
+                self.activation_key = None;
+                true
+            }
+            Event::Window { win_event, .. } => {
+                match win_event {
+                    WindowEvent::Leave => self.hovered = false,
+                    // The releases go to another window
+                    WindowEvent::FocusLost => {
+                        self.pressed = false;
+                        self.activation_key = None;
+                    }
+                    _ => {}
+                }
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn hover(&mut self, x: i32, y: i32) {
+        self.hovered = self.rect.contains_point((x, y));
+        if !self.hovered {
+            self.released = false;
+        }
+    }
+}
+
+fn is_activation_key(keycode: Keycode) -> bool {
+    matches!(keycode, Keycode::Return | Keycode::KpEnter | Keycode::Space)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthetic::{click, mouse_down, mouse_motion, mouse_up};
+    use sdl2::keyboard::Mod;
+
+    // Without a scancode, looking it up needs SDL's keymap
+    fn key(keycode: Keycode, down: bool) -> Event {
+        if down {
+            Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Some(keycode),
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: false,
+            }
+        } else {
+            Event::KeyUp {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Some(keycode),
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: false,
+            }
+        }
+    }
+
+    fn window(win_event: WindowEvent) -> Event {
+        Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event,
+        }
+    }
+
+    fn clicks(input: &mut ButtonInput, events: Vec<Event>) -> usize {
+        events
+            .iter()
+            .filter(|event| input.handle_event(event))
+            .count()
+    }
+
+    #[test]
+    fn skin() {
+        let skin = ButtonSkin::new("mouse-out")
+            .frame(ButtonState::Hovered, "mouse-over")
+            .frame(ButtonState::Pressed, "mouse-down")
+            .focused("mouse-up");
+        assert_eq!(skin.frame_name(ButtonState::Normal, false), "mouse-out");
+        assert_eq!(skin.frame_name(ButtonState::Released, false), "mouse-over");
+        assert_eq!(skin.frame_name(ButtonState::Disabled, false), "mouse-out");
+        assert_eq!(skin.frame_name(ButtonState::Hovered, true), "mouse-up");
+        assert_eq!(skin.frame_name(ButtonState::Pressed, true), "mouse-down");
+        assert_eq!(
+            ButtonSkin::new("normal").frame_name(ButtonState::Released, true),
+            "normal"
+        );
+    }
+
+    #[test]
+    fn hit_testing() {
+        let mut input = ButtonInput::new(Rect::new(100, 100, 50, 20));
+        for (x, y, hovered) in &[
+            (100, 100, true),
+            (149, 119, true),
+            (150, 110, false),
+            (110, 120, false),
+            (99, 110, false),
+        ] {
+            input.handle_event(&mouse_motion(*x, *y));
+            assert_eq!(input.hovered, *hovered, "{}, {}", x, y);
+        }
+        input.handle_event(&mouse_motion(110, 110));
+        input.handle_event(&window(WindowEvent::Leave));
+        assert_eq!(input.state(), ButtonState::Normal);
+    }
+
+    #[test]
+    fn mouse_clicks() {
+        let mut input = ButtonInput::new(Rect::new(100, 100, 50, 20));
+        let left = MouseButton::Left;
+        assert_eq!(clicks(&mut input, click(left, 110, 110)), 1);
+        assert_eq!(input.state(), ButtonState::Released);
+        // Dragging out before releasing, pressing outside and releasing inside, other buttons
+        assert_eq!(
+            clicks(
+                &mut input,
+                vec![
+                    mouse_down(left, 110, 110),
+                    mouse_motion(10, 10),
+                    mouse_up(left, 10, 10),
+                    mouse_down(left, 10, 10),
+                    mouse_up(left, 110, 110),
+                ]
+            ),
+            0
+        );
+        assert_eq!(clicks(&mut input, click(MouseButton::Right, 110, 110)), 0);
+        // Dragging out and back in before releasing still clicks
+        input.handle_event(&mouse_down(left, 110, 110));
+        assert_eq!(input.state(), ButtonState::Pressed);
+        input.handle_event(&mouse_motion(10, 10));
+        assert_eq!(input.state(), ButtonState::Normal);
+        input.handle_event(&mouse_motion(120, 110));
+        assert_eq!(input.state(), ButtonState::Pressed);
+        assert!(input.handle_event(&mouse_up(left, 120, 110)));
+
+        // Losing focus mid press lets go of it
+        input.handle_event(&mouse_down(left, 110, 110));
+        input.handle_event(&window(WindowEvent::FocusLost));
+        assert!(!input.handle_event(&mouse_up(left, 110, 110)));
+        assert_eq!(input.state(), ButtonState::Hovered);
+    }
+
+    #[test]
+    fn keyboard_focus() {
+        let mut input = ButtonInput::new(Rect::new(100, 100, 50, 20));
+        let left = MouseButton::Left;
+        assert!(!input.handle_event(&key(Keycode::Space, true)));
+        assert!(!input.handle_event(&key(Keycode::Space, false)));
+
+        // Clicking focused it, so enter and space activate it until clicking elsewhere
+        clicks(&mut input, click(left, 110, 110));
+        assert!(input.focused);
+        input.handle_event(&key(Keycode::Return, true));
+        assert_eq!(input.state(), ButtonState::Pressed);
+        // Only the key that went down first clicks
+        input.handle_event(&key(Keycode::Space, true));
+        assert!(!input.handle_event(&key(Keycode::Space, false)));
+        assert!(input.handle_event(&key(Keycode::Return, false)));
+        assert!(!input.handle_event(&key(Keycode::A, true)));
+        input.handle_event(&mouse_down(left, 10, 10));
+        assert!(!input.focused);
+        input.handle_event(&key(Keycode::Space, true));
+        assert!(!input.handle_event(&key(Keycode::Space, false)));
+    }
+
+    #[test]
+    fn disabled() {
+        let mut input = ButtonInput::new(Rect::new(100, 100, 50, 20));
+        input.handle_event(&mouse_down(MouseButton::Left, 110, 110));
+        input.set_disabled(true);
+        input.focus();
+        assert!(!input.focused);
+        for event in click(MouseButton::Left, 110, 110) {
+            assert!(!input.handle_event(&event));
+        }
+        assert_eq!(input.state(), ButtonState::Disabled);
+        input.set_disabled(false);
+        assert_eq!(input.state(), ButtonState::Hovered);
+    }
+}
@@ -0,0 +1,128 @@
+use crate::error::Error;
+use crate::sprite::{Clip, Sprite};
+use sdl2::mouse::{Cursor, MouseUtil, SystemCursor};
+use sdl2::render::WindowCanvas;
+use sdl2::surface::SurfaceRef;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Current {
+    System(SystemCursor),
+    Custom(String),
+}
+
+// The mouse cursor, switched between system cursors, i.e. a hand over buttons and an I-beam over
+// text inputs, and color cursors created from images.
+//
+// Cursors are created once and kept around since SDL only points at the one being shown.
+// Also hides and shows the cursor and turns relative mouse mode on and off, in which the cursor
+// is hidden and mouse motion keeps reporting relative movement at the edges of the window, i.e.
+// to drag a camera around.
+pub struct Cursors {
+    mouse: MouseUtil,
+    system: HashMap<SystemCursor, Cursor>,
+    custom: HashMap<String, Cursor>,
+    current: Option<Current>,
+}
+
+impl Cursors {
+    pub fn new(mouse: MouseUtil) -> Self {
+        Cursors {
+            mouse,
+            system: HashMap::new(),
+            custom: HashMap::new(),
+            current: None,
+        }
+    }
+
+    // Adds a color cursor showing the surface, with the hotspot being the pixel that points
+    pub fn add_surface<S: AsRef<SurfaceRef>>(
+        &mut self,
+        name: &str,
+        surface: S,
+        hot_x: i32,
+        hot_y: i32,
+    ) -> Result<(), Error> {
+        let cursor = Cursor::from_surface(surface, hot_x, hot_y)
+            .map_err(|err| Error::video("SDL_CreateColorCursor", err))?;
+        self.custom.insert(name.to_string(), cursor);
+        // Replacing the cursor being shown frees it, so it needs to be set again
+        if self.current == Some(Current::Custom(name.to_string())) {
+            self.current = None;
+            self.set(name)?;
+        }
+        Ok(())
+    }
+
+    // Adds a color cursor showing the clip of the sprite, i.e. a frame of its atlas, with the
+    // hotspot relative to the clip
+    pub fn add_sprite<'c, C: Into<Clip<'c>>>(
+        &mut self,
+        name: &str,
+        canvas: &mut WindowCanvas,
+        sprite: &Sprite,
+        clip: C,
+        hot_x: i32,
+        hot_y: i32,
+    ) -> Result<(), Error> {
+        let surface = sprite.to_surface(canvas, clip)?;
+        self.add_surface(name, surface, hot_x, hot_y)
+    }
+
+    // Shows the color cursor added with the name, fails with `Error::UnknownCursor` if there is none
+    pub fn set(&mut self, name: &str) -> Result<(), Error> {
+        let current = Current::Custom(name.to_string());
+        if self.current.as_ref() == Some(&current) {
+            return Ok(());
+        }
+        let cursor = self.custom.get(name).ok_or_else(|| Error::UnknownCursor {
+            name: name.to_string(),
+        })?;
+        cursor.set();
+        self.current = Some(current);
+        Ok(())
+    }
+
+    pub fn set_system(&mut self, system_cursor: SystemCursor) -> Result<(), Error> {
+        let current = Current::System(system_cursor);
+        if self.current.as_ref() == Some(&current) {
+            return Ok(());
+        }
+        let cursor = match self.system.entry(system_cursor) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(
+                Cursor::from_system(system_cursor)
+                    .map_err(|err| Error::video("SDL_CreateSystemCursor", err))?,
+            ),
+        };
+        cursor.set();
+        self.current = Some(current);
+        Ok(())
+    }
+
+    // Back to the arrow
+    pub fn reset(&mut self) -> Result<(), Error> {
+        self.set_system(SystemCursor::Arrow)
+    }
+
+    pub fn show(&self) {
+        self.mouse.show_cursor(true);
+    }
+
+    pub fn hide(&self) {
+        self.mouse.show_cursor(false);
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.mouse.is_cursor_showing()
+    }
+
+    pub fn set_relative_mouse_mode(&self, relative: bool) {
+        self.mouse.set_relative_mouse_mode(relative);
+    }
+
+    pub fn is_relative_mouse_mode(&self) -> bool {
+        self.mouse.relative_mouse_mode()
+    }
+}
@@ -0,0 +1,111 @@
+use crate::sprite::{Clip, Flip};
+use sdl2::pixels::Color;
+
+// How a sprite is drawn, built up from `DrawParams::at` and passed to `Sprite::render_ex`:
+//
+//   let params = DrawParams::at(320.0, 240.0)
+//       .clip("walk-0")
+//       .origin(0.5, 0.5)
+//       .rotation_degrees(45.0)
+//       .scale(2.0, 2.0)
+//       .flip(Flip::Horizontal)
+//       .alpha(128);
+//
+// The position is where the origin of the sprite ends up, and rotation and scale happen around
+// it. The origin is a fraction of the (clipped) sprite size, `(0.0, 0.0)` being its top left
+// and `(0.5, 0.5)` its center. Coordinates are floats so sprites can move by sub-pixels.
+//
+// Tint and alpha only apply to this draw, the texture's own color and alpha mod are restored
+// afterwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawParams<'c> {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) clip: Clip<'c>,
+    pub(crate) scale_x: f32,
+    pub(crate) scale_y: f32,
+    pub(crate) degrees: f64,
+    pub(crate) origin_x: f32,
+    pub(crate) origin_y: f32,
+    pub(crate) flip: Flip,
+    pub(crate) tint: Option<Color>,
+    pub(crate) alpha: Option<u8>,
+}
+
+impl<'c> Default for DrawParams<'c> {
+    fn default() -> Self {
+        DrawParams::at(0.0, 0.0)
+    }
+}
+
+impl<'c> DrawParams<'c> {
+    pub fn at(x: f32, y: f32) -> Self {
+        DrawParams {
+            x,
+            y,
+            clip: Clip::Full,
+            scale_x: 1.0,
+            scale_y: 1.0,
+            degrees: 0.0,
+            origin_x: 0.0,
+            origin_y: 0.0,
+            flip: Flip::None,
+            tint: None,
+            alpha: None,
+        }
+    }
+
+    pub fn position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    pub fn clip<C: Into<Clip<'c>>>(mut self, clip: C) -> Self {
+        self.clip = clip.into();
+        self
+    }
+
+    // Negative factors are clamped to 0, use `flip` to mirror the sprite.
+    pub fn scale(mut self, x: f32, y: f32) -> Self {
+        self.scale_x = x.max(0.0);
+        self.scale_y = y.max(0.0);
+        self
+    }
+
+    pub fn uniform_scale(self, factor: f32) -> Self {
+        self.scale(factor, factor)
+    }
+
+    // Clockwise, around the origin
+    pub fn rotation_degrees(mut self, degrees: f64) -> Self {
+        self.degrees = degrees;
+        self
+    }
+
+    pub fn rotation_radians(self, radians: f64) -> Self {
+        self.rotation_degrees(radians.to_degrees())
+    }
+
+    pub fn origin(mut self, x: f32, y: f32) -> Self {
+        self.origin_x = x;
+        self.origin_y = y;
+        self
+    }
+
+    pub fn flip(mut self, flip: Flip) -> Self {
+        self.flip = flip;
+        self
+    }
+
+    // Multiplied with the sprite's colors, white leaves them as they are
+    pub fn tint(mut self, color: Color) -> Self {
+        self.tint = Some(color);
+        self
+    }
+
+    pub fn alpha(mut self, alpha: u8) -> Self {
+        self.alpha = Some(alpha);
+        self
+    }
+}
@@ -0,0 +1,323 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Everything that can go wrong inside the engine.
+// Variants carry the SDL call that failed or the asset involved so callers can match on them and
+// recover, i.e. retry with a software renderer when the accelerated one isn't available.
+#[derive(Debug)]
+pub enum Error {
+    // Initializing SDL itself or one of its subsystems failed
+    SdlInit {
+        call: &'static str,
+        message: String,
+    },
+    // Creating the window or renderer, or drawing with it failed
+    Video {
+        call: &'static str,
+        message: String,
+    },
+    TextureCreation {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    ImageDecode {
+        path: PathBuf,
+        message: String,
+    },
+    Ttf {
+        call: &'static str,
+        path: Option<PathBuf>,
+        message: String,
+    },
+    AudioDevice {
+        call: &'static str,
+        message: String,
+    },
+    // Loading a sound effect or music via SDL_mixer failed
+    Mixer {
+        call: &'static str,
+        path: Option<PathBuf>,
+        message: String,
+    },
+    AudioConversion {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    MissingAsset {
+        path: PathBuf,
+    },
+    // Invalid sprite sheet descriptor, unknown frame or frame outside of the texture
+    Atlas {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    // Invalid BMFont or grid font descriptor
+    BitmapFont {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    // Opening a gamepad or adding gamepad mappings failed
+    Gamepad {
+        call: &'static str,
+        message: String,
+    },
+    // Invalid input bindings file or binding
+    Bindings {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    // Invalid input recording
+    Recording {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    // Showing a cursor that wasn't added to `Cursors`
+    UnknownCursor {
+        name: String,
+    },
+    // A glyph cache can't hold the glyph, or the text drawn needs more glyphs than it holds
+    GlyphCache {
+        ch: Option<char>,
+        message: String,
+    },
+    // Rich text with unknown, unbalanced or malformed tags, at the byte offset of the tag
+    Markup {
+        position: usize,
+        message: String,
+    },
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    // Invalid or missing value of an env var or flag
+    Config {
+        key: String,
+        value: Option<String>,
+        message: String,
+    },
+}
+
+impl Error {
+    pub fn sdl_init(call: &'static str, message: impl ToString) -> Self {
+        Error::SdlInit {
+            call,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn video(call: &'static str, message: impl ToString) -> Self {
+        Error::Video {
+            call,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn texture_creation(path: Option<&Path>, message: impl ToString) -> Self {
+        Error::TextureCreation {
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn image_decode(path: &Path, message: impl ToString) -> Self {
+        Error::ImageDecode {
+            path: path.to_path_buf(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn ttf(call: &'static str, path: Option<&Path>, message: impl ToString) -> Self {
+        Error::Ttf {
+            call,
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn audio_device(call: &'static str, message: impl ToString) -> Self {
+        Error::AudioDevice {
+            call,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn mixer(call: &'static str, path: Option<&Path>, message: impl ToString) -> Self {
+        Error::Mixer {
+            call,
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn audio_conversion(path: Option<&Path>, message: impl ToString) -> Self {
+        Error::AudioConversion {
+            path: path.map(Path::to_path_buf),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn gamepad(call: &'static str, message: impl ToString) -> Self {
+        Error::Gamepad {
+            call,
+            message: message.to_string(),
+        }
+    }
+
+    pub fn config(key: &str, value: Option<&str>, message: impl ToString) -> Self {
+        Error::Config {
+            key: key.to_string(),
+            value: value.map(str::to_string),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn io(path: &Path, source: io::Error) -> Self {
+        Error::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    // The asset this error is about, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::TextureCreation { path, .. }
+            | Error::Ttf { path, .. }
+            | Error::Mixer { path, .. }
+            | Error::AudioConversion { path, .. }
+            | Error::Atlas { path, .. }
+            | Error::BitmapFont { path, .. }
+            | Error::Bindings { path, .. }
+            | Error::Recording { path, .. } => path.as_deref(),
+            Error::ImageDecode { path, .. }
+            | Error::MissingAsset { path }
+            | Error::Io { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::SdlInit { call, message } => write!(f, "{} failed: {}", call, message),
+            Error::Video { call, message } => write!(f, "{} failed: {}", call, message),
+            Error::TextureCreation {
+                path: Some(path),
+                message,
+            } => write!(f, "unable to create texture from {:?}: {}", path, message),
+            Error::TextureCreation {
+                path: None,
+                message,
+            } => write!(f, "unable to create texture: {}", message),
+            Error::ImageDecode { path, message } => {
+                write!(f, "unable to decode image {:?}: {}", path, message)
+            }
+            Error::Ttf {
+                call,
+                path: Some(path),
+                message,
+            } => write!(f, "{} failed for {:?}: {}", call, path, message),
+            Error::Ttf {
+                call,
+                path: None,
+                message,
+            } => write!(f, "{} failed: {}", call, message),
+            Error::AudioDevice { call, message } => write!(f, "{} failed: {}", call, message),
+            Error::Mixer {
+                call,
+                path: Some(path),
+                message,
+            } => write!(f, "{} failed for {:?}: {}", call, path, message),
+            Error::Mixer {
+                call,
+                path: None,
+                message,
+            } => write!(f, "{} failed: {}", call, message),
+            Error::AudioConversion {
+                path: Some(path),
+                message,
+            } => write!(f, "unable to convert audio {:?}: {}", path, message),
+            Error::AudioConversion {
+                path: None,
+                message,
+            } => write!(f, "unable to convert audio: {}", message),
+            Error::MissingAsset { path } => write!(f, "missing asset {:?}", path),
+            Error::Atlas {
+                path: Some(path),
+                message,
+            } => write!(f, "invalid atlas {:?}: {}", path, message),
+            Error::Atlas {
+                path: None,
+                message,
+            } => write!(f, "invalid atlas: {}", message),
+            Error::BitmapFont {
+                path: Some(path),
+                message,
+            } => write!(f, "invalid bitmap font {:?}: {}", path, message),
+            Error::BitmapFont {
+                path: None,
+                message,
+            } => write!(f, "invalid bitmap font: {}", message),
+            Error::Gamepad { call, message } => write!(f, "{} failed: {}", call, message),
+            Error::Bindings {
+                path: Some(path),
+                message,
+            } => write!(f, "invalid bindings {:?}: {}", path, message),
+            Error::Bindings {
+                path: None,
+                message,
+            } => write!(f, "invalid bindings: {}", message),
+            Error::Recording {
+                path: Some(path),
+                message,
+            } => write!(f, "invalid recording {:?}: {}", path, message),
+            Error::Recording {
+                path: None,
+                message,
+            } => write!(f, "invalid recording: {}", message),
+            Error::UnknownCursor { name } => write!(f, "unknown cursor {:?}", name),
+            Error::GlyphCache {
+                ch: Some(ch),
+                message,
+            } => write!(f, "glyph cache can't hold {:?}: {}", ch, message),
+            Error::GlyphCache { ch: None, message } => write!(f, "glyph cache: {}", message),
+            Error::Markup { position, message } => {
+                write!(f, "invalid markup at {}: {}", position, message)
+            }
+            Error::Io { path, source } => write!(f, "{:?}: {}", path, source),
+            Error::Config {
+                key,
+                value: Some(value),
+                message,
+            } => write!(f, "invalid value {:?} for {}, {}", value, key, message),
+            Error::Config {
+                key,
+                value: None,
+                message,
+            } => write!(f, "{}: {}", key, message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// SDL reports a missing file the same way as a corrupt one, so we check first to tell them apart
+pub(crate) fn ensure_exists(path: &Path) -> Result<(), Error> {
+    if path.exists() {
+        Ok(())
+    } else {
+        Err(Error::MissingAsset {
+            path: path.to_path_buf(),
+        })
+    }
+}
@@ -0,0 +1,221 @@
+use crate::recording::{RecordedFrame, Recording};
+use sdl2::event::Event;
+use sdl2::EventPump;
+
+// Where the `GameLoop` takes each frame's events from, live input by default.
+pub trait EventSource {
+    // Events of the next frame
+    fn poll(&mut self, event_pump: &mut EventPump) -> Vec<Event>;
+
+    // Number of fixed updates the frame runs, `None` to catch up with the elapsed time
+    fn updates(&self) -> Option<u32> {
+        None
+    }
+
+    // Called with the number of updates the frame ran once it was rendered
+    fn frame_done(&mut self, _updates: u32) {}
+
+    // Ends the loop, i.e. once a replay ran out of frames
+    fn is_done(&self) -> bool {
+        false
+    }
+}
+
+impl<S: EventSource + ?Sized> EventSource for Box<S> {
+    fn poll(&mut self, event_pump: &mut EventPump) -> Vec<Event> {
+        (**self).poll(event_pump)
+    }
+
+    fn updates(&self) -> Option<u32> {
+        (**self).updates()
+    }
+
+    fn frame_done(&mut self, updates: u32) {
+        (**self).frame_done(updates)
+    }
+
+    fn is_done(&self) -> bool {
+        (**self).is_done()
+    }
+}
+
+// Events as they come in from SDL
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LiveEvents;
+
+impl EventSource for LiveEvents {
+    fn poll(&mut self, event_pump: &mut EventPump) -> Vec<Event> {
+        event_pump.poll_iter().collect()
+    }
+}
+
+// Passes the events of another source on while recording them along with the number of updates
+// each frame ran
+pub struct Recorder<S> {
+    source: S,
+    recording: Recording,
+    frame: u64,
+}
+
+impl<S: EventSource> Recorder<S> {
+    pub fn new(source: S) -> Self {
+        Recorder {
+            source,
+            recording: Recording::new(),
+            frame: 0,
+        }
+    }
+
+    pub fn recording(&self) -> &Recording {
+        &self.recording
+    }
+
+    pub fn into_recording(self) -> Recording {
+        self.recording
+    }
+}
+
+impl<S: EventSource> EventSource for Recorder<S> {
+    fn poll(&mut self, event_pump: &mut EventPump) -> Vec<Event> {
+        let events = self.source.poll(event_pump);
+        // The updates are filled in once the frame is done, a frame that quits the loop never is
+        self.recording.push(RecordedFrame {
+            frame: self.frame,
+            updates: 0,
+            events: events
+                .iter()
+                .filter(|event| Recording::is_recorded(event))
+                .cloned()
+                .collect(),
+        });
+        events
+    }
+
+    fn updates(&self) -> Option<u32> {
+        self.source.updates()
+    }
+
+    fn frame_done(&mut self, updates: u32) {
+        self.source.frame_done(updates);
+        self.recording.finish_frame(updates);
+        self.frame += 1;
+    }
+
+    fn is_done(&self) -> bool {
+        self.source.is_done()
+    }
+}
+
+// Plays a recording back instead of live input, running the same number of updates per frame as
+// the recorded run so the game ends up in the same state independent of the frame rate.
+//
+// Live input is dropped except for quitting, so the window can still be closed. Keyboard and
+// mouse state queried from the event pump reflect live input, so games being replayed should
+// use the `InputState` instead.
+pub struct Replay {
+    recording: Recording,
+    frame: u64,
+    // Index of the next recorded frame
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Replay {
+            recording,
+            frame: 0,
+            next: 0,
+        }
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    fn current(&self) -> Option<&RecordedFrame> {
+        self.recording
+            .frames()
+            .get(self.next)
+            .filter(|recorded| recorded.frame == self.frame)
+    }
+}
+
+impl EventSource for Replay {
+    fn poll(&mut self, event_pump: &mut EventPump) -> Vec<Event> {
+        let mut events: Vec<Event> = event_pump
+            .poll_iter()
+            .filter(|event| matches!(event, Event::Quit { .. }))
+            .collect();
+        if let Some(recorded) = self.current() {
+            events.extend(recorded.events.iter().cloned());
+        }
+        events
+    }
+
+    fn updates(&self) -> Option<u32> {
+        Some(self.current().map_or(1, |recorded| recorded.updates))
+    }
+
+    fn frame_done(&mut self, _updates: u32) {
+        if self.current().is_some() {
+            self.next += 1;
+        }
+        self.frame += 1;
+    }
+
+    fn is_done(&self) -> bool {
+        self.frame >= self.recording.frame_count()
+    }
+}
+
+// Hands the loop a script of events instead of live input, one update per frame, so tests can
+// drive a game without a human at the keyboard. See `synthetic` for events to script.
+//
+// Live input is drained and dropped so it can't interfere, and the loop ends once the script
+// ran out of frames.
+#[derive(Clone, Debug, Default)]
+pub struct ScriptedEvents {
+    frames: Vec<Vec<Event>>,
+    frame: usize,
+}
+
+impl ScriptedEvents {
+    pub fn new() -> Self {
+        ScriptedEvents::default()
+    }
+
+    // Adds a frame handling the events
+    pub fn frame<I: IntoIterator<Item = Event>>(mut self, events: I) -> Self {
+        self.frames.push(events.into_iter().collect());
+        self
+    }
+
+    // Adds frames without events, i.e. to let the game update for a while
+    pub fn idle(mut self, frames: usize) -> Self {
+        self.frames.extend((0..frames).map(|_| Vec::new()));
+        self
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+impl EventSource for ScriptedEvents {
+    fn poll(&mut self, event_pump: &mut EventPump) -> Vec<Event> {
+        event_pump.poll_iter().for_each(drop);
+        self.frames.get(self.frame).cloned().unwrap_or_default()
+    }
+
+    fn updates(&self) -> Option<u32> {
+        Some(1)
+    }
+
+    fn frame_done(&mut self, _updates: u32) {
+        self.frame += 1;
+    }
+
+    fn is_done(&self) -> bool {
+        self.frame >= self.frames.len()
+    }
+}
@@ -0,0 +1,158 @@
+use crate::error::Error;
+use crate::sprite::Sprite;
+use crate::text::TextMode;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, TextureCreator};
+use sdl2::surface::Surface;
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use std::ops::Range;
+use std::rc::Rc;
+
+// Part of a text rendered with one font of the stack
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontRun {
+    // Index into the fonts of the stack
+    pub font: usize,
+    // Byte range into the text
+    pub range: Range<usize>,
+}
+
+// Ordered list of fonts where each character is rendered with the first font that has a glyph
+// for it, i.e. accents, symbols or CJK missing from the primary font come from a fallback font.
+//
+// Characters none of the fonts provide are rendered with the primary font, as are characters
+// outside of the Basic Multilingual Plane since SDL_ttf can't check those.
+// Renders a single line, the runs of each font share a common baseline.
+pub struct FontStack<'a> {
+    fonts: Vec<Rc<Font<'a, 'static>>>,
+}
+
+impl<'a> FontStack<'a> {
+    pub fn new(primary: Rc<Font<'a, 'static>>) -> Self {
+        FontStack {
+            fonts: vec![primary],
+        }
+    }
+
+    // Adds a font used for characters none of the fonts added before provide
+    pub fn fallback(mut self, font: Rc<Font<'a, 'static>>) -> Self {
+        self.fonts.push(font);
+        self
+    }
+
+    pub fn fonts(&self) -> &[Rc<Font<'a, 'static>>] {
+        &self.fonts
+    }
+
+    // Index of the first font providing the character
+    pub fn font_for(&self, ch: char) -> usize {
+        self.fonts
+            .iter()
+            .position(|font| provides(font, ch))
+            .unwrap_or(0)
+    }
+
+    // Splits the text where the font needed for its characters changes
+    pub fn runs(&self, text: &str) -> Vec<FontRun> {
+        let mut runs: Vec<FontRun> = Vec::new();
+        for (idx, ch) in text.char_indices() {
+            let font = self.font_for(ch);
+            let end = idx + ch.len_utf8();
+            match runs.last_mut() {
+                // Whitespace stays with the current run if its font has it, which keeps runs long
+                Some(run)
+                    if run.font == font
+                        || (ch.is_whitespace() && provides(&self.fonts[run.font], ch)) =>
+                {
+                    run.range.end = end
+                }
+                _ => runs.push(FontRun {
+                    font,
+                    range: idx..end,
+                }),
+            }
+        }
+        runs
+    }
+
+    // Width and height of the text as rendered by `render`
+    pub fn size_of(&self, text: &str) -> Result<(u32, u32), Error> {
+        let runs = self.runs(text);
+        let mut width = 0;
+        for run in &runs {
+            width += self.fonts[run.font]
+                .size_of(&text[run.range.clone()])
+                .map(|(width, _)| width)
+                .map_err(|err| Error::ttf("TTF_SizeUTF8", None, err))?;
+        }
+        let baseline = self.baseline(&runs);
+        let height = self
+            .used_fonts(&runs)
+            .map(|font| baseline - font.ascent() + font.height())
+            .max()
+            .unwrap_or(0);
+        Ok((width, height.max(0) as u32))
+    }
+
+    pub fn render_surface(&self, text: &str, color: Color) -> Result<Surface<'static>, Error> {
+        let runs = self.runs(text);
+        let baseline = self.baseline(&runs);
+        let mut rendered = Vec::with_capacity(runs.len());
+        let (mut width, mut height) = (0, 0);
+        for run in &runs {
+            let font = &self.fonts[run.font];
+            let mut surface = TextMode::Blended.render(&text[run.range.clone()], color, font)?;
+            // Copies the alpha of the glyphs as is, blending them onto the transparent surface
+            // would darken their edges
+            surface
+                .set_blend_mode(BlendMode::None)
+                .map_err(|err| Error::video("SDL_SetSurfaceBlendMode", err))?;
+            let y = baseline - font.ascent();
+            width += surface.width();
+            height = height.max(y + surface.height() as i32);
+            rendered.push((surface, y));
+        }
+
+        let mut target = Surface::new(width.max(1), height.max(1) as u32, PixelFormatEnum::RGBA32)
+            .map_err(|err| Error::texture_creation(None, err))?;
+        let mut x = 0;
+        for (surface, y) in rendered {
+            let rect = Rect::new(x, y, surface.width(), surface.height());
+            surface
+                .blit(None, &mut target, rect)
+                .map_err(|err| Error::video("SDL_BlitSurface", err))?;
+            x += surface.width() as i32;
+        }
+        Ok(target)
+    }
+
+    pub fn render<'t>(
+        &self,
+        text: &str,
+        color: Color,
+        texture_creator: &'t TextureCreator<WindowContext>,
+    ) -> Result<Sprite<'t>, Error> {
+        Sprite::from_surface(self.render_surface(text, color)?, texture_creator)
+    }
+
+    fn used_fonts<'r>(
+        &'r self,
+        runs: &'r [FontRun],
+    ) -> impl Iterator<Item = &'r Rc<Font<'a, 'static>>> {
+        runs.iter().map(move |run| &self.fonts[run.font])
+    }
+
+    // Distance from the top to the common baseline, the ascent of the tallest font used
+    fn baseline(&self, runs: &[FontRun]) -> i32 {
+        self.used_fonts(runs)
+            .map(|font| font.ascent())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn provides(font: &Font, ch: char) -> bool {
+    ch <= '\u{ffff}' && font.find_glyph(ch).is_some()
+}
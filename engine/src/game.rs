@@ -0,0 +1,314 @@
+use crate::error::Error;
+use crate::event_source::{EventSource, LiveEvents, Recorder, Replay};
+use crate::gamepad::Gamepads;
+use crate::input_state::InputState;
+use crate::recording::Recording;
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use sdl2::render::WindowCanvas;
+use sdl2::{EventPump, Sdl};
+use std::cmp::min;
+use std::env;
+use std::error;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const ENV_MAX_FRAMES: &str = "AMA_SDL_MAX_FRAMES";
+const ENV_RECORD: &str = "AMA_SDL_RECORD";
+const ENV_REPLAY: &str = "AMA_SDL_REPLAY";
+
+// Hooks the GameLoop calls into on each frame.
+pub trait Game {
+    // Called for each event except the ones that quit the loop.
+    // The event pump is passed along to query keyboard and mouse state after events were pumped.
+    fn handle_event(&mut self, _event: &Event, _event_pump: &EventPump) {}
+
+    // Called zero or more times per frame, always with the same fixed timestep.
+    fn update(
+        &mut self,
+        _dt: Duration,
+        _event_pump: &EventPump,
+    ) -> Result<(), Box<dyn error::Error>> {
+        Ok(())
+    }
+
+    // Called once per frame after the canvas was cleared and before it is presented.
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn error::Error>>;
+
+    // Lets the game end the loop, i.e. when the user picked "Quit" from a menu.
+    fn is_done(&self) -> bool {
+        false
+    }
+
+    // Input state the loop feeds with each frame's events before they are handled.
+    fn input_state(&mut self) -> Option<&mut InputState> {
+        None
+    }
+
+    // Gamepads the loop opens and closes as they are plugged in and out. Their axis motion is
+    // passed on to the input state and the game with dead zones applied.
+    fn gamepads(&mut self) -> Option<&mut Gamepads> {
+        None
+    }
+
+    // Called when a gamepad that was plugged in fails to open. The loop keeps running either way,
+    // the remaining players keep playing.
+    fn gamepad_error(&mut self, _err: Error) {}
+}
+
+// Color the game loop clears the canvas with unless configured otherwise
+pub(crate) const DEFAULT_CLEAR_COLOR: Color = Color::RGBA(0xff, 0xff, 0xff, 0xff);
+
+// Drives a Game with a fixed-timestep accumulator loop.
+// Rendering happens once per frame while updates catch up with the elapsed time in fixed steps,
+// so game logic behaves the same independent of the frame rate.
+// https://gafferongames.com/post/fix_your_timestep/
+#[derive(Clone, Debug)]
+pub struct GameLoop {
+    timestep: Duration,
+    max_frame_time: Duration,
+    frame_cap: Option<u32>,
+    max_frames: Option<u64>,
+    lockstep: bool,
+    quit_on_escape: bool,
+    clear_color: Option<Color>,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+}
+
+impl Default for GameLoop {
+    fn default() -> Self {
+        GameLoop::new()
+    }
+}
+
+impl GameLoop {
+    pub fn new() -> Self {
+        GameLoop {
+            timestep: Duration::from_secs(1) / 60,
+            max_frame_time: Duration::from_millis(250),
+            frame_cap: Some(60),
+            max_frames: None,
+            lockstep: false,
+            quit_on_escape: true,
+            clear_color: Some(DEFAULT_CLEAR_COLOR),
+            record: None,
+            replay: None,
+        }
+    }
+
+    pub fn timestep(mut self, timestep: Duration) -> Self {
+        self.timestep = timestep;
+        self
+    }
+
+    pub fn updates_per_second(self, updates: u32) -> Self {
+        self.timestep(Duration::from_secs(1) / updates)
+    }
+
+    // Upper bound of time we catch up on after a slow frame, i.e. after the window was dragged,
+    // so we don't spiral into running more and more updates per frame.
+    pub fn max_frame_time(mut self, max_frame_time: Duration) -> Self {
+        self.max_frame_time = max_frame_time;
+        self
+    }
+
+    // Frames per second we sleep down to when the canvas doesn't present with vsync.
+    // `None` renders as fast as possible.
+    pub fn frame_cap(mut self, fps: Option<u32>) -> Self {
+        self.frame_cap = fps;
+        self
+    }
+
+    // Stops the loop after the given number of frames, i.e. for tests.
+    pub fn max_frames(mut self, frames: u64) -> Self {
+        self.max_frames = Some(frames);
+        self
+    }
+
+    // Runs exactly one update per frame independent of the elapsed time which makes runs
+    // deterministic, i.e. for tests.
+    pub fn lockstep(mut self, lockstep: bool) -> Self {
+        self.lockstep = lockstep;
+        self
+    }
+
+    pub fn quit_on_escape(mut self, quit_on_escape: bool) -> Self {
+        self.quit_on_escape = quit_on_escape;
+        self
+    }
+
+    // Color the canvas is cleared with before each render, `None` to not clear at all.
+    pub fn clear_color(mut self, color: Option<Color>) -> Self {
+        self.clear_color = color;
+        self
+    }
+
+    // Records the input of the run to the file once the loop ends, see `Recording`.
+    pub fn record<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.record = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    // Drives the game with the input recorded to the file instead of live input and ends the
+    // loop once the recording does.
+    pub fn replay<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.replay = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    // Applies `AMA_SDL_MAX_FRAMES` so binaries can be run to completion on CI, as well as
+    // `AMA_SDL_RECORD` and `AMA_SDL_REPLAY` to record input to or replay it from a file.
+    pub fn with_env(mut self) -> Result<Self, Error> {
+        if let Ok(frames) = env::var(ENV_MAX_FRAMES) {
+            let frames = frames.parse().map_err(|_| {
+                Error::config(ENV_MAX_FRAMES, Some(&frames), "expected a frame count")
+            })?;
+            self = self.max_frames(frames);
+        }
+        if let Ok(path) = env::var(ENV_RECORD) {
+            self = self.record(path);
+        }
+        if let Ok(path) = env::var(ENV_REPLAY) {
+            self = self.replay(path);
+        }
+        Ok(self)
+    }
+
+    // Returns the number of frames that were rendered.
+    pub fn run<G: Game>(
+        &self,
+        sdl_context: &Sdl,
+        canvas: &mut WindowCanvas,
+        game: &mut G,
+    ) -> Result<u64, Box<dyn error::Error>> {
+        let mut source: Box<dyn EventSource> = match &self.replay {
+            Some(path) => Box::new(Replay::new(Recording::load(path)?)),
+            None => Box::new(LiveEvents),
+        };
+        match &self.record {
+            Some(path) => {
+                let mut recorder = Recorder::new(source);
+                let result = self.run_with_source(sdl_context, canvas, game, &mut recorder);
+                // Saving what we have even if the game failed, the input may be what broke it
+                recorder.recording().save(path)?;
+                result
+            }
+            None => self.run_with_source(sdl_context, canvas, game, &mut source),
+        }
+    }
+
+    // Like `run` but takes the events of each frame from the source instead of live input.
+    pub fn run_with_source<G: Game, S: EventSource + ?Sized>(
+        &self,
+        sdl_context: &Sdl,
+        canvas: &mut WindowCanvas,
+        game: &mut G,
+        source: &mut S,
+    ) -> Result<u64, Box<dyn error::Error>> {
+        let mut event_pump = sdl_context
+            .event_pump()
+            .map_err(|err| Error::sdl_init("SDL_InitSubSystem(SDL_INIT_EVENTS)", err))?;
+
+        let vsync = canvas.info().flags
+            & sdl2::sys::SDL_RendererFlags::SDL_RENDERER_PRESENTVSYNC as u32
+            != 0;
+        let frame_duration = match self.frame_cap {
+            Some(fps) if !vsync && fps > 0 => Some(Duration::from_secs(1) / fps),
+            _ => None,
+        };
+
+        if let Some(input) = game.input_state() {
+            input.set_mouse_state(&event_pump.mouse_state());
+        }
+
+        let mut frames: u64 = 0;
+        let mut accumulator = Duration::from_secs(0);
+        let mut previous = Instant::now();
+
+        'running: loop {
+            if self.max_frames.is_some_and(|max| frames >= max) || source.is_done() {
+                break 'running;
+            }
+            let frame_start = Instant::now();
+
+            // Collected first so the game can query the event pump while handling each event
+            let events = source.poll(&mut event_pump);
+            if let Some(input) = game.input_state() {
+                input.begin_frame();
+            }
+            for event in events {
+                if self.is_quit(&event) {
+                    break 'running;
+                }
+                let event = match game.gamepads() {
+                    Some(gamepads) => {
+                        let opened = gamepads.handle_event(&event);
+                        let event = gamepads.apply_dead_zones(event);
+                        if let Err(err) = opened {
+                            game.gamepad_error(err);
+                        }
+                        event
+                    }
+                    None => event,
+                };
+                if let Some(input) = game.input_state() {
+                    input.handle_event(&event);
+                }
+                game.handle_event(&event, &event_pump);
+            }
+            if game.is_done() {
+                break 'running;
+            }
+
+            let updates = match source.updates() {
+                Some(updates) => updates,
+                None if self.lockstep => 1,
+                None => {
+                    let now = Instant::now();
+                    accumulator += min(now - previous, self.max_frame_time);
+                    previous = now;
+                    let mut updates = 0;
+                    while accumulator >= self.timestep {
+                        accumulator -= self.timestep;
+                        updates += 1;
+                    }
+                    updates
+                }
+            };
+            for _ in 0..updates {
+                game.update(self.timestep, &event_pump)?;
+            }
+
+            if let Some(color) = self.clear_color {
+                canvas.set_draw_color(color);
+                canvas.clear();
+            }
+            game.render(canvas)?;
+            canvas.present();
+            source.frame_done(updates);
+            frames += 1;
+
+            if let Some(frame_duration) = frame_duration {
+                let elapsed = frame_start.elapsed();
+                if elapsed < frame_duration {
+                    sleep(frame_duration - elapsed);
+                }
+            }
+        }
+        Ok(frames)
+    }
+
+    fn is_quit(&self, event: &Event) -> bool {
+        match event {
+            Event::Quit { .. } => true,
+            Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => self.quit_on_escape,
+            _ => false,
+        }
+    }
+}
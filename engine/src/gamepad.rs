@@ -0,0 +1,432 @@
+use crate::error::Error;
+use sdl2::controller::{Axis, GameController};
+use sdl2::event::Event;
+use sdl2::GameControllerSubsystem;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Dead zone of the sticks unless configured otherwise, the same value Lazy Foo uses
+pub const DEFAULT_DEAD_ZONE: i16 = 8000;
+
+const DEFAULT_MAX_PLAYERS: usize = 4;
+
+// What `Gamepads` needs from SDL, implemented by `SdlGamepadBackend` and by mocks in tests.
+//
+// Devices are identified by their device index when they are connected and by their instance id
+// from then on, the same way SDL's controller events refer to them.
+pub trait GamepadBackend {
+    // Whether SDL knows a gamepad mapping for the joystick at the device index
+    fn is_gamepad(&self, device_index: u32) -> bool;
+
+    // Opens the gamepad at the device index and returns its instance id. SDL only sends events for
+    // gamepads that are open.
+    fn open(&mut self, device_index: u32) -> Result<u32, Error>;
+
+    fn close(&mut self, instance_id: u32);
+
+    fn name(&self, instance_id: u32) -> String;
+
+    // Adds mappings in the SDL_GameControllerDB format and returns how many were added
+    fn add_mappings(&mut self, mappings: &str) -> Result<u32, Error>;
+}
+
+pub struct SdlGamepadBackend {
+    subsystem: GameControllerSubsystem,
+    controllers: HashMap<u32, GameController>,
+}
+
+impl SdlGamepadBackend {
+    pub fn new(subsystem: GameControllerSubsystem) -> Self {
+        SdlGamepadBackend {
+            subsystem,
+            controllers: HashMap::new(),
+        }
+    }
+}
+
+impl GamepadBackend for SdlGamepadBackend {
+    fn is_gamepad(&self, device_index: u32) -> bool {
+        self.subsystem.is_game_controller(device_index)
+    }
+
+    fn open(&mut self, device_index: u32) -> Result<u32, Error> {
+        let controller = self
+            .subsystem
+            .open(device_index)
+            .map_err(|err| Error::gamepad("SDL_GameControllerOpen", err))?;
+        let instance_id = controller.instance_id();
+        // Opening an open gamepad again only bumps SDL's reference count, which dropping the
+        // controller we replace undoes
+        self.controllers.insert(instance_id, controller);
+        Ok(instance_id)
+    }
+
+    fn close(&mut self, instance_id: u32) {
+        self.controllers.remove(&instance_id);
+    }
+
+    fn name(&self, instance_id: u32) -> String {
+        self.controllers
+            .get(&instance_id)
+            .map(GameController::name)
+            .unwrap_or_default()
+    }
+
+    fn add_mappings(&mut self, mappings: &str) -> Result<u32, Error> {
+        self.subsystem
+            .load_mappings_from_read(&mut mappings.as_bytes())
+            .map(|added| added as u32)
+            .map_err(|err| Error::gamepad("SDL_GameControllerAddMappingsFromRW", err))
+    }
+}
+
+// A connected gamepad and the player it was assigned to
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Gamepad {
+    // Index of the player, starting at 0
+    pub player: usize,
+    // What SDL's controller events call `which`
+    pub instance_id: u32,
+    pub name: String,
+}
+
+// Opens gamepads as they are plugged in and assigns each to the first free player slot.
+//
+// Slots are kept when a gamepad is unplugged so the other players keep theirs, the next gamepad
+// plugged in takes the first free slot.
+// Only joysticks SDL has a gamepad mapping for are supported. There is no fallback to SDL's raw
+// joystick API, so joysticks without a mapping are ignored and their `Joy*` events are neither
+// tracked by the `InputState` nor matched by bindings. Add mappings for them, i.e. via
+// `load_mappings` with the community database https://github.com/gabomdq/SDL_GameControllerDB,
+// after which SDL reports them as added gamepads.
+// Axis values are passed on with dead zones applied, see `apply_dead_zones`. The button and axis
+// state itself is tracked by the `InputState` along with keyboard and mouse.
+pub struct Gamepads {
+    backend: Box<dyn GamepadBackend>,
+    slots: Vec<Option<Gamepad>>,
+    dead_zone: i16,
+    axis_dead_zones: HashMap<Axis, i16>,
+}
+
+impl Gamepads {
+    pub fn new(subsystem: GameControllerSubsystem) -> Self {
+        Gamepads::with_backend(Box::new(SdlGamepadBackend::new(subsystem)))
+    }
+
+    pub fn with_backend(backend: Box<dyn GamepadBackend>) -> Self {
+        let mut axis_dead_zones = HashMap::new();
+        // Triggers rest at 0 and only go one way, they don't drift like sticks do
+        axis_dead_zones.insert(Axis::TriggerLeft, 0);
+        axis_dead_zones.insert(Axis::TriggerRight, 0);
+        Gamepads {
+            backend,
+            slots: vec![None; DEFAULT_MAX_PLAYERS],
+            dead_zone: DEFAULT_DEAD_ZONE,
+            axis_dead_zones,
+        }
+    }
+
+    // Gamepads plugged in while all slots are taken aren't opened
+    pub fn max_players(mut self, max_players: usize) -> Self {
+        self.slots.resize(max_players, None);
+        self
+    }
+
+    // Dead zone of the sticks, values closer to the center are reported as 0
+    pub fn dead_zone(mut self, dead_zone: i16) -> Self {
+        self.dead_zone = dead_zone;
+        self
+    }
+
+    pub fn axis_dead_zone(mut self, axis: Axis, dead_zone: i16) -> Self {
+        self.axis_dead_zones.insert(axis, dead_zone);
+        self
+    }
+
+    pub fn load_mappings(&mut self, path: &Path) -> Result<u32, Error> {
+        let mappings = fs::read_to_string(path).map_err(|err| {
+            if !path.exists() {
+                Error::MissingAsset {
+                    path: path.to_path_buf(),
+                }
+            } else {
+                Error::io(path, err)
+            }
+        })?;
+        self.add_mappings(&mappings)
+    }
+
+    pub fn add_mappings(&mut self, mappings: &str) -> Result<u32, Error> {
+        self.backend.add_mappings(mappings)
+    }
+
+    // Opens and closes gamepads as they are plugged in and out
+    pub fn handle_event(&mut self, event: &Event) -> Result<(), Error> {
+        match event {
+            Event::ControllerDeviceAdded { which, .. } => self.connect(*which)?,
+            Event::ControllerDeviceRemoved { which, .. } => self.disconnect(*which),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    // Scales axis motion so it starts at 0 at the edge of the axis' dead zone and still reaches
+    // the full range, so games don't see sticks drifting around their center
+    pub fn apply_dead_zones(&self, event: Event) -> Event {
+        match event {
+            Event::ControllerAxisMotion {
+                timestamp,
+                which,
+                axis,
+                value,
+            } => Event::ControllerAxisMotion {
+                timestamp,
+                which,
+                axis,
+                value: self.apply_dead_zone(axis, value),
+            },
+            event => event,
+        }
+    }
+
+    pub fn apply_dead_zone(&self, axis: Axis, value: i16) -> i16 {
+        let max = i32::from(i16::MAX);
+        let dead_zone = self.axis_dead_zones.get(&axis).copied();
+        let dead_zone = i32::from(dead_zone.unwrap_or(self.dead_zone)).clamp(0, max - 1);
+        // -32768 would scale past the positive range
+        let value = i32::from(value).max(-max);
+        if value.abs() <= dead_zone {
+            return 0;
+        }
+        let scaled = (value.abs() - dead_zone) * max / (max - dead_zone);
+        (value.signum() * scaled) as i16
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &Gamepad> {
+        self.slots.iter().flatten()
+    }
+
+    pub fn gamepad(&self, player: usize) -> Option<&Gamepad> {
+        self.slots.get(player).and_then(Option::as_ref)
+    }
+
+    // Instance id of the player's gamepad to query the `InputState` with
+    pub fn instance_id(&self, player: usize) -> Option<u32> {
+        self.gamepad(player).map(|gamepad| gamepad.instance_id)
+    }
+
+    pub fn player(&self, instance_id: u32) -> Option<usize> {
+        self.players()
+            .find(|gamepad| gamepad.instance_id == instance_id)
+            .map(|gamepad| gamepad.player)
+    }
+
+    fn connect(&mut self, device_index: u32) -> Result<(), Error> {
+        if !self.backend.is_gamepad(device_index) {
+            return Ok(());
+        }
+        let player = match self.slots.iter().position(Option::is_none) {
+            Some(player) => player,
+            None => return Ok(()),
+        };
+        let instance_id = self.backend.open(device_index)?;
+        // SDL reports gamepads connected at startup as added which we may have opened already
+        if self.player(instance_id).is_none() {
+            self.slots[player] = Some(Gamepad {
+                player,
+                instance_id,
+                name: self.backend.name(instance_id),
+            });
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self, instance_id: u32) {
+        if let Some(player) = self.player(instance_id) {
+            self.slots[player] = None;
+            self.backend.close(instance_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input_map::{AxisDirection, Binding, InputMap};
+    use crate::input_state::InputState;
+    use sdl2::controller::Button;
+
+    // Device 9 is a joystick without mapping, device 8 fails to open
+    #[derive(Default)]
+    struct MockGamepadBackend {
+        next_instance_id: u32,
+        mappings: Vec<String>,
+    }
+
+    impl GamepadBackend for MockGamepadBackend {
+        fn is_gamepad(&self, device_index: u32) -> bool {
+            device_index != 9 || !self.mappings.is_empty()
+        }
+
+        fn open(&mut self, device_index: u32) -> Result<u32, Error> {
+            if device_index == 8 {
+                return Err(Error::gamepad("SDL_GameControllerOpen", "mock failure"));
+            }
+            self.next_instance_id += 1;
+            Ok(self.next_instance_id)
+        }
+
+        fn close(&mut self, _instance_id: u32) {}
+
+        fn name(&self, instance_id: u32) -> String {
+            format!("Mock Pad {}", instance_id)
+        }
+
+        fn add_mappings(&mut self, mappings: &str) -> Result<u32, Error> {
+            let added: Vec<String> = mappings
+                .lines()
+                .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+                .map(String::from)
+                .collect();
+            self.mappings.extend(added.iter().cloned());
+            Ok(added.len() as u32)
+        }
+    }
+
+    fn gamepads() -> Gamepads {
+        Gamepads::with_backend(Box::new(MockGamepadBackend::default()))
+    }
+
+    fn added(which: u32) -> Event {
+        Event::ControllerDeviceAdded {
+            timestamp: 0,
+            which,
+        }
+    }
+
+    fn removed(which: u32) -> Event {
+        Event::ControllerDeviceRemoved {
+            timestamp: 0,
+            which,
+        }
+    }
+
+    fn axis_motion(which: u32, axis: Axis, value: i16) -> Event {
+        Event::ControllerAxisMotion {
+            timestamp: 0,
+            which,
+            axis,
+            value,
+        }
+    }
+
+    #[test]
+    fn player_slots() {
+        let mut gamepads = gamepads().max_players(2);
+
+        // Slots are assigned in order and kept for the remaining players when one unplugs
+        gamepads.handle_event(&added(0)).unwrap();
+        gamepads.handle_event(&added(1)).unwrap();
+        gamepads.handle_event(&added(2)).unwrap();
+        assert_eq!(gamepads.instance_id(0), Some(1));
+        assert_eq!(gamepads.instance_id(1), Some(2));
+        assert_eq!(gamepads.players().count(), 2);
+        gamepads.handle_event(&removed(1)).unwrap();
+        assert_eq!(gamepads.instance_id(0), None);
+        assert_eq!(gamepads.player(2), Some(1));
+        gamepads.handle_event(&added(0)).unwrap();
+        assert_eq!(
+            gamepads.gamepad(0).map(|gamepad| gamepad.name.as_str()),
+            Some("Mock Pad 3")
+        );
+    }
+
+    #[test]
+    fn mappings() {
+        let mut gamepads = gamepads();
+
+        // Joysticks without mapping are picked up once one was added
+        gamepads.handle_event(&added(9)).unwrap();
+        assert_eq!(gamepads.players().count(), 0);
+        let mappings = "# comment\n03000000000000000000000000000000,Mock Stick,a:b0,leftx:a0,\n";
+        assert_eq!(gamepads.add_mappings(mappings).unwrap(), 1);
+        gamepads.handle_event(&added(9)).unwrap();
+        assert_eq!(gamepads.instance_id(0), Some(1));
+
+        assert!(matches!(
+            gamepads.load_mappings(Path::new("missing/gamecontrollerdb.txt")),
+            Err(Error::MissingAsset { .. })
+        ));
+    }
+
+    #[test]
+    fn open_failure() {
+        let mut gamepads = gamepads();
+        assert!(gamepads.handle_event(&added(8)).is_err());
+        assert_eq!(gamepads.players().count(), 0);
+
+        // The slot stays free for the next gamepad
+        gamepads.handle_event(&added(0)).unwrap();
+        assert_eq!(gamepads.instance_id(0), Some(1));
+    }
+
+    #[test]
+    fn dead_zones() {
+        let gamepads = gamepads();
+        assert_eq!(gamepads.apply_dead_zone(Axis::LeftX, DEFAULT_DEAD_ZONE), 0);
+        assert_eq!(gamepads.apply_dead_zone(Axis::LeftX, i16::MAX), i16::MAX);
+        assert_eq!(gamepads.apply_dead_zone(Axis::LeftX, i16::MIN), -i16::MAX);
+        assert_eq!(gamepads.apply_dead_zone(Axis::TriggerLeft, 100), 100);
+        let gamepads = gamepads.axis_dead_zone(Axis::TriggerLeft, 1000);
+        assert_eq!(gamepads.apply_dead_zone(Axis::TriggerLeft, 100), 0);
+        assert_eq!(
+            gamepads.apply_dead_zones(axis_motion(1, Axis::TriggerLeft, 100)),
+            axis_motion(1, Axis::TriggerLeft, 0)
+        );
+    }
+
+    #[test]
+    fn input_state() {
+        let mut gamepads = gamepads();
+        gamepads.handle_event(&added(0)).unwrap();
+        let which = gamepads.instance_id(0).unwrap();
+
+        // Gamepads feed the same input state and bindings as keyboard and mouse
+        let input_map = InputMap::new()
+            .bind("Jump", Binding::GamepadButton(Button::A))
+            .bind(
+                "Up",
+                Binding::GamepadAxis(Axis::LeftY, AxisDirection::Negative),
+            );
+        let mut input = InputState::new();
+        input.begin_frame();
+        input.handle_event(&Event::ControllerButtonDown {
+            timestamp: 0,
+            which,
+            button: Button::A,
+        });
+        input.handle_event(&gamepads.apply_dead_zones(axis_motion(which, Axis::LeftY, -5000)));
+        assert!(input.is_gamepad_button_just_pressed(which, Button::A));
+        assert!(input.is_action_just_pressed(&input_map, "Jump"));
+        assert_eq!(input.gamepad_axis(which, Axis::LeftY), 0);
+        assert!(!input.is_action_held(&input_map, "Up"));
+
+        input.begin_frame();
+        input.handle_event(&gamepads.apply_dead_zones(axis_motion(which, Axis::LeftY, -30000)));
+        assert!(input.is_action_held(&input_map, "Jump"));
+        assert!(!input.is_action_just_pressed(&input_map, "Jump"));
+        assert!(input.is_action_just_pressed(&input_map, "Up"));
+
+        input.begin_frame();
+        assert!(input.is_action_held(&input_map, "Up"));
+        assert!(!input.is_action_just_pressed(&input_map, "Up"));
+
+        // Unplugging releases everything the gamepad held
+        input.begin_frame();
+        input.handle_event(&removed(which));
+        assert!(input.is_action_just_released(&input_map, "Jump"));
+        assert!(input.is_action_just_released(&input_map, "Up"));
+        assert_eq!(input.gamepad_axis(which, Axis::LeftY), 0);
+    }
+}
@@ -0,0 +1,422 @@
+use crate::error::Error;
+use crate::layout::TextLayout;
+use crate::text_renderer::TextRenderer;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Keeps glyphs from bleeding into their neighbors when the atlas is filtered
+const PADDING: u32 = 1;
+
+const DEFAULT_PAGE_SIZE: u32 = 256;
+const DEFAULT_MAX_PAGES: usize = 4;
+
+// Draws text that changes every frame, i.e. FPS counters or scores, without creating a new
+// texture each time.
+//
+// Glyphs are rasterized white the first time they are drawn, packed into shelves of an atlas
+// page and tinted while drawing. Once a page is full another one is added, up to `max_pages`,
+// after that the least recently drawn glyphs make room for new ones. Text is drawn via
+// `TextRenderer`.
+pub struct GlyphCache<'a> {
+    font: Rc<Font<'a, 'static>>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    page_width: u32,
+    page_height: u32,
+    max_pages: usize,
+    pages: Vec<Page<'a>>,
+    glyphs: HashMap<char, Glyph>,
+    // Bumped with every draw, glyphs remember the last draw they were part of
+    tick: u64,
+}
+
+struct Page<'a> {
+    texture: Texture<'a>,
+    shelves: Shelves,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Glyph {
+    page: usize,
+    // Where the glyph is on its page, the padding right and below it is part of its slot
+    rect: Rect,
+    last_used: u64,
+}
+
+impl<'a> GlyphCache<'a> {
+    // Pages are only created once glyphs are drawn
+    pub fn new(
+        font: Rc<Font<'a, 'static>>,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Self {
+        GlyphCache {
+            font,
+            texture_creator,
+            page_width: DEFAULT_PAGE_SIZE,
+            page_height: DEFAULT_PAGE_SIZE,
+            max_pages: DEFAULT_MAX_PAGES,
+            pages: Vec::new(),
+            glyphs: HashMap::new(),
+            tick: 0,
+        }
+    }
+
+    // Size of each atlas texture, glyphs larger than that can't be drawn
+    pub fn page_size(mut self, width: u32, height: u32) -> Self {
+        self.page_width = width;
+        self.page_height = height;
+        self
+    }
+
+    // At least one
+    pub fn max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages.max(1);
+        self
+    }
+
+    pub fn font(&self) -> &Font<'a, 'static> {
+        &self.font
+    }
+
+    // Number of atlas textures created so far
+    pub fn pages(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    pub fn contains(&self, ch: char) -> bool {
+        self.glyphs.contains_key(&ch)
+    }
+
+    fn copy_glyph(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        glyph: &Glyph,
+        x: i32,
+        y: i32,
+        color: Color,
+    ) -> Result<(), Error> {
+        let texture = &mut self.pages[glyph.page].texture;
+        texture.set_color_mod(color.r, color.g, color.b);
+        texture.set_alpha_mod(color.a);
+        let dst = Rect::new(x, y, glyph.rect.width(), glyph.rect.height());
+        canvas
+            .copy(texture, glyph.rect, dst)
+            .map_err(|err| Error::video("SDL_RenderCopy", err))
+    }
+
+    // Returns the cached glyph, rasterizing it into a free slot first if needed
+    fn glyph(&mut self, ch: char) -> Result<Glyph, Error> {
+        if let Some(glyph) = self.glyphs.get_mut(&ch) {
+            glyph.last_used = self.tick;
+            return Ok(*glyph);
+        }
+
+        let surface = self
+            .font
+            .render_char(ch)
+            .blended(Color::WHITE)
+            .map_err(|err| Error::ttf("TTF_RenderUTF8_Blended", None, err))?;
+        let (width, height) = (surface.width(), surface.height());
+        if width + PADDING > self.page_width || height + PADDING > self.page_height {
+            return Err(Error::GlyphCache {
+                ch: Some(ch),
+                message: format!(
+                    "it is {}x{} which exceeds the {}x{} pages",
+                    width, height, self.page_width, self.page_height
+                ),
+            });
+        }
+        let (page, slot) = self.allocate(width + PADDING, height + PADDING)?;
+
+        let surface = surface
+            .convert_format(PixelFormatEnum::RGBA32)
+            .map_err(|err| Error::texture_creation(None, err))?;
+        let rect = Rect::new(slot.x(), slot.y(), width, height);
+        let pitch = surface.pitch() as usize;
+        let texture = &mut self.pages[page].texture;
+        // Whatever was drawn there before would bleed into the padding
+        clear(texture, slot)?;
+        surface
+            .with_lock(|pixels| texture.update(rect, pixels, pitch))
+            .map_err(|err| Error::video("SDL_UpdateTexture", err))?;
+
+        let glyph = Glyph {
+            page,
+            rect,
+            last_used: self.tick,
+        };
+        self.glyphs.insert(ch, glyph);
+        Ok(glyph)
+    }
+
+    // Finds room on a page, adding pages and evicting glyphs until there is some
+    fn allocate(&mut self, width: u32, height: u32) -> Result<(usize, Rect), Error> {
+        loop {
+            for (index, page) in self.pages.iter_mut().enumerate() {
+                if let Some(slot) = page.shelves.allocate(width, height) {
+                    return Ok((index, slot));
+                }
+            }
+            if self.pages.len() < self.max_pages {
+                self.add_page()?;
+            } else {
+                self.evict()?;
+            }
+        }
+    }
+
+    fn add_page(&mut self) -> Result<(), Error> {
+        let mut texture = self
+            .texture_creator
+            .create_texture_streaming(PixelFormatEnum::RGBA32, self.page_width, self.page_height)
+            .map_err(|err| Error::texture_creation(None, err))?;
+        texture.set_blend_mode(BlendMode::Blend);
+        // Streaming textures start out with undefined pixels
+        clear(
+            &mut texture,
+            Rect::new(0, 0, self.page_width, self.page_height),
+        )?;
+        self.pages.push(Page {
+            texture,
+            shelves: Shelves::new(self.page_width, self.page_height),
+        });
+        Ok(())
+    }
+
+    // Frees the slot of the least recently drawn glyph
+    fn evict(&mut self) -> Result<(), Error> {
+        // Glyphs of the text currently drawn have to stay
+        let (&ch, glyph) = self
+            .glyphs
+            .iter()
+            .filter(|(_, glyph)| glyph.last_used < self.tick)
+            .min_by_key(|(_, glyph)| (glyph.last_used, glyph.page, glyph.rect.y(), glyph.rect.x()))
+            .ok_or_else(|| Error::GlyphCache {
+                ch: None,
+                message: format!(
+                    "text needs more glyphs than {} pages of {}x{} hold",
+                    self.max_pages, self.page_width, self.page_height
+                ),
+            })?;
+        let Glyph { page, rect, .. } = *glyph;
+        self.glyphs.remove(&ch);
+        self.pages[page]
+            .shelves
+            .free(rect.x() as u32, rect.y() as u32, rect.width() + PADDING);
+        Ok(())
+    }
+}
+
+impl<'a> TextRenderer for GlyphCache<'a> {
+    fn font_height(&self) -> i32 {
+        self.font.height()
+    }
+
+    fn line_height(&self) -> i32 {
+        self.font.recommended_line_spacing()
+    }
+
+    fn text_width(&self, text: &str) -> Result<u32, Error> {
+        self.font
+            .size_of(text)
+            .map(|(width, _)| width)
+            .map_err(|err| Error::ttf("TTF_SizeUTF8", None, err))
+    }
+
+    // Fails if the text holds more distinct glyphs than all pages together have room for.
+    fn draw_layout(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        layout: &TextLayout,
+        color: Color,
+    ) -> Result<(), Error> {
+        self.tick += 1;
+        for positioned in layout.glyphs().filter(|glyph| !glyph.ch.is_whitespace()) {
+            let glyph = self.glyph(positioned.ch)?;
+            let (x, y) = (positioned.rect.x(), positioned.rect.y());
+            self.copy_glyph(canvas, &glyph, x, y, color)?;
+        }
+        Ok(())
+    }
+}
+
+// Makes the area of the texture transparent
+fn clear(texture: &mut Texture, rect: Rect) -> Result<(), Error> {
+    let pitch = rect.width() as usize * 4;
+    let pixels = vec![0; pitch * rect.height() as usize];
+    texture
+        .update(rect, &pixels, pitch)
+        .map_err(|err| Error::video("SDL_UpdateTexture", err))
+}
+
+// Packs slots into rows of a page, each row as high as the first slot put into it. Slots go into
+// the least high row they fit into, a new row is opened below the others if none has room.
+//
+// Freed slots become free spans of their row again, merged with free neighbors, so later slots
+// of the same or a smaller size can take their place. Rows at the bottom that are entirely free
+// are closed, making room for rows of any height.
+#[derive(Clone, Debug)]
+struct Shelves {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+#[derive(Clone, Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    // Spans of the row not taken by any slot as x and width, sorted by x
+    free: Vec<(u32, u32)>,
+}
+
+impl Shelves {
+    fn new(width: u32, height: u32) -> Self {
+        Shelves {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<Rect> {
+        let fits = self
+            .shelves
+            .iter()
+            .enumerate()
+            .filter(|(_, shelf)| shelf.height >= height)
+            .filter_map(|(index, shelf)| {
+                let span = shelf.free.iter().position(|&(_, free)| free >= width)?;
+                Some((shelf.height, index, span))
+            })
+            .min();
+        let (index, span) = match fits {
+            Some((_, index, span)) => (index, span),
+            None => {
+                let y = self
+                    .shelves
+                    .last()
+                    .map_or(0, |shelf| shelf.y + shelf.height);
+                if width > self.width || y + height > self.height {
+                    return None;
+                }
+                self.shelves.push(Shelf {
+                    y,
+                    height,
+                    free: vec![(0, self.width)],
+                });
+                (self.shelves.len() - 1, 0)
+            }
+        };
+
+        let shelf = &mut self.shelves[index];
+        let (x, free) = shelf.free[span];
+        if free == width {
+            shelf.free.remove(span);
+        } else {
+            shelf.free[span] = (x + width, free - width);
+        }
+        Some(Rect::new(x as i32, shelf.y as i32, width, height))
+    }
+
+    // Gives back the slot at the position, only its width matters since it spans its row
+    fn free(&mut self, x: u32, y: u32, width: u32) {
+        let shelf = match self.shelves.iter_mut().find(|shelf| shelf.y == y) {
+            Some(shelf) => shelf,
+            None => return,
+        };
+        let index = shelf.free.partition_point(|&(free_x, _)| free_x < x);
+        shelf.free.insert(index, (x, width));
+        if let Some(&(next_x, next_width)) = shelf.free.get(index + 1) {
+            if x + width == next_x {
+                shelf.free[index].1 += next_width;
+                shelf.free.remove(index + 1);
+            }
+        }
+        if index > 0 {
+            let (prev_x, prev_width) = shelf.free[index - 1];
+            if prev_x + prev_width == x {
+                shelf.free[index - 1].1 += shelf.free[index].1;
+                shelf.free.remove(index);
+            }
+        }
+        let width = self.width;
+        while let Some(shelf) = self.shelves.last() {
+            if shelf.free != [(0, width)] {
+                break;
+            }
+            self.shelves.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shelves() {
+        let mut shelves = Shelves::new(30, 25);
+        assert_eq!(shelves.allocate(10, 10), Some(Rect::new(0, 0, 10, 10)));
+        assert_eq!(shelves.allocate(12, 8), Some(Rect::new(10, 0, 12, 8)));
+        // Doesn't fit next to the others, so it opens a row below
+        assert_eq!(shelves.allocate(10, 10), Some(Rect::new(0, 10, 10, 10)));
+        assert_eq!(shelves.allocate(8, 10), Some(Rect::new(22, 0, 8, 10)));
+        // Too high for the rows and the room left below them
+        assert_eq!(shelves.allocate(5, 12), None);
+        assert_eq!(shelves.allocate(5, 5), Some(Rect::new(10, 10, 5, 5)));
+        assert_eq!(shelves.allocate(31, 1), None);
+    }
+
+    #[test]
+    fn least_high_row() {
+        let mut shelves = Shelves::new(20, 20);
+        assert_eq!(shelves.allocate(10, 6), Some(Rect::new(0, 0, 10, 6)));
+        assert_eq!(shelves.allocate(10, 12), Some(Rect::new(0, 6, 10, 12)));
+        // Both rows have room, the first one wastes less of it
+        assert_eq!(shelves.allocate(4, 5), Some(Rect::new(10, 0, 4, 5)));
+        assert_eq!(shelves.allocate(4, 8), Some(Rect::new(10, 6, 4, 8)));
+        assert_eq!(shelves.allocate(4, 3), Some(Rect::new(14, 0, 4, 3)));
+    }
+
+    #[test]
+    fn free() {
+        let mut shelves = Shelves::new(30, 10);
+        for x in [0, 10, 20].iter() {
+            assert_eq!(shelves.allocate(10, 10), Some(Rect::new(*x, 0, 10, 10)));
+        }
+        assert_eq!(shelves.allocate(10, 10), None);
+
+        shelves.free(10, 0, 10);
+        // Smaller slots split what was freed
+        assert_eq!(shelves.allocate(4, 10), Some(Rect::new(10, 0, 4, 10)));
+        assert_eq!(shelves.allocate(7, 10), None);
+        assert_eq!(shelves.allocate(6, 10), Some(Rect::new(14, 0, 6, 10)));
+
+        // Freed neighbors merge into a span wide enough for a larger slot
+        shelves.free(0, 0, 10);
+        shelves.free(14, 0, 6);
+        shelves.free(10, 0, 4);
+        assert_eq!(shelves.shelves[0].free, vec![(0, 20)]);
+        assert_eq!(shelves.allocate(20, 10), Some(Rect::new(0, 0, 20, 10)));
+        assert!(shelves.shelves[0].free.is_empty());
+
+        // Once the row is entirely free it is closed, so a higher one fits in its place
+        shelves.free(0, 0, 20);
+        shelves.free(20, 0, 10);
+        assert!(shelves.shelves.is_empty());
+        assert_eq!(shelves.allocate(30, 10), Some(Rect::new(0, 0, 30, 10)));
+    }
+}
@@ -0,0 +1,541 @@
+use crate::error::Error;
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::mouse::MouseButton;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+// How far a gamepad axis needs to be pushed for a binding to it to count as pressed
+pub const AXIS_PRESS_THRESHOLD: i16 = i16::MAX / 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+// Something the player presses to trigger an action.
+//
+// Written as `kind:name` in bindings files, i.e. `key:Up`, `scancode:W`, `mouse:left`,
+// `button:a` or `axis:lefty-`. Keys, scancodes, buttons and axes use the names SDL gives them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    // Follows the keyboard layout, i.e. the key labeled Z
+    Key(Keycode),
+    // Follows the position on the keyboard, i.e. the key left of X on any layout
+    Scancode(Scancode),
+    Mouse(MouseButton),
+    GamepadButton(Button),
+    // One half of an axis, i.e. pushing the left stick up is `Axis::LeftY` `Negative`
+    GamepadAxis(Axis, AxisDirection),
+}
+
+impl Binding {
+    // Whether the event presses (`Some(true)`) or releases (`Some(false)`) the binding.
+    // Key repeats are ignored. Axis motion reports whether the axis is past the threshold.
+    pub fn triggered_by(&self, event: &Event) -> Option<bool> {
+        match (self, event) {
+            (
+                Binding::Key(key),
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                },
+            ) if key == keycode => Some(true),
+            (
+                Binding::Key(key),
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    ..
+                },
+            ) if key == keycode => Some(false),
+            (
+                Binding::Scancode(code),
+                Event::KeyDown {
+                    scancode: Some(scancode),
+                    repeat: false,
+                    ..
+                },
+            ) if code == scancode => Some(true),
+            (
+                Binding::Scancode(code),
+                Event::KeyUp {
+                    scancode: Some(scancode),
+                    ..
+                },
+            ) if code == scancode => Some(false),
+            (Binding::Mouse(button), Event::MouseButtonDown { mouse_btn, .. })
+                if button == mouse_btn =>
+            {
+                Some(true)
+            }
+            (Binding::Mouse(button), Event::MouseButtonUp { mouse_btn, .. })
+                if button == mouse_btn =>
+            {
+                Some(false)
+            }
+            (Binding::GamepadButton(bound), Event::ControllerButtonDown { button, .. })
+                if bound == button =>
+            {
+                Some(true)
+            }
+            (Binding::GamepadButton(bound), Event::ControllerButtonUp { button, .. })
+                if bound == button =>
+            {
+                Some(false)
+            }
+            (
+                Binding::GamepadAxis(bound, direction),
+                Event::ControllerAxisMotion { axis, value, .. },
+            ) if bound == axis => Some(direction.is_pressed(*value)),
+            _ => None,
+        }
+    }
+
+    // The binding the event presses, i.e. to rebind an action to whatever the player presses
+    // next. Keys are captured by keycode.
+    pub fn capture(event: &Event) -> Option<Self> {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat: false,
+                ..
+            } => Some(Binding::Key(*keycode)),
+            Event::MouseButtonDown { mouse_btn, .. } => Some(Binding::Mouse(*mouse_btn)),
+            Event::ControllerButtonDown { button, .. } => Some(Binding::GamepadButton(*button)),
+            Event::ControllerAxisMotion { axis, value, .. } => {
+                [AxisDirection::Positive, AxisDirection::Negative]
+                    .iter()
+                    .find(|direction| direction.is_pressed(*value))
+                    .map(|direction| Binding::GamepadAxis(*axis, *direction))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl AxisDirection {
+    pub fn is_pressed(self, value: i16) -> bool {
+        match self {
+            AxisDirection::Positive => value >= AXIS_PRESS_THRESHOLD,
+            AxisDirection::Negative => value <= -AXIS_PRESS_THRESHOLD,
+        }
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(keycode) => write!(f, "key:{}", keycode.name()),
+            Binding::Scancode(scancode) => write!(f, "scancode:{}", scancode.name()),
+            Binding::Mouse(button) => write!(f, "mouse:{}", mouse_button_name(*button)),
+            Binding::GamepadButton(button) => write!(f, "button:{}", button.string()),
+            Binding::GamepadAxis(axis, direction) => {
+                let sign = match direction {
+                    AxisDirection::Positive => '+',
+                    AxisDirection::Negative => '-',
+                };
+                write!(f, "axis:{}{}", axis.string(), sign)
+            }
+        }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(binding: &str) -> Result<Self, Self::Err> {
+        let invalid = |expected: &str| format!("invalid binding {:?}, {}", binding, expected);
+        let (kind, name) = binding
+            .split_once(':')
+            .ok_or_else(|| invalid("expected kind:name, i.e. key:Up"))?;
+        let unknown = || invalid(&format!("unknown {} {:?}", kind, name));
+        match kind {
+            "key" => Keycode::from_name(name)
+                .map(Binding::Key)
+                .ok_or_else(unknown),
+            "scancode" => Scancode::from_name(name)
+                .map(Binding::Scancode)
+                .ok_or_else(unknown),
+            "mouse" => mouse_button_from_name(name)
+                .map(Binding::Mouse)
+                .ok_or_else(unknown),
+            "button" => Button::from_string(name)
+                .map(Binding::GamepadButton)
+                .ok_or_else(unknown),
+            "axis" => {
+                let (axis, direction) = if let Some(axis) = name.strip_suffix('+') {
+                    (axis, AxisDirection::Positive)
+                } else if let Some(axis) = name.strip_suffix('-') {
+                    (axis, AxisDirection::Negative)
+                } else {
+                    return Err(invalid("axes need a + or - direction, i.e. axis:lefty-"));
+                };
+                Axis::from_string(axis)
+                    .map(|axis| Binding::GamepadAxis(axis, direction))
+                    .ok_or_else(unknown)
+            }
+            _ => Err(invalid("expected key, scancode, mouse, button or axis")),
+        }
+    }
+}
+
+fn mouse_button_name(button: MouseButton) -> &'static str {
+    match button {
+        MouseButton::Left => "left",
+        MouseButton::Middle => "middle",
+        MouseButton::Right => "right",
+        MouseButton::X1 => "x1",
+        MouseButton::X2 => "x2",
+        MouseButton::Unknown => "unknown",
+    }
+}
+
+fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    [
+        MouseButton::Left,
+        MouseButton::Middle,
+        MouseButton::Right,
+        MouseButton::X1,
+        MouseButton::X2,
+    ]
+    .iter()
+    .copied()
+    .find(|button| mouse_button_name(*button) == name)
+}
+
+// An action's binding was pressed or released
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ActionEvent<'m> {
+    pub action: &'m str,
+    pub pressed: bool,
+}
+
+// Named actions, i.e. `MoveUp`, each bound to any number of keys, mouse buttons or gamepad
+// buttons and axes, so games handle actions instead of hard coding keys.
+//
+// Bindings are loaded from and saved to TOML files listing the bindings of each action:
+//
+// ```toml
+// [actions]
+// MoveUp = ["key:Up", "key:W", "button:dpup", "axis:lefty-"]
+// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InputMap {
+    actions: BTreeMap<String, Vec<Binding>>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BindingsFile {
+    actions: BTreeMap<String, Vec<String>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        InputMap::default()
+    }
+
+    pub fn bind(mut self, action: &str, binding: Binding) -> Self {
+        self.add_binding(action, binding);
+        self
+    }
+
+    pub fn add_binding(&mut self, action: &str, binding: Binding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    pub fn remove_binding(&mut self, action: &str, binding: &Binding) {
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|bound| bound != binding);
+        }
+    }
+
+    // Swaps one binding of the action for another, keeping its position. Adds the new binding if
+    // the action didn't have the old one.
+    pub fn rebind(&mut self, action: &str, old: &Binding, new: Binding) {
+        let bindings = self.actions.entry(action.to_string()).or_default();
+        if !bindings.contains(old) {
+            self.add_binding(action, new);
+            return;
+        }
+        // The new binding moves to the old one's position if the action already had it
+        bindings.retain(|bound| bound == old || *bound != new);
+        if let Some(idx) = bindings.iter().position(|bound| bound == old) {
+            bindings[idx] = new;
+        }
+    }
+
+    // Replaces all bindings of the action, bindings listed more than once are kept once
+    pub fn set_bindings(&mut self, action: &str, bindings: Vec<Binding>) {
+        let mut unique = Vec::with_capacity(bindings.len());
+        for binding in bindings {
+            if !unique.contains(&binding) {
+                unique.push(binding);
+            }
+        }
+        self.actions.insert(action.to_string(), unique);
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+
+    // Actions whose bindings the event presses or releases
+    pub fn action_events(&self, event: &Event) -> Vec<ActionEvent<'_>> {
+        self.actions
+            .iter()
+            .filter_map(|(action, bindings)| {
+                bindings
+                    .iter()
+                    .find_map(|binding| binding.triggered_by(event))
+                    .map(|pressed| ActionEvent {
+                        action: action.as_str(),
+                        pressed,
+                    })
+            })
+            .collect()
+    }
+
+    // Whether the event presses one of the action's bindings
+    pub fn just_pressed(&self, action: &str, event: &Event) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.triggered_by(event) == Some(true))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let src = fs::read_to_string(path).map_err(|err| {
+            if !path.exists() {
+                Error::MissingAsset {
+                    path: path.to_path_buf(),
+                }
+            } else {
+                Error::io(path, err)
+            }
+        })?;
+        InputMap::from_toml(&src).map_err(|message| Error::Bindings {
+            path: Some(path.to_path_buf()),
+            message,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let toml = self.to_toml().map_err(|message| Error::Bindings {
+            path: Some(path.to_path_buf()),
+            message,
+        })?;
+        fs::write(path, toml).map_err(|err| Error::io(path, err))
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self, String> {
+        let file: BindingsFile = toml::from_str(toml).map_err(|err| err.to_string())?;
+        let mut map = InputMap::new();
+        for (action, bindings) in file.actions {
+            let bindings = bindings
+                .iter()
+                .map(|binding| binding.parse())
+                .collect::<Result<Vec<Binding>, _>>()
+                .map_err(|err| format!("action {:?}: {}", action, err))?;
+            map.set_bindings(&action, bindings);
+        }
+        Ok(map)
+    }
+
+    pub fn to_toml(&self) -> Result<String, String> {
+        let file = BindingsFile {
+            actions: self
+                .actions
+                .iter()
+                .map(|(action, bindings)| {
+                    let bindings = bindings.iter().map(Binding::to_string).collect();
+                    (action.clone(), bindings)
+                })
+                .collect(),
+        };
+        toml::to_string(&file).map_err(|err| err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::keyboard::Mod;
+
+    fn key_down(keycode: Keycode) -> Event {
+        Event::KeyDown {
+            timestamp: 0,
+            window_id: 0,
+            keycode: Some(keycode),
+            scancode: None,
+            keymod: Mod::NOMOD,
+            repeat: false,
+        }
+    }
+
+    fn pressed(input_map: &InputMap, event: &Event) -> Vec<String> {
+        input_map
+            .action_events(event)
+            .iter()
+            .filter(|action| action.pressed)
+            .map(|action| action.action.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn bindings() {
+        for binding in [
+            "key:Up",
+            "scancode:W",
+            "mouse:left",
+            "button:a",
+            "axis:lefty-",
+            "axis:triggerleft+",
+        ]
+        .iter()
+        {
+            let parsed: Binding = binding.parse().unwrap();
+            assert_eq!(&parsed.to_string(), binding);
+        }
+        assert_eq!(
+            "axis:leftx+".parse(),
+            Ok(Binding::GamepadAxis(Axis::LeftX, AxisDirection::Positive))
+        );
+    }
+
+    #[test]
+    fn action_events() {
+        let input_map = InputMap::new()
+            .bind("Up", Binding::Key(Keycode::W))
+            .bind(
+                "Up",
+                Binding::GamepadAxis(Axis::LeftY, AxisDirection::Negative),
+            )
+            .bind("Fire", Binding::Key(Keycode::Space));
+        assert_eq!(pressed(&input_map, &key_down(Keycode::W)), ["Up"]);
+        let stick_up = Event::ControllerAxisMotion {
+            timestamp: 0,
+            which: 0,
+            axis: Axis::LeftY,
+            value: -30000,
+        };
+        assert_eq!(pressed(&input_map, &stick_up), ["Up"]);
+        assert!(input_map.just_pressed("Fire", &key_down(Keycode::Space)));
+        assert!(pressed(&input_map, &key_down(Keycode::Q)).is_empty());
+    }
+
+    #[test]
+    fn rebind() {
+        let mut input_map = InputMap::new().bind("RedUp", Binding::Key(Keycode::Q));
+
+        // Rebinding at runtime to whatever is pressed next
+        let captured = Binding::capture(&key_down(Keycode::R)).unwrap();
+        input_map.rebind("RedUp", &Binding::Key(Keycode::Q), captured);
+        assert!(pressed(&input_map, &key_down(Keycode::Q)).is_empty());
+        assert_eq!(pressed(&input_map, &key_down(Keycode::R)), ["RedUp"]);
+    }
+
+    #[test]
+    fn rebind_to_a_binding_the_action_has() {
+        let (q, w, e) = (
+            Binding::Key(Keycode::Q),
+            Binding::Key(Keycode::W),
+            Binding::Key(Keycode::E),
+        );
+        let mut input_map = InputMap::new().bind("Up", q).bind("Up", w).bind("Up", e);
+        input_map.rebind("Up", &q, e);
+        assert_eq!(input_map.bindings("Up"), &[e, w]);
+        input_map.rebind("Up", &w, w);
+        assert_eq!(input_map.bindings("Up"), &[e, w]);
+
+        input_map.set_bindings("Up", vec![w, q, w, e, q]);
+        assert_eq!(input_map.bindings("Up"), &[w, q, e]);
+
+        let input_map = InputMap::from_toml(
+            "[actions]\nFire = [\"mouse:left\", \"mouse:right\", \"mouse:left\"]",
+        )
+        .unwrap();
+        assert_eq!(
+            input_map.bindings("Fire"),
+            &[
+                Binding::Mouse(MouseButton::Left),
+                Binding::Mouse(MouseButton::Right)
+            ]
+        );
+    }
+
+    #[test]
+    fn toml() {
+        let input_map = InputMap::from_toml(
+            "[actions]\nJump = [\"key:Space\", \"button:a\"]\nUp = [\"axis:lefty-\"]",
+        )
+        .unwrap();
+        assert_eq!(
+            input_map.bindings("Jump"),
+            &[
+                Binding::Key(Keycode::Space),
+                Binding::GamepadButton(Button::A)
+            ]
+        );
+        assert_eq!(input_map.actions().collect::<Vec<_>>(), ["Jump", "Up"]);
+
+        let mut input_map = input_map;
+        input_map.add_binding("Jump", "mouse:left".parse().unwrap());
+        assert_eq!(
+            InputMap::from_toml(&input_map.to_toml().unwrap()),
+            Ok(input_map),
+            "bindings survive saving"
+        );
+    }
+
+    #[test]
+    fn invalid_bindings() {
+        for invalid in [
+            "key",
+            "key:NotAKey",
+            "axis:lefty",
+            "axis:sideways+",
+            "mouse:x3",
+            "pedal:left",
+        ]
+        .iter()
+        {
+            assert!(invalid.parse::<Binding>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn invalid_toml() {
+        let unknown_key = InputMap::from_toml("[actions]\nJump = [\"key:NotAKey\"]").unwrap_err();
+        assert!(unknown_key.contains("Jump"), "{}", unknown_key);
+        assert!(unknown_key.contains("NotAKey"), "{}", unknown_key);
+
+        let duplicate =
+            InputMap::from_toml("[actions]\nJump = [\"key:Space\"]\nJump = [\"key:W\"]");
+        assert!(duplicate.is_err());
+
+        for malformed in [
+            "[actions",
+            "[actions]\nJump = \"key:Space\"",
+            "[actions]\nJump = [\"key:Space\"",
+            "[bindings]\nJump = [\"key:Space\"]",
+        ]
+        .iter()
+        {
+            assert!(InputMap::from_toml(malformed).is_err(), "{}", malformed);
+        }
+    }
+}
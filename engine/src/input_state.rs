@@ -0,0 +1,520 @@
+use crate::input_map::{AxisDirection, Binding, InputMap};
+use sdl2::controller::{Axis, Button};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Scancode};
+use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+// Held buttons of one kind of input plus what changed this frame
+#[derive(Clone, Debug)]
+struct Buttons<T> {
+    held: HashSet<T>,
+    pressed: HashSet<T>,
+    released: HashSet<T>,
+    repeated: HashSet<T>,
+}
+
+impl<T> Default for Buttons<T> {
+    fn default() -> Self {
+        Buttons {
+            held: HashSet::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            repeated: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Buttons<T> {
+    fn press(&mut self, button: T, repeat: bool) {
+        if repeat {
+            self.repeated.insert(button);
+        } else if self.held.insert(button) {
+            self.pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: T) {
+        if self.held.remove(&button) {
+            self.released.insert(button);
+        }
+    }
+
+    fn release_all(&mut self) {
+        let held: Vec<T> = self.held.iter().copied().collect();
+        for button in held {
+            self.release(button);
+        }
+    }
+
+    fn clear_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.repeated.clear();
+    }
+}
+
+// Keyboard, mouse and gamepad input as of the current frame, fed with the frame's events.
+//
+// Besides whether a key or button is held it knows whether it was pressed or released this
+// frame and whether the key repeated, so games don't have to diff state between frames.
+// The `GameLoop` keeps it up to date for games that return it from `Game::input_state`, otherwise
+// call `begin_frame` before and `handle_event` for each of the frame's events.
+// Gamepads are told apart by the instance id SDL's controller events call `which`, see
+// `Gamepads::instance_id` for the one of a player. Only gamepads opened by `Gamepads` send events.
+#[derive(Clone, Debug, Default)]
+pub struct InputState {
+    keys: Buttons<Keycode>,
+    scancodes: Buttons<Scancode>,
+    mouse_buttons: Buttons<MouseButton>,
+    mouse_position: (i32, i32),
+    mouse_delta: (i32, i32),
+    wheel: (i32, i32),
+    gamepad_buttons: Buttons<(u32, Button)>,
+    gamepad_axes: HashMap<(u32, Axis), i16>,
+    // Axis values at the start of the frame to tell when an axis binding was just pressed
+    previous_gamepad_axes: HashMap<(u32, Axis), i16>,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        InputState::default()
+    }
+
+    // Takes over where the mouse is and which buttons are held, i.e. when starting up so the
+    // position isn't 0/0 until the mouse moves
+    pub fn set_mouse_state(&mut self, mouse_state: &MouseState) {
+        self.mouse_position = (mouse_state.x(), mouse_state.y());
+        self.mouse_buttons.held = mouse_state.pressed_mouse_buttons().collect();
+    }
+
+    // Forgets what was pressed and released and how far the mouse moved during the last frame
+    pub fn begin_frame(&mut self) {
+        self.keys.clear_frame();
+        self.scancodes.clear_frame();
+        self.mouse_buttons.clear_frame();
+        self.mouse_delta = (0, 0);
+        self.wheel = (0, 0);
+        self.gamepad_buttons.clear_frame();
+        self.previous_gamepad_axes = self.gamepad_axes.clone();
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::KeyDown {
+                keycode,
+                scancode,
+                repeat,
+                ..
+            } => {
+                if let Some(keycode) = keycode {
+                    self.keys.press(*keycode, *repeat);
+                }
+                if let Some(scancode) = scancode {
+                    self.scancodes.press(*scancode, *repeat);
+                }
+            }
+            Event::KeyUp {
+                keycode, scancode, ..
+            } => {
+                if let Some(keycode) = keycode {
+                    self.keys.release(*keycode);
+                }
+                if let Some(scancode) = scancode {
+                    self.scancodes.release(*scancode);
+                }
+            }
+            Event::MouseButtonDown {
+                mouse_btn, x, y, ..
+            } => {
+                self.mouse_position = (*x, *y);
+                self.mouse_buttons.press(*mouse_btn, false);
+            }
+            Event::MouseButtonUp {
+                mouse_btn, x, y, ..
+            } => {
+                self.mouse_position = (*x, *y);
+                self.mouse_buttons.release(*mouse_btn);
+            }
+            Event::MouseMotion {
+                x, y, xrel, yrel, ..
+            } => {
+                self.mouse_position = (*x, *y);
+                self.mouse_delta.0 += xrel;
+                self.mouse_delta.1 += yrel;
+            }
+            Event::MouseWheel {
+                x, y, direction, ..
+            } => {
+                // Reports scrolling the same way independent of natural scrolling settings
+                let sign = match direction {
+                    MouseWheelDirection::Flipped => -1,
+                    _ => 1,
+                };
+                self.wheel.0 += x * sign;
+                self.wheel.1 += y * sign;
+            }
+            Event::ControllerButtonDown { which, button, .. } => {
+                self.gamepad_buttons.press((*which, *button), false);
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                self.gamepad_buttons.release((*which, *button));
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                self.gamepad_axes.insert((*which, *axis), *value);
+            }
+            Event::ControllerDeviceRemoved { which, .. } => {
+                let held: Vec<_> = self
+                    .gamepad_buttons
+                    .held
+                    .iter()
+                    .copied()
+                    .filter(|(id, _)| id == which)
+                    .collect();
+                for button in held {
+                    self.gamepad_buttons.release(button);
+                }
+                self.gamepad_axes.retain(|(id, _), _| id != which);
+            }
+            // We won't see the key and button releases while another window has focus
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => {
+                self.keys.release_all();
+                self.scancodes.release_all();
+                self.mouse_buttons.release_all();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn is_key_held(&self, keycode: Keycode) -> bool {
+        self.keys.held.contains(&keycode)
+    }
+
+    pub fn is_key_just_pressed(&self, keycode: Keycode) -> bool {
+        self.keys.pressed.contains(&keycode)
+    }
+
+    pub fn is_key_just_released(&self, keycode: Keycode) -> bool {
+        self.keys.released.contains(&keycode)
+    }
+
+    // Whether the OS sent a key repeat for the held key this frame
+    pub fn is_key_repeated(&self, keycode: Keycode) -> bool {
+        self.keys.repeated.contains(&keycode)
+    }
+
+    pub fn is_scancode_held(&self, scancode: Scancode) -> bool {
+        self.scancodes.held.contains(&scancode)
+    }
+
+    pub fn is_scancode_just_pressed(&self, scancode: Scancode) -> bool {
+        self.scancodes.pressed.contains(&scancode)
+    }
+
+    pub fn is_scancode_just_released(&self, scancode: Scancode) -> bool {
+        self.scancodes.released.contains(&scancode)
+    }
+
+    pub fn is_scancode_repeated(&self, scancode: Scancode) -> bool {
+        self.scancodes.repeated.contains(&scancode)
+    }
+
+    pub fn held_scancodes(&self) -> impl Iterator<Item = Scancode> + '_ {
+        self.scancodes.held.iter().copied()
+    }
+
+    pub fn is_mouse_held(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.held.contains(&button)
+    }
+
+    pub fn is_mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.pressed.contains(&button)
+    }
+
+    pub fn is_mouse_just_released(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.released.contains(&button)
+    }
+
+    pub fn held_mouse_buttons(&self) -> impl Iterator<Item = MouseButton> + '_ {
+        self.mouse_buttons.held.iter().copied()
+    }
+
+    pub fn mouse_position(&self) -> (i32, i32) {
+        self.mouse_position
+    }
+
+    // How far the mouse moved this frame, also while in relative mouse mode
+    pub fn mouse_delta(&self) -> (i32, i32) {
+        self.mouse_delta
+    }
+
+    // Horizontal and vertical scroll this frame, positive y scrolls up, away from the user
+    pub fn wheel(&self) -> (i32, i32) {
+        self.wheel
+    }
+
+    pub fn is_gamepad_button_held(&self, which: u32, button: Button) -> bool {
+        self.gamepad_buttons.held.contains(&(which, button))
+    }
+
+    pub fn is_gamepad_button_just_pressed(&self, which: u32, button: Button) -> bool {
+        self.gamepad_buttons.pressed.contains(&(which, button))
+    }
+
+    pub fn is_gamepad_button_just_released(&self, which: u32, button: Button) -> bool {
+        self.gamepad_buttons.released.contains(&(which, button))
+    }
+
+    // Latest value of the axis, with dead zones applied when fed by the `GameLoop`
+    pub fn gamepad_axis(&self, which: u32, axis: Axis) -> i16 {
+        self.gamepad_axes.get(&(which, axis)).copied().unwrap_or(0)
+    }
+
+    // Bindings to gamepad buttons and axes are held if they are held on any of the gamepads
+    pub fn is_binding_held(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Key(keycode) => self.is_key_held(*keycode),
+            Binding::Scancode(scancode) => self.is_scancode_held(*scancode),
+            Binding::Mouse(button) => self.is_mouse_held(*button),
+            Binding::GamepadButton(button) => any_button(&self.gamepad_buttons.held, *button),
+            Binding::GamepadAxis(axis, direction) => {
+                any_axis(&self.gamepad_axes, *axis, *direction)
+            }
+        }
+    }
+
+    pub fn is_binding_just_pressed(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Key(keycode) => self.is_key_just_pressed(*keycode),
+            Binding::Scancode(scancode) => self.is_scancode_just_pressed(*scancode),
+            Binding::Mouse(button) => self.is_mouse_just_pressed(*button),
+            Binding::GamepadButton(button) => any_button(&self.gamepad_buttons.pressed, *button),
+            Binding::GamepadAxis(axis, direction) => {
+                self.is_binding_held(binding)
+                    && !any_axis(&self.previous_gamepad_axes, *axis, *direction)
+            }
+        }
+    }
+
+    pub fn is_binding_just_released(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::Key(keycode) => self.is_key_just_released(*keycode),
+            Binding::Scancode(scancode) => self.is_scancode_just_released(*scancode),
+            Binding::Mouse(button) => self.is_mouse_just_released(*button),
+            Binding::GamepadButton(button) => any_button(&self.gamepad_buttons.released, *button),
+            Binding::GamepadAxis(axis, direction) => {
+                !self.is_binding_held(binding)
+                    && any_axis(&self.previous_gamepad_axes, *axis, *direction)
+            }
+        }
+    }
+
+    // Whether the binding was held when the frame began, including ones released since
+    fn was_binding_held(&self, binding: &Binding) -> bool {
+        match binding {
+            Binding::GamepadAxis(axis, direction) => {
+                any_axis(&self.previous_gamepad_axes, *axis, *direction)
+            }
+            _ => {
+                (self.is_binding_held(binding) || self.is_binding_just_released(binding))
+                    && !self.is_binding_just_pressed(binding)
+            }
+        }
+    }
+
+    // Whether any binding of the action is held
+    pub fn is_action_held(&self, input_map: &InputMap, action: &str) -> bool {
+        input_map
+            .bindings(action)
+            .iter()
+            .any(|binding| self.is_binding_held(binding))
+    }
+
+    // Whether a binding of the action was pressed this frame while none was held before
+    pub fn is_action_just_pressed(&self, input_map: &InputMap, action: &str) -> bool {
+        let bindings = input_map.bindings(action);
+        bindings
+            .iter()
+            .any(|binding| self.is_binding_just_pressed(binding))
+            && !bindings
+                .iter()
+                .any(|binding| self.was_binding_held(binding))
+    }
+
+    // Whether the last held binding of the action was released this frame
+    pub fn is_action_just_released(&self, input_map: &InputMap, action: &str) -> bool {
+        let bindings = input_map.bindings(action);
+        bindings
+            .iter()
+            .any(|binding| self.is_binding_just_released(binding))
+            && !self.is_action_held(input_map, action)
+    }
+}
+
+fn any_button(buttons: &HashSet<(u32, Button)>, button: Button) -> bool {
+    buttons.iter().any(|(_, held)| *held == button)
+}
+
+fn any_axis(axes: &HashMap<(u32, Axis), i16>, axis: Axis, direction: AxisDirection) -> bool {
+    axes.iter()
+        .any(|((_, moved), value)| *moved == axis && direction.is_pressed(*value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sdl2::keyboard::Mod;
+
+    // SDL's keymap is empty until the video subsystem is initialized, so the scancode is given
+    fn space(down: bool, repeat: bool) -> Event {
+        let (keycode, scancode) = (Some(Keycode::Space), Some(Scancode::Space));
+        if down {
+            Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode,
+                scancode,
+                keymod: Mod::NOMOD,
+                repeat,
+            }
+        } else {
+            Event::KeyUp {
+                timestamp: 0,
+                window_id: 0,
+                keycode,
+                scancode,
+                keymod: Mod::NOMOD,
+                repeat,
+            }
+        }
+    }
+
+    fn left_down(x: i32, y: i32) -> Event {
+        Event::MouseButtonDown {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            mouse_btn: MouseButton::Left,
+            clicks: 1,
+            x,
+            y,
+        }
+    }
+
+    fn fire() -> InputMap {
+        InputMap::new()
+            .bind("Fire", Binding::Key(Keycode::Space))
+            .bind("Fire", Binding::Mouse(MouseButton::Left))
+    }
+
+    #[test]
+    fn keys() {
+        let input_map = fire();
+        let mut input = InputState::new();
+
+        input.begin_frame();
+        input.handle_event(&space(true, false));
+        assert!(input.is_key_held(Keycode::Space));
+        assert!(input.is_key_just_pressed(Keycode::Space));
+        assert!(input.is_scancode_just_pressed(Scancode::Space));
+        assert!(input.is_action_just_pressed(&input_map, "Fire"));
+
+        input.begin_frame();
+        input.handle_event(&space(true, true));
+        assert!(input.is_key_held(Keycode::Space));
+        assert!(!input.is_key_just_pressed(Keycode::Space));
+        assert!(input.is_key_repeated(Keycode::Space));
+        assert!(input.is_action_held(&input_map, "Fire"));
+        assert!(!input.is_action_just_pressed(&input_map, "Fire"));
+
+        input.begin_frame();
+        input.handle_event(&space(false, false));
+        assert!(!input.is_key_held(Keycode::Space));
+        assert!(input.is_key_just_released(Keycode::Space));
+        assert!(input.is_action_just_released(&input_map, "Fire"));
+    }
+
+    #[test]
+    fn actions_held_by_any_binding() {
+        let input_map = fire();
+        let mut input = InputState::new();
+        input.begin_frame();
+        input.handle_event(&space(true, false));
+
+        // Still held via the mouse, so releasing space doesn't release the action
+        input.begin_frame();
+        input.handle_event(&left_down(10, 20));
+        input.handle_event(&space(false, false));
+        assert!(input.is_key_just_released(Keycode::Space));
+        assert!(!input.is_action_just_pressed(&input_map, "Fire"));
+        assert!(!input.is_action_just_released(&input_map, "Fire"));
+        assert!(input.is_action_held(&input_map, "Fire"));
+        assert!(input.is_mouse_just_pressed(MouseButton::Left));
+    }
+
+    #[test]
+    fn mouse() {
+        let mut input = InputState::new();
+        input.begin_frame();
+        input.handle_event(&left_down(10, 20));
+        assert_eq!(input.mouse_position(), (10, 20));
+
+        input.begin_frame();
+        for (x, y, xrel, yrel) in [(15, 22, 5, 2), (12, 30, -3, 8)].iter() {
+            input.handle_event(&Event::MouseMotion {
+                timestamp: 0,
+                window_id: 0,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(0),
+                x: *x,
+                y: *y,
+                xrel: *xrel,
+                yrel: *yrel,
+            });
+        }
+        input.handle_event(&Event::MouseWheel {
+            timestamp: 0,
+            window_id: 0,
+            which: 0,
+            x: 0,
+            y: 1,
+            direction: MouseWheelDirection::Flipped,
+        });
+        assert_eq!(input.mouse_position(), (12, 30));
+        assert_eq!(input.mouse_delta(), (2, 10));
+        assert_eq!(input.wheel(), (0, -1));
+        assert!(input.is_mouse_held(MouseButton::Left));
+        assert!(!input.is_mouse_just_pressed(MouseButton::Left));
+
+        input.begin_frame();
+        assert_eq!(input.mouse_delta(), (0, 0));
+        assert_eq!(input.wheel(), (0, 0));
+    }
+
+    #[test]
+    fn focus_lost_releases_everything() {
+        let input_map = fire();
+        let mut input = InputState::new();
+        input.begin_frame();
+        input.handle_event(&space(true, false));
+        input.handle_event(&left_down(10, 20));
+
+        // The releases go to another window
+        input.begin_frame();
+        input.handle_event(&Event::Window {
+            timestamp: 0,
+            window_id: 0,
+            win_event: WindowEvent::FocusLost,
+        });
+        assert!(!input.is_key_held(Keycode::Space));
+        assert!(!input.is_mouse_held(MouseButton::Left));
+        assert!(input.is_action_just_released(&input_map, "Fire"));
+        assert_eq!(input.mouse_delta(), (0, 0));
+    }
+}
@@ -0,0 +1,462 @@
+use crate::error::Error;
+use sdl2::rect::Rect;
+#[cfg(feature = "ttf")]
+use sdl2::ttf::Font;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+    // Stretches the gaps between words so lines fill the bounds, except the last line of each
+    // paragraph which is left aligned
+    Justify,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VerticalAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+// Describes how text is arranged inside a rect, see `TextLayout`.
+// Defaults to left aligned text at the top of the rect, wrapped at its width.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LayoutOptions {
+    pub(crate) align: Align,
+    vertical_align: VerticalAlign,
+    pub(crate) max_width: Option<u32>,
+    pub(crate) line_spacing: i32,
+}
+
+impl Default for LayoutOptions {
+    fn default() -> Self {
+        LayoutOptions::new()
+    }
+}
+
+impl LayoutOptions {
+    pub fn new() -> Self {
+        LayoutOptions {
+            align: Align::Left,
+            vertical_align: VerticalAlign::Top,
+            max_width: None,
+            line_spacing: 0,
+        }
+    }
+
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    pub fn vertical_align(mut self, vertical_align: VerticalAlign) -> Self {
+        self.vertical_align = vertical_align;
+        self
+    }
+
+    // Wraps lines at this width instead of the width of the bounds
+    pub fn max_width(mut self, max_width: u32) -> Self {
+        self.max_width = Some(max_width);
+        self
+    }
+
+    // Pixels added to the font's line spacing, negative values move lines closer together
+    pub fn line_spacing(mut self, line_spacing: i32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    // Positions a box of the given size inside the bounds, i.e. a sprite of pre-rendered text.
+    // Justified boxes are left aligned.
+    pub fn place(&self, width: u32, height: u32, bounds: Rect) -> Rect {
+        let x = bounds.x() + offset(self.align_offset(), bounds.width(), width);
+        let y = bounds.y() + offset(self.vertical_offset(), bounds.height(), height);
+        Rect::new(x, y, width, height)
+    }
+
+    // Fraction of the free space placed before the box
+    pub(crate) fn align_offset(&self) -> f32 {
+        match self.align {
+            Align::Left | Align::Justify => 0.0,
+            Align::Center => 0.5,
+            Align::Right => 1.0,
+        }
+    }
+
+    fn vertical_offset(&self) -> f32 {
+        match self.vertical_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => 0.5,
+            VerticalAlign::Bottom => 1.0,
+        }
+    }
+}
+
+// Boxes larger than the available space overflow on both sides when centered
+pub(crate) fn offset(fraction: f32, available: u32, size: u32) -> i32 {
+    ((available as i64 - size as i64) as f32 * fraction).floor() as i32
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PositionedGlyph {
+    // Byte offset of the character into the laid out text
+    pub index: usize,
+    pub ch: char,
+    pub rect: Rect,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutLine {
+    pub text: String,
+    pub rect: Rect,
+    pub glyphs: Vec<PositionedGlyph>,
+}
+
+// Text broken into lines and positioned inside a rect.
+//
+// Lines break at spaces between words so they fit the max width, and at explicit newlines.
+// Words wider than a whole line are broken between characters. Spaces at a line break belong to
+// neither line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextLayout {
+    lines: Vec<LayoutLine>,
+    rect: Rect,
+}
+
+// Byte range of a line into the text and whether it ends a paragraph
+struct LineRange {
+    start: usize,
+    end: usize,
+    last: bool,
+}
+
+impl TextLayout {
+    #[cfg(feature = "ttf")]
+    pub fn new(
+        text: &str,
+        font: &Font,
+        bounds: Rect,
+        options: &LayoutOptions,
+    ) -> Result<Self, Error> {
+        TextLayout::with_measure(
+            text,
+            bounds,
+            options,
+            font.height(),
+            font.recommended_line_spacing(),
+            |text| {
+                font.size_of(text)
+                    .map(|(width, _)| width)
+                    .map_err(|err| Error::ttf("TTF_SizeUTF8", None, err))
+            },
+        )
+    }
+
+    // Lays out text of any font that can measure the width of a string.
+    pub fn with_measure<M>(
+        text: &str,
+        bounds: Rect,
+        options: &LayoutOptions,
+        font_height: i32,
+        line_height: i32,
+        measure: M,
+    ) -> Result<Self, Error>
+    where
+        M: Fn(&str) -> Result<u32, Error>,
+    {
+        let widths = char_widths(text, &measure)?;
+        let max_width = options.max_width.unwrap_or_else(|| bounds.width());
+        let ranges = break_lines(text, max_width, &widths);
+
+        let line_step = line_height + options.line_spacing;
+        let height = (ranges.len() as i32 - 1) * line_step + font_height;
+        let top = bounds.y()
+            + offset(
+                options.vertical_offset(),
+                bounds.height(),
+                height.max(0) as u32,
+            );
+
+        let mut lines = Vec::with_capacity(ranges.len());
+        for (idx, range) in ranges.iter().enumerate() {
+            let line = &text[range.start..range.end];
+            let width = widths[range.start..range.end].iter().sum();
+            let justify = options.align == Align::Justify && !range.last;
+            let gaps = line.matches(' ').count() as u32;
+            let (left, extra) = if justify && gaps > 0 {
+                (bounds.x(), bounds.width().saturating_sub(width))
+            } else {
+                let x = bounds.x() + offset(options.align_offset(), bounds.width(), width);
+                (x, 0)
+            };
+            let y = top + idx as i32 * line_step;
+
+            let mut glyphs = Vec::with_capacity(line.len());
+            let mut gap = 0;
+            // Width of the line up to the glyph
+            let mut x = 0;
+            for (byte, ch) in line.char_indices() {
+                let next = x + widths[range.start + byte];
+                // Spaces take up their share of the extra width of justified lines
+                let shift = (extra * gap / gaps.max(1)) as i32;
+                if ch == ' ' {
+                    gap += 1;
+                }
+                let end = next as i32 + (extra * gap / gaps.max(1)) as i32;
+                let glyph_x = left + x as i32 + shift;
+                glyphs.push(PositionedGlyph {
+                    index: range.start + byte,
+                    ch,
+                    rect: Rect::new(
+                        glyph_x,
+                        y,
+                        (left + end - glyph_x).max(1) as u32,
+                        font_height.max(1) as u32,
+                    ),
+                });
+                x = next;
+            }
+            lines.push(LayoutLine {
+                text: line.to_string(),
+                rect: Rect::new(left, y, (width + extra).max(1), font_height.max(1) as u32),
+                glyphs,
+            });
+        }
+
+        let rect = lines
+            .iter()
+            .map(|line| line.rect)
+            .reduce(|a, b| a.union(b))
+            .unwrap_or_else(|| Rect::new(bounds.x(), top, 1, 1));
+        Ok(TextLayout { lines, rect })
+    }
+
+    pub fn lines(&self) -> &[LayoutLine] {
+        &self.lines
+    }
+
+    // Bounding box of all lines, lines are at least 1 pixel wide
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn glyphs(&self) -> impl Iterator<Item = &PositionedGlyph> {
+        self.lines.iter().flat_map(|line| line.glyphs.iter())
+    }
+
+    // The glyph at the given point, i.e. the one that was clicked
+    pub fn hit_test(&self, x: i32, y: i32) -> Option<&PositionedGlyph> {
+        self.glyphs()
+            .find(|glyph| glyph.rect.contains_point((x, y)))
+    }
+}
+
+// Width of each character of the text at the byte offset it starts at, 0 for the other bytes and
+// newlines. Characters are measured one at a time so laying out takes time linear in the length
+// of the text, which leaves out kerning between them.
+fn char_widths<M>(text: &str, measure: &M) -> Result<Vec<u32>, Error>
+where
+    M: Fn(&str) -> Result<u32, Error>,
+{
+    let mut widths = vec![0; text.len()];
+    let mut buf = [0; 4];
+    for (byte, ch) in text.char_indices() {
+        if ch != '\n' {
+            widths[byte] = measure(ch.encode_utf8(&mut buf))?;
+        }
+    }
+    Ok(widths)
+}
+
+fn break_lines(text: &str, max_width: u32, widths: &[u32]) -> Vec<LineRange> {
+    let width_of = |start: usize, end: usize| widths[start..end].iter().sum::<u32>();
+    let mut lines = Vec::new();
+    let mut paragraph_start = 0;
+    for paragraph in text.split('\n') {
+        let mut start = paragraph_start;
+        // End of the last word that fit, equal to start while the line is still empty
+        let mut end = start;
+        // Width of the line up to end
+        let mut width = 0;
+        for (word_offset, word) in words(paragraph) {
+            let word_start = paragraph_start + word_offset;
+            let word_end = word_start + word.len();
+            // Includes the spaces in front of the word
+            let with_word = width + width_of(end, word_end);
+            if end > start && with_word <= max_width {
+                end = word_end;
+                width = with_word;
+                continue;
+            }
+            if end > start {
+                lines.push(LineRange {
+                    start,
+                    end,
+                    last: false,
+                });
+                start = word_start;
+            }
+            // Keeps the indentation of the paragraph's first line
+            end = start;
+            width = width_of(start, word_start);
+            for (char_offset, ch) in word.char_indices() {
+                let char_start = word_start + char_offset;
+                if end > start && width + widths[char_start] > max_width {
+                    lines.push(LineRange {
+                        start,
+                        end,
+                        last: false,
+                    });
+                    start = end;
+                    width = 0;
+                }
+                width += widths[char_start];
+                end = char_start + ch.len_utf8();
+            }
+        }
+        lines.push(LineRange {
+            start,
+            end,
+            last: true,
+        });
+        paragraph_start += paragraph.len() + 1;
+    }
+    lines
+}
+
+// Words of the paragraph with their byte offset
+fn words(paragraph: &str) -> impl Iterator<Item = (usize, &str)> {
+    paragraph
+        .split(' ')
+        .scan(0, |offset, word| {
+            let start = *offset;
+            *offset += word.len() + 1;
+            Some((start, word))
+        })
+        .filter(|(_, word)| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn bounds() -> Rect {
+        Rect::new(0, 0, 100, 100)
+    }
+
+    // Every character is 10 pixels wide, lines are 10 pixels high with 2 pixels in between
+    fn layout(text: &str, options: &LayoutOptions) -> TextLayout {
+        TextLayout::with_measure(text, bounds(), options, 10, 12, |text| {
+            Ok(text.chars().count() as u32 * 10)
+        })
+        .unwrap()
+    }
+
+    fn lines(layout: &TextLayout) -> Vec<&str> {
+        layout
+            .lines()
+            .iter()
+            .map(|line| line.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn breaks_between_words() {
+        let layout = layout("aaa bb  cc dddd", &LayoutOptions::new().max_width(60));
+        assert_eq!(lines(&layout), ["aaa bb", "cc", "dddd"]);
+        let tops: Vec<_> = layout.lines().iter().map(|line| line.rect.y()).collect();
+        assert_eq!(tops, [0, 12, 24]);
+        assert_eq!(layout.rect(), Rect::new(0, 0, 60, 34));
+    }
+
+    #[test]
+    fn breaks_long_words_and_paragraphs() {
+        let layout = layout("  abcdefgh\n\nab  cd", &LayoutOptions::new().max_width(40));
+        assert_eq!(lines(&layout), ["  ab", "cdef", "gh", "", "ab", "cd"]);
+        let layout = self::layout("abc", &LayoutOptions::new().max_width(0));
+        assert_eq!(lines(&layout), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn measures_each_character_once() {
+        let calls = Cell::new(0);
+        let text = "The quick brown fox\njumps over the lazy dog";
+        let layout = TextLayout::with_measure(
+            text,
+            bounds(),
+            &LayoutOptions::new().max_width(50).align(Align::Justify),
+            10,
+            12,
+            |text| {
+                assert_eq!(text.chars().count(), 1);
+                calls.set(calls.get() + 1);
+                Ok(10)
+            },
+        )
+        .unwrap();
+        assert!(layout.lines().len() > 4);
+        assert_eq!(calls.get(), text.len() - 1);
+    }
+
+    #[test]
+    fn measure_errors() {
+        let result =
+            TextLayout::with_measure("ab", bounds(), &LayoutOptions::new(), 10, 12, |_| {
+                Err(Error::GlyphCache {
+                    ch: None,
+                    message: "unable to measure".to_string(),
+                })
+            });
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn glyph_positions() {
+        let xs = |layout: &TextLayout| -> Vec<i32> {
+            layout.glyphs().map(|glyph| glyph.rect.x()).collect()
+        };
+        let left = layout("ab c", &LayoutOptions::new());
+        assert_eq!(xs(&left), [0, 10, 20, 30]);
+        let glyph = left.glyphs().nth(3).unwrap();
+        assert_eq!((glyph.index, glyph.ch), (3, 'c'));
+        assert_eq!(glyph.rect, Rect::new(30, 0, 10, 10));
+
+        let centered = layout("ab c", &LayoutOptions::new().align(Align::Center));
+        assert_eq!(xs(&centered), [30, 40, 50, 60]);
+        let right = layout(
+            "ab c",
+            &LayoutOptions::new()
+                .align(Align::Right)
+                .vertical_align(VerticalAlign::Bottom),
+        );
+        assert_eq!(xs(&right), [60, 70, 80, 90]);
+        assert_eq!(right.rect(), Rect::new(60, 90, 40, 10));
+    }
+
+    #[test]
+    fn justify() {
+        let layout = layout("aa bb cc dd", &LayoutOptions::new().align(Align::Justify));
+        assert_eq!(lines(&layout), ["aa bb cc", "dd"]);
+        let first = &layout.lines()[0];
+        assert_eq!(first.rect, Rect::new(0, 0, 100, 10));
+        let xs: Vec<_> = first.glyphs.iter().map(|glyph| glyph.rect.x()).collect();
+        assert_eq!(xs, [0, 10, 20, 40, 50, 60, 80, 90]);
+        // Spaces stretch to meet the next word
+        assert_eq!(first.glyphs[2].rect.width(), 20);
+        // The last line of a paragraph stays left aligned
+        assert_eq!(layout.lines()[1].rect, Rect::new(0, 12, 20, 10));
+    }
+
+    #[test]
+    fn hit_test() {
+        let layout = layout("ab\ncd", &LayoutOptions::new());
+        let hit = |x, y| layout.hit_test(x, y).map(|glyph| glyph.ch);
+        assert_eq!(hit(15, 5), Some('b'));
+        assert_eq!(hit(5, 15), Some('c'));
+        assert_eq!(hit(25, 5), None);
+        assert_eq!(hit(5, 11), None);
+    }
+}
@@ -0,0 +1,68 @@
+mod animation;
+mod assets;
+mod atlas;
+#[cfg(feature = "image")]
+mod bitmap_font;
+mod button;
+mod cursor;
+mod draw;
+mod error;
+mod event_source;
+#[cfg(feature = "ttf")]
+mod font_stack;
+mod game;
+mod gamepad;
+#[cfg(feature = "ttf")]
+mod glyph_cache;
+mod input_map;
+mod input_state;
+mod layout;
+mod recording;
+mod renderer;
+#[cfg(feature = "ttf")]
+mod rich_text;
+mod rumble;
+#[cfg(feature = "image")]
+pub mod snapshot;
+mod sprite;
+pub mod synthetic;
+#[cfg(feature = "ttf")]
+mod text;
+#[cfg(feature = "ttf")]
+mod text_input;
+mod text_renderer;
+
+pub use animation::{Animation, AnimationEvent, AnimationFrame, Playback};
+pub use assets::Assets;
+pub use atlas::{Atlas, Frame};
+#[cfg(feature = "image")]
+pub use bitmap_font::{BitmapFont, BitmapGlyph};
+pub use button::{Button, ButtonSkin, ButtonState};
+pub use cursor::Cursors;
+pub use draw::DrawParams;
+pub use error::Error;
+pub use event_source::{EventSource, LiveEvents, Recorder, Replay, ScriptedEvents};
+#[cfg(feature = "ttf")]
+pub use font_stack::{FontRun, FontStack};
+pub use game::{Game, GameLoop};
+pub use gamepad::{Gamepad, GamepadBackend, Gamepads, SdlGamepadBackend, DEFAULT_DEAD_ZONE};
+#[cfg(feature = "ttf")]
+pub use glyph_cache::GlyphCache;
+pub use input_map::{ActionEvent, AxisDirection, Binding, InputMap, AXIS_PRESS_THRESHOLD};
+pub use input_state::InputState;
+pub use layout::{Align, LayoutLine, LayoutOptions, PositionedGlyph, TextLayout, VerticalAlign};
+pub use recording::{RecordedFrame, Recording};
+pub use renderer::{init_renderer, Renderer, RendererConfig};
+#[cfg(feature = "ttf")]
+pub use rich_text::{RichText, RichTextLayout, Span, SpanStyle, StyledRun};
+pub use rumble::{
+    RecordingRumble, Rumble, RumbleBackend, RumbleCall, RumbleEffect, SdlRumbleBackend,
+};
+pub use sprite::{load_media, Clip, Flip, Sprite};
+#[cfg(feature = "image")]
+pub use sprite::{LoadOptions, DEFAULT_COLOR_KEY};
+#[cfg(feature = "ttf")]
+pub use text::{FontHinting, FontOptions, TextMode};
+#[cfg(feature = "ttf")]
+pub use text_input::TextInput;
+pub use text_renderer::TextRenderer;
@@ -0,0 +1,723 @@
+use crate::error::Error;
+use sdl2::controller::{Axis, Button};
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+const HEADER: &str = "ama-sdl2-recording";
+const VERSION: u32 = 1;
+
+// Events a frame of the game loop received and how many fixed updates it ran
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedFrame {
+    pub frame: u64,
+    pub updates: u32,
+    pub events: Vec<Event>,
+}
+
+// Input of a run of the game loop to replay it, see `Recorder` and `Replay`.
+//
+// Only frames that received events or didn't run exactly one update are kept. Keyboard, text,
+// mouse, window, gamepad and quit events are recorded, everything else is dropped.
+// Saved as text with a line per frame followed by a line per event of the frame:
+//
+// ```text
+// ama-sdl2-recording 1 <frame count>
+// frame <frame> <updates>
+// <timestamp> <kind> <fields>
+// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recording {
+    frame_count: u64,
+    frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Recording::default()
+    }
+
+    // Frames need to be pushed in order
+    pub fn push(&mut self, frame: RecordedFrame) {
+        self.frame_count = self.frame_count.max(frame.frame + 1);
+        self.frames.push(frame);
+    }
+
+    pub fn frames(&self) -> &[RecordedFrame] {
+        &self.frames
+    }
+
+    // Number of frames the recorded run lasted, including the ones without events
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    // Fills in the updates of the last frame, dropping it if it is an ordinary frame
+    pub(crate) fn finish_frame(&mut self, updates: u32) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.updates = updates;
+            if frame.events.is_empty() && updates == 1 {
+                self.frames.pop();
+            }
+        }
+    }
+
+    pub fn is_recorded(event: &Event) -> bool {
+        encode_event(event).is_some()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let src = fs::read_to_string(path).map_err(|err| {
+            if !path.exists() {
+                Error::MissingAsset {
+                    path: path.to_path_buf(),
+                }
+            } else {
+                Error::io(path, err)
+            }
+        })?;
+        Recording::parse(&src).map_err(|message| Error::Recording {
+            path: Some(path.to_path_buf()),
+            message,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        fs::write(path, self.to_text()).map_err(|err| Error::io(path, err))
+    }
+
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut lines = src
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (idx, header) = lines.next().ok_or("empty recording")?;
+        let header_error = |err: String| format!("line {}: {}", idx + 1, err);
+        let mut fields = Fields::new(header);
+        if fields.word().map_err(header_error)? != HEADER {
+            return Err(header_error(format!("expected {:?} header", HEADER)));
+        }
+        let version: u32 = fields.next().map_err(header_error)?;
+        if version != VERSION {
+            return Err(header_error(format!("unsupported version {}", version)));
+        }
+        let frame_count = fields.next().map_err(header_error)?;
+
+        let mut recording = Recording::new();
+        let mut current: Option<RecordedFrame> = None;
+        for (idx, line) in lines {
+            let line_error = |err: String| format!("line {}: {}", idx + 1, err);
+            let mut fields = Fields::new(line);
+            if line.starts_with("frame ") {
+                fields.word()?;
+                let frame = RecordedFrame {
+                    frame: fields.next().map_err(line_error)?,
+                    updates: fields.next().map_err(line_error)?,
+                    events: Vec::new(),
+                };
+                // Replays look frames up in order, so they must strictly increase
+                if let Some(previous) = current.as_ref().filter(|prev| prev.frame >= frame.frame) {
+                    return Err(line_error(format!(
+                        "frame {} follows frame {}",
+                        frame.frame, previous.frame
+                    )));
+                }
+                if let Some(previous) = current.replace(frame) {
+                    recording.push(previous);
+                }
+            } else {
+                let frame = current
+                    .as_mut()
+                    .ok_or_else(|| line_error("event outside of a frame".to_string()))?;
+                frame
+                    .events
+                    .push(decode_event(&mut fields).map_err(line_error)?);
+            }
+        }
+        recording.frames.extend(current);
+        recording.frame_count = recording.frame_count.max(frame_count);
+        Ok(recording)
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} {} {}\n", HEADER, VERSION, self.frame_count);
+        for frame in &self.frames {
+            let _ = writeln!(text, "frame {} {}", frame.frame, frame.updates);
+            for line in frame.events.iter().filter_map(encode_event) {
+                text.push_str(&line);
+                text.push('\n');
+            }
+        }
+        text
+    }
+}
+
+fn encode_event(event: &Event) -> Option<String> {
+    let line = match event {
+        Event::Quit { timestamp } => format!("{} quit", timestamp),
+        Event::Window {
+            timestamp,
+            window_id,
+            win_event,
+        } => {
+            let (name, data1, data2) = encode_window_event(win_event);
+            format!(
+                "{} window {} {} {} {}",
+                timestamp, window_id, name, data1, data2
+            )
+        }
+        Event::KeyDown {
+            timestamp,
+            window_id,
+            keycode,
+            scancode,
+            keymod,
+            repeat,
+        }
+        | Event::KeyUp {
+            timestamp,
+            window_id,
+            keycode,
+            scancode,
+            keymod,
+            repeat,
+        } => {
+            let kind = match event {
+                Event::KeyDown { .. } => "keydown",
+                _ => "keyup",
+            };
+            format!(
+                "{} {} {} {} {} {} {}",
+                timestamp,
+                kind,
+                window_id,
+                keycode.map_or(-1, |keycode| keycode as i32),
+                scancode.map_or(-1, |scancode| scancode as i32),
+                keymod.bits(),
+                *repeat as u8
+            )
+        }
+        Event::TextEditing {
+            timestamp,
+            window_id,
+            text,
+            start,
+            length,
+        } => format!(
+            "{} textediting {} {} {} {}",
+            timestamp,
+            window_id,
+            start,
+            length,
+            quote(text)
+        ),
+        Event::TextInput {
+            timestamp,
+            window_id,
+            text,
+        } => format!("{} textinput {} {}", timestamp, window_id, quote(text)),
+        Event::MouseMotion {
+            timestamp,
+            window_id,
+            which,
+            mousestate,
+            x,
+            y,
+            xrel,
+            yrel,
+        } => format!(
+            "{} mousemotion {} {} {} {} {} {} {}",
+            timestamp,
+            window_id,
+            which,
+            mousestate.to_sdl_state(),
+            x,
+            y,
+            xrel,
+            yrel
+        ),
+        Event::MouseButtonDown {
+            timestamp,
+            window_id,
+            which,
+            mouse_btn,
+            clicks,
+            x,
+            y,
+        }
+        | Event::MouseButtonUp {
+            timestamp,
+            window_id,
+            which,
+            mouse_btn,
+            clicks,
+            x,
+            y,
+        } => {
+            let kind = match event {
+                Event::MouseButtonDown { .. } => "mousedown",
+                _ => "mouseup",
+            };
+            format!(
+                "{} {} {} {} {} {} {} {}",
+                timestamp, kind, window_id, which, *mouse_btn as u8, clicks, x, y
+            )
+        }
+        Event::MouseWheel {
+            timestamp,
+            window_id,
+            which,
+            x,
+            y,
+            direction,
+        } => format!(
+            "{} mousewheel {} {} {} {} {}",
+            timestamp,
+            window_id,
+            which,
+            x,
+            y,
+            direction.to_ll()
+        ),
+        Event::ControllerAxisMotion {
+            timestamp,
+            which,
+            axis,
+            value,
+        } => format!(
+            "{} controlleraxis {} {} {}",
+            timestamp,
+            which,
+            axis.string(),
+            value
+        ),
+        Event::ControllerButtonDown {
+            timestamp,
+            which,
+            button,
+        } => format!("{} controllerdown {} {}", timestamp, which, button.string()),
+        Event::ControllerButtonUp {
+            timestamp,
+            which,
+            button,
+        } => format!("{} controllerup {} {}", timestamp, which, button.string()),
+        Event::ControllerDeviceAdded { timestamp, which } => {
+            format!("{} controlleradded {}", timestamp, which)
+        }
+        Event::ControllerDeviceRemoved { timestamp, which } => {
+            format!("{} controllerremoved {}", timestamp, which)
+        }
+        Event::ControllerDeviceRemapped { timestamp, which } => {
+            format!("{} controllerremapped {}", timestamp, which)
+        }
+        _ => return None,
+    };
+    Some(line)
+}
+
+fn decode_event(fields: &mut Fields) -> Result<Event, String> {
+    let timestamp = fields.next()?;
+    let kind = fields.word()?;
+    let event = match kind {
+        "quit" => Event::Quit { timestamp },
+        "window" => {
+            let window_id = fields.next()?;
+            let name = fields.word()?;
+            let win_event = decode_window_event(name, fields.next()?, fields.next()?)?;
+            Event::Window {
+                timestamp,
+                window_id,
+                win_event,
+            }
+        }
+        "keydown" | "keyup" => {
+            let window_id = fields.next()?;
+            let keycode = Keycode::from_i32(fields.next()?);
+            let scancode = Scancode::from_i32(fields.next()?);
+            let keymod = Mod::from_bits_truncate(fields.next()?);
+            let repeat = fields.next::<u8>()? != 0;
+            if kind == "keydown" {
+                Event::KeyDown {
+                    timestamp,
+                    window_id,
+                    keycode,
+                    scancode,
+                    keymod,
+                    repeat,
+                }
+            } else {
+                Event::KeyUp {
+                    timestamp,
+                    window_id,
+                    keycode,
+                    scancode,
+                    keymod,
+                    repeat,
+                }
+            }
+        }
+        "textediting" => Event::TextEditing {
+            timestamp,
+            window_id: fields.next()?,
+            start: fields.next()?,
+            length: fields.next()?,
+            text: fields.quoted()?,
+        },
+        "textinput" => Event::TextInput {
+            timestamp,
+            window_id: fields.next()?,
+            text: fields.quoted()?,
+        },
+        "mousemotion" => Event::MouseMotion {
+            timestamp,
+            window_id: fields.next()?,
+            which: fields.next()?,
+            mousestate: MouseState::from_sdl_state(fields.next()?),
+            x: fields.next()?,
+            y: fields.next()?,
+            xrel: fields.next()?,
+            yrel: fields.next()?,
+        },
+        "mousedown" | "mouseup" => {
+            let window_id = fields.next()?;
+            let which = fields.next()?;
+            let mouse_btn = MouseButton::from_ll(fields.next()?);
+            let clicks = fields.next()?;
+            let x = fields.next()?;
+            let y = fields.next()?;
+            if kind == "mousedown" {
+                Event::MouseButtonDown {
+                    timestamp,
+                    window_id,
+                    which,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            } else {
+                Event::MouseButtonUp {
+                    timestamp,
+                    window_id,
+                    which,
+                    mouse_btn,
+                    clicks,
+                    x,
+                    y,
+                }
+            }
+        }
+        "mousewheel" => Event::MouseWheel {
+            timestamp,
+            window_id: fields.next()?,
+            which: fields.next()?,
+            x: fields.next()?,
+            y: fields.next()?,
+            direction: MouseWheelDirection::from_ll(fields.next()?),
+        },
+        "controlleraxis" => {
+            let which = fields.next()?;
+            let name = fields.word()?;
+            Event::ControllerAxisMotion {
+                timestamp,
+                which,
+                axis: Axis::from_string(name).ok_or_else(|| format!("unknown axis {:?}", name))?,
+                value: fields.next()?,
+            }
+        }
+        "controllerdown" | "controllerup" => {
+            let which = fields.next()?;
+            let name = fields.word()?;
+            let button =
+                Button::from_string(name).ok_or_else(|| format!("unknown button {:?}", name))?;
+            if kind == "controllerdown" {
+                Event::ControllerButtonDown {
+                    timestamp,
+                    which,
+                    button,
+                }
+            } else {
+                Event::ControllerButtonUp {
+                    timestamp,
+                    which,
+                    button,
+                }
+            }
+        }
+        "controlleradded" => Event::ControllerDeviceAdded {
+            timestamp,
+            which: fields.next()?,
+        },
+        "controllerremoved" => Event::ControllerDeviceRemoved {
+            timestamp,
+            which: fields.next()?,
+        },
+        "controllerremapped" => Event::ControllerDeviceRemapped {
+            timestamp,
+            which: fields.next()?,
+        },
+        _ => return Err(format!("unknown event {:?}", kind)),
+    };
+    Ok(event)
+}
+
+fn encode_window_event(win_event: &WindowEvent) -> (&'static str, i32, i32) {
+    match *win_event {
+        WindowEvent::None => ("none", 0, 0),
+        WindowEvent::Shown => ("shown", 0, 0),
+        WindowEvent::Hidden => ("hidden", 0, 0),
+        WindowEvent::Exposed => ("exposed", 0, 0),
+        WindowEvent::Moved(x, y) => ("moved", x, y),
+        WindowEvent::Resized(width, height) => ("resized", width, height),
+        WindowEvent::SizeChanged(width, height) => ("sizechanged", width, height),
+        WindowEvent::Minimized => ("minimized", 0, 0),
+        WindowEvent::Maximized => ("maximized", 0, 0),
+        WindowEvent::Restored => ("restored", 0, 0),
+        WindowEvent::Enter => ("enter", 0, 0),
+        WindowEvent::Leave => ("leave", 0, 0),
+        WindowEvent::FocusGained => ("focusgained", 0, 0),
+        WindowEvent::FocusLost => ("focuslost", 0, 0),
+        WindowEvent::Close => ("close", 0, 0),
+        WindowEvent::TakeFocus => ("takefocus", 0, 0),
+        WindowEvent::HitTest => ("hittest", 0, 0),
+    }
+}
+
+fn decode_window_event(name: &str, data1: i32, data2: i32) -> Result<WindowEvent, String> {
+    let win_event = match name {
+        "none" => WindowEvent::None,
+        "shown" => WindowEvent::Shown,
+        "hidden" => WindowEvent::Hidden,
+        "exposed" => WindowEvent::Exposed,
+        "moved" => WindowEvent::Moved(data1, data2),
+        "resized" => WindowEvent::Resized(data1, data2),
+        "sizechanged" => WindowEvent::SizeChanged(data1, data2),
+        "minimized" => WindowEvent::Minimized,
+        "maximized" => WindowEvent::Maximized,
+        "restored" => WindowEvent::Restored,
+        "enter" => WindowEvent::Enter,
+        "leave" => WindowEvent::Leave,
+        "focusgained" => WindowEvent::FocusGained,
+        "focuslost" => WindowEvent::FocusLost,
+        "close" => WindowEvent::Close,
+        "takefocus" => WindowEvent::TakeFocus,
+        "hittest" => WindowEvent::HitTest,
+        _ => return Err(format!("unknown window event {:?}", name)),
+    };
+    Ok(win_event)
+}
+
+// Text is written as a JSON string so spaces and line breaks stay on the event's line
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+// Space separated fields of a line, where a quoted text field takes up the rest of the line
+struct Fields<'l> {
+    rest: &'l str,
+}
+
+impl<'l> Fields<'l> {
+    fn new(line: &'l str) -> Self {
+        Fields { rest: line.trim() }
+    }
+
+    fn word(&mut self) -> Result<&'l str, String> {
+        if self.rest.is_empty() {
+            return Err("missing field".to_string());
+        }
+        let (word, rest) = self.rest.split_once(' ').unwrap_or((self.rest, ""));
+        self.rest = rest.trim_start();
+        Ok(word)
+    }
+
+    fn next<T: FromStr>(&mut self) -> Result<T, String> {
+        let word = self.word()?;
+        word.parse()
+            .map_err(|_| format!("invalid field {:?}", word))
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let text = serde_json::from_str(self.rest)
+            .map_err(|err| format!("invalid text {}: {}", self.rest, err))?;
+        self.rest = "";
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn src() -> String {
+        format!(
+            "ama-sdl2-recording 1 10
+frame 0 1
+120 keydown 1 {q} {q_scancode} 0 0
+frame 2 3
+150 keyup 1 {q} {q_scancode} 0 0
+155 textinput 1 \"Crème brûlée\"
+frame 4 0
+frame 5 2
+160 mousedown 1 0 1 1 10 20
+",
+            q = Keycode::Q as i32,
+            q_scancode = Scancode::Q as i32
+        )
+    }
+
+    #[test]
+    fn text() {
+        let src = src();
+        let recording = Recording::parse(&src).unwrap();
+        assert_eq!(recording.frame_count(), 10);
+        assert_eq!(recording.frames().len(), 4);
+        assert_eq!(recording.frames()[1].updates, 3);
+        assert_eq!(
+            recording.frames()[1].events[1],
+            Event::TextInput {
+                timestamp: 155,
+                window_id: 1,
+                text: "Crème brûlée".to_string(),
+            }
+        );
+        assert_eq!(recording.to_text(), src);
+    }
+
+    #[test]
+    fn round_trip() {
+        // Every kind of recorded event survives the round trip
+        let events = vec![
+            Event::Quit { timestamp: 1 },
+            Event::Window {
+                timestamp: 2,
+                window_id: 1,
+                win_event: WindowEvent::Resized(800, 600),
+            },
+            Event::KeyDown {
+                timestamp: 3,
+                window_id: 1,
+                keycode: None,
+                scancode: Some(Scancode::W),
+                keymod: Mod::LSHIFTMOD | Mod::CAPSMOD,
+                repeat: true,
+            },
+            Event::TextEditing {
+                timestamp: 4,
+                window_id: 1,
+                text: "line\nbreak \"quoted\"".to_string(),
+                start: 2,
+                length: 3,
+            },
+            Event::MouseMotion {
+                timestamp: 5,
+                window_id: 1,
+                which: 0,
+                mousestate: MouseState::from_sdl_state(1),
+                x: 10,
+                y: 20,
+                xrel: -3,
+                yrel: 4,
+            },
+            Event::MouseButtonUp {
+                timestamp: 6,
+                window_id: 1,
+                which: 0,
+                mouse_btn: MouseButton::Right,
+                clicks: 2,
+                x: 10,
+                y: 20,
+            },
+            Event::MouseWheel {
+                timestamp: 7,
+                window_id: 1,
+                which: 0,
+                x: 0,
+                y: -1,
+                direction: MouseWheelDirection::Flipped,
+            },
+            Event::ControllerAxisMotion {
+                timestamp: 8,
+                which: 3,
+                axis: Axis::TriggerLeft,
+                value: 1234,
+            },
+            Event::ControllerButtonDown {
+                timestamp: 9,
+                which: 3,
+                button: Button::DPadUp,
+            },
+            Event::ControllerDeviceRemoved {
+                timestamp: 10,
+                which: 3,
+            },
+        ];
+        assert!(events.iter().all(Recording::is_recorded));
+        let mut recording = Recording::new();
+        recording.push(RecordedFrame {
+            frame: 3,
+            updates: 2,
+            events,
+        });
+        assert_eq!(recording.frame_count(), 4);
+        assert_eq!(Recording::parse(&recording.to_text()), Ok(recording));
+    }
+
+    #[test]
+    fn finish_frame() {
+        let mut recording = Recording::new();
+        recording.push(RecordedFrame {
+            frame: 0,
+            updates: 0,
+            events: Vec::new(),
+        });
+        recording.finish_frame(1);
+        assert!(recording.frames().is_empty());
+        assert_eq!(recording.frame_count(), 1);
+    }
+
+    #[test]
+    fn malformed_lines() {
+        let malformed = |line: &str| {
+            let src = format!("ama-sdl2-recording 1 10\n\nframe 0 1\n{}\n", line);
+            Recording::parse(&src).unwrap_err()
+        };
+        // Blank lines still count towards the line numbers
+        assert_eq!(
+            malformed("120 jump 1"),
+            "line 4: unknown event \"jump\"".to_string()
+        );
+        assert_eq!(
+            malformed("120 mousedown 1 0 one 1 10 20"),
+            "line 4: invalid field \"one\"".to_string()
+        );
+        assert_eq!(malformed("frame 1"), "line 4: missing field".to_string());
+        assert!(malformed("130 textinput 1 \"unterminated").starts_with("line 4: invalid text"));
+
+        assert_eq!(
+            Recording::parse("ama-sdl2-recording 1 1\n120 quit\n"),
+            Err("line 2: event outside of a frame".to_string())
+        );
+        assert_eq!(
+            Recording::parse("ama-sdl2-recording 2 1\n"),
+            Err("line 1: unsupported version 2".to_string())
+        );
+        assert!(Recording::parse("recording 1 1\n").is_err());
+
+        let frames = |src: &str| Recording::parse(&format!("ama-sdl2-recording 1 10\n{}", src));
+        assert_eq!(
+            frames("frame 2 1\nframe 2 1\n"),
+            Err("line 3: frame 2 follows frame 2".to_string())
+        );
+        assert_eq!(
+            frames("frame 3 1\nframe 5 1\nframe 4 1\n"),
+            Err("line 4: frame 4 follows frame 5".to_string())
+        );
+        assert!(frames("frame 0 1\nframe 1 1\nframe 7 2\n").is_ok());
+        assert!(Recording::parse("").is_err());
+    }
+}
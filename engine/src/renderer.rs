@@ -0,0 +1,492 @@
+use crate::error::Error;
+use sdl2::render::WindowCanvas;
+use sdl2::Sdl;
+use std::env;
+
+// Environment variables which override the config, i.e. `AMA_SDL_HEADLESS=1 cargo run --bin ..`
+const ENV_VIDEO_DRIVER: &str = "AMA_SDL_VIDEO_DRIVER";
+const ENV_HEADLESS: &str = "AMA_SDL_HEADLESS";
+const ENV_SOFTWARE: &str = "AMA_SDL_SOFTWARE";
+const ENV_HIDDEN: &str = "AMA_SDL_HIDDEN";
+const ENV_VSYNC: &str = "AMA_SDL_VSYNC";
+const ENV_RESIZABLE: &str = "AMA_SDL_RESIZABLE";
+const ENV_SIZE: &str = "AMA_SDL_SIZE";
+
+// Creates window and canvas described by the config after applying overrides passed via
+// `AMA_SDL_*` env vars or flags, i.e. `cargo run --bin 03_event_driven -- --headless`
+pub fn init_renderer(config: RendererConfig) -> Result<Renderer, Error> {
+    config.with_env_and_args()?.init()
+}
+
+// What `RendererConfig::init` created.
+// The window is still accessible via `canvas.window()` since it 'owns' it.
+pub struct Renderer {
+    pub sdl_context: Sdl,
+    pub canvas: WindowCanvas,
+    // Why no accelerated renderer could be created if it fell back to the software one
+    pub fallback: Option<Error>,
+}
+
+// Describes how the window and canvas are created.
+// The defaults match what the lessons used all along, an accelerated renderer with vsync.
+#[derive(Clone, Debug)]
+pub struct RendererConfig {
+    title: String,
+    width: u32,
+    height: u32,
+    resizable: bool,
+    hidden: bool,
+    accelerated: bool,
+    software_fallback: bool,
+    vsync: bool,
+    video_driver: Option<String>,
+    hints: Vec<(String, String)>,
+}
+
+impl RendererConfig {
+    pub fn new(title: &str, width: u32, height: u32) -> Self {
+        RendererConfig {
+            title: title.to_string(),
+            width,
+            height,
+            resizable: false,
+            hidden: false,
+            accelerated: true,
+            software_fallback: true,
+            vsync: true,
+            video_driver: None,
+            hints: Vec::new(),
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    // Leverage the GPU to render
+    pub fn accelerated(mut self) -> Self {
+        self.accelerated = true;
+        self
+    }
+
+    // Enforce software rendering which works on machines without a GPU
+    pub fn software(mut self) -> Self {
+        self.accelerated = false;
+        self
+    }
+
+    // Whether to render in software on machines where no accelerated renderer is available,
+    // instead of failing. On by default.
+    pub fn software_fallback(mut self, software_fallback: bool) -> Self {
+        self.software_fallback = software_fallback;
+        self
+    }
+
+    // Synchronize renderer `present` with display refresh rate.
+    // It only has an effect in accelerated mode.
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    // Selects the SDL video driver, i.e. `dummy` or `offscreen` to run without a display.
+    // Same as setting the `SDL_VIDEODRIVER` environment variable.
+    pub fn video_driver(mut self, driver: &str) -> Self {
+        self.video_driver = Some(driver.to_string());
+        self
+    }
+
+    // Sets an SDL hint, i.e. `SDL_RENDER_SCALE_QUALITY`, before SDL is initialized.
+    pub fn hint(mut self, name: &str, value: &str) -> Self {
+        self.hints.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    // Hidden window rendered in software via the `dummy` video driver, i.e. for CI boxes
+    // without a GPU or display.
+    pub fn headless(self) -> Self {
+        self.video_driver("dummy")
+            .hidden(true)
+            .software()
+            .vsync(false)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn is_accelerated(&self) -> bool {
+        self.accelerated
+    }
+
+    // Applies overrides found in the `AMA_SDL_*` environment variables.
+    // Headless goes first so that more specific variables can override parts of it.
+    pub fn with_env(self) -> Result<Self, Error> {
+        self.with_vars(|key| env::var(key).ok())
+    }
+
+    // Same as `with_env` with the variables looked up via `var`
+    fn with_vars<F>(mut self, var: F) -> Result<Self, Error>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let var_bool = |key| var(key).map(|value| parse_bool(key, &value)).transpose();
+        if let Some(headless) = var_bool(ENV_HEADLESS)? {
+            if headless {
+                self = self.headless();
+            }
+        }
+        if let Some(driver) = var(ENV_VIDEO_DRIVER) {
+            self = self.video_driver(&driver);
+        }
+        if let Some(software) = var_bool(ENV_SOFTWARE)? {
+            self = if software {
+                self.software()
+            } else {
+                self.accelerated()
+            };
+        }
+        if let Some(hidden) = var_bool(ENV_HIDDEN)? {
+            self = self.hidden(hidden);
+        }
+        if let Some(vsync) = var_bool(ENV_VSYNC)? {
+            self = self.vsync(vsync);
+        }
+        if let Some(resizable) = var_bool(ENV_RESIZABLE)? {
+            self = self.resizable(resizable);
+        }
+        if let Some(size) = var(ENV_SIZE) {
+            let (width, height) = parse_size(ENV_SIZE, &size)?;
+            self = self.size(width, height);
+        }
+        Ok(self)
+    }
+
+    // Applies overrides passed as command line flags.
+    // Arguments we don't know about are ignored so binaries can still parse their own.
+    //
+    // --headless | --software | --accelerated | --hidden | --vsync | --no-vsync | --resizable
+    // --video-driver <name> | --size <width>x<height> | --hint <name>=<value>
+    //
+    // Boolean flags can be turned off with an inline value, i.e. `--headless=0` or `--vsync=false`.
+    pub fn with_args<I>(mut self, args: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // Support both `--flag value` and `--flag=value`
+            let (flag, inline_value) = match arg.find('=') {
+                Some(idx) if arg.starts_with("--") => {
+                    (&arg[..idx], Some(arg[idx + 1..].to_string()))
+                }
+                _ => (arg.as_str(), None),
+            };
+            let mut value = || {
+                inline_value
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| Error::config(flag, None, "missing value"))
+            };
+            // Boolean flags never take the next argument as their value
+            let on = || {
+                inline_value
+                    .as_deref()
+                    .map_or(Ok(true), |value| parse_bool(flag, value))
+            };
+            self = match flag {
+                "--headless" if on()? => self.headless(),
+                "--headless" => self,
+                "--software" if on()? => self.software(),
+                "--software" => self.accelerated(),
+                "--accelerated" if on()? => self.accelerated(),
+                "--accelerated" => self.software(),
+                "--hidden" => self.hidden(on()?),
+                "--vsync" => self.vsync(on()?),
+                "--no-vsync" => self.vsync(!on()?),
+                "--resizable" => self.resizable(on()?),
+                "--video-driver" => self.video_driver(&value()?),
+                "--size" => {
+                    let (width, height) = parse_size(flag, &value()?)?;
+                    self.size(width, height)
+                }
+                "--hint" => {
+                    let hint = value()?;
+                    match hint.find('=') {
+                        Some(idx) => self.hint(&hint[..idx], &hint[idx + 1..]),
+                        None => {
+                            return Err(Error::config(flag, Some(&hint), "expected NAME=VALUE"))
+                        }
+                    }
+                }
+                _ => self,
+            };
+        }
+        Ok(self)
+    }
+
+    // Environment first so that flags passed to a specific run win.
+    pub fn with_env_and_args(self) -> Result<Self, Error> {
+        self.with_env()?.with_args(env::args().skip(1))
+    }
+
+    // Falls back to software rendering if enabled and the accelerated renderer can't be created,
+    // the error is passed along as `Renderer::fallback` then.
+    pub fn init(&self) -> Result<Renderer, Error> {
+        match self.create() {
+            Ok((sdl_context, canvas)) => Ok(Renderer {
+                sdl_context,
+                canvas,
+                fallback: None,
+            }),
+            Err(
+                err @ Error::Video {
+                    call: "SDL_CreateRenderer",
+                    ..
+                },
+            ) if self.accelerated && self.software_fallback => {
+                let (sdl_context, canvas) = self.clone().software().vsync(false).create()?;
+                Ok(Renderer {
+                    sdl_context,
+                    canvas,
+                    fallback: Some(err),
+                })
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn create(&self) -> Result<(Sdl, WindowCanvas), Error> {
+        // SDL picks the video driver when it initializes, so this has to happen first
+        if let Some(driver) = &self.video_driver {
+            env::set_var("SDL_VIDEODRIVER", driver);
+        }
+        for (name, value) in &self.hints {
+            sdl2::hint::set(name, value);
+        }
+
+        let sdl_context: Sdl = sdl2::init().map_err(|err| Error::sdl_init("SDL_Init", err))?;
+        let video = sdl_context
+            .video()
+            .map_err(|err| Error::sdl_init("SDL_InitSubSystem(SDL_INIT_VIDEO)", err))?;
+
+        let mut window_builder = video.window(&self.title, self.width, self.height);
+        window_builder.position_centered();
+        if self.resizable {
+            window_builder.resizable();
+        }
+        if self.hidden {
+            window_builder.hidden();
+        }
+        let window = window_builder
+            .build()
+            .map_err(|err| Error::video("SDL_CreateWindow", err))?;
+
+        let mut canvas_builder = if self.accelerated {
+            window.into_canvas().accelerated()
+        } else {
+            window.into_canvas().software()
+        };
+        if self.vsync {
+            canvas_builder = canvas_builder.present_vsync();
+        }
+        let canvas = canvas_builder
+            .build()
+            .map_err(|err| Error::video("SDL_CreateRenderer", err))?;
+        Ok((sdl_context, canvas))
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, Error> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" => Ok(true),
+        "0" | "false" | "no" | "off" => Ok(false),
+        _ => Err(Error::config(
+            key,
+            Some(value),
+            "expected a boolean, i.e. 1 or 0",
+        )),
+    }
+}
+
+fn parse_size(key: &str, value: &str) -> Result<(u32, u32), Error> {
+    let invalid = || Error::config(key, Some(value), "expected WIDTHxHEIGHT");
+    let idx = value.find('x').ok_or_else(invalid)?;
+    let width = value[..idx].parse().map_err(|_| invalid())?;
+    let height = value[idx + 1..].parse().map_err(|_| invalid())?;
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config() -> RendererConfig {
+        RendererConfig::new("Test", 640, 480)
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn with_vars(vars: &[(&str, &str)]) -> Result<RendererConfig, Error> {
+        let vars: HashMap<_, _> = vars.iter().cloned().collect();
+        config().with_vars(|key| vars.get(key).map(|value| value.to_string()))
+    }
+
+    #[test]
+    fn flags() -> Result<(), Error> {
+        let config = config().with_args(args(&[
+            "--software",
+            "--no-vsync",
+            "--size=800x600",
+            "--video-driver",
+            "offscreen",
+            "--hint",
+            "SDL_RENDER_SCALE_QUALITY=linear",
+            "--level",
+            "3",
+        ]))?;
+        assert!(!config.is_accelerated());
+        assert!(!config.vsync);
+        assert_eq!((config.width(), config.height()), (800, 600));
+        assert_eq!(config.video_driver.as_deref(), Some("offscreen"));
+        assert_eq!(
+            config.hints,
+            [("SDL_RENDER_SCALE_QUALITY".to_string(), "linear".to_string())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn headless_flag() -> Result<(), Error> {
+        let config = config().with_args(args(&["--headless", "--vsync"]))?;
+        assert_eq!(config.video_driver.as_deref(), Some("dummy"));
+        assert!(config.hidden);
+        assert!(!config.is_accelerated());
+        assert!(config.vsync, "flags after --headless override it");
+        Ok(())
+    }
+
+    #[test]
+    fn boolean_flags_with_values() -> Result<(), Error> {
+        let headless_off =
+            config().with_args(args(&["--headless=0", "--resizable=yes", "--vsync=off"]))?;
+        assert_eq!(headless_off.video_driver, None);
+        assert!(!headless_off.hidden);
+        assert!(headless_off.is_accelerated());
+        assert!(headless_off.resizable);
+        assert!(!headless_off.vsync);
+
+        let negated =
+            config().with_args(args(&["--software=false", "--no-vsync=0", "--hidden"]))?;
+        assert!(negated.is_accelerated());
+        assert!(negated.vsync);
+        assert!(negated.hidden);
+
+        // The next argument is never taken as the value
+        let next = config().with_args(args(&["--software", "0"]))?;
+        assert!(!next.is_accelerated());
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_flags() {
+        for (invalid, flag, value) in [
+            (&["--size", "800"][..], "--size", Some("800")),
+            (&["--size", "800xtall"], "--size", Some("800xtall")),
+            (&["--size=x600"], "--size", Some("x600")),
+            (
+                &["--hint", "SDL_RENDER_SCALE_QUALITY"],
+                "--hint",
+                Some("SDL_RENDER_SCALE_QUALITY"),
+            ),
+            (&["--video-driver"], "--video-driver", None),
+            (&["--headless=maybe"], "--headless", Some("maybe")),
+            (&["--vsync="], "--vsync", Some("")),
+        ]
+        .iter()
+        {
+            match config().with_args(args(invalid)) {
+                Err(Error::Config {
+                    key,
+                    value: invalid_value,
+                    ..
+                }) => {
+                    assert_eq!(&key, flag);
+                    assert_eq!(invalid_value.as_deref(), *value);
+                }
+                result => panic!("{:?} should fail, got {:?}", invalid, result.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn vars() -> Result<(), Error> {
+        let config = with_vars(&[
+            (ENV_HEADLESS, "1"),
+            (ENV_SOFTWARE, "no"),
+            (ENV_RESIZABLE, "TRUE"),
+            (ENV_SIZE, "320x240"),
+        ])?;
+        assert!(config.hidden);
+        assert_eq!(config.video_driver.as_deref(), Some("dummy"));
+        assert!(
+            config.is_accelerated(),
+            "more specific vars override headless"
+        );
+        assert!(config.resizable);
+        assert_eq!((config.width(), config.height()), (320, 240));
+
+        let config = with_vars(&[(ENV_HEADLESS, "off"), (ENV_VIDEO_DRIVER, "x11")])?;
+        assert!(!config.hidden);
+        assert_eq!(config.video_driver.as_deref(), Some("x11"));
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_vars() {
+        for (var, value) in [
+            (ENV_VSYNC, "maybe"),
+            (ENV_HIDDEN, ""),
+            (ENV_SIZE, "640*480"),
+            (ENV_SIZE, "-1x480"),
+        ]
+        .iter()
+        {
+            match with_vars(&[(*var, *value)]) {
+                Err(Error::Config {
+                    key,
+                    value: invalid_value,
+                    ..
+                }) => {
+                    assert_eq!(&key, var);
+                    assert_eq!(invalid_value.as_deref(), Some(*value));
+                }
+                result => panic!("{} should fail, got {:?}", var, result.map(|_| ())),
+            }
+        }
+    }
+}
@@ -0,0 +1,642 @@
+use crate::assets::Assets;
+use crate::error::Error;
+use crate::layout::{offset, Align, LayoutOptions};
+use crate::sprite::Sprite;
+use crate::text::FontOptions;
+use sdl2::pixels::{Color, PixelFormatEnum};
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, TextureCreator};
+use sdl2::surface::Surface;
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use std::path::Path;
+use std::rc::Rc;
+
+// Style of a span of rich text, the color is `None` outside of any `[color]` tag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SpanStyle {
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub color: Option<Color>,
+}
+
+impl SpanStyle {
+    pub fn font_options(&self) -> FontOptions {
+        FontOptions::new()
+            .bold(self.bold)
+            .italic(self.italic)
+            .underline(self.underline)
+            .strikethrough(self.strikethrough)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+// Text with inline markup, i.e. `[color=#ff0000]danger[/color] [b]bold[/b] [i]italic[/i]`.
+//
+// Supported tags are `[b]`, `[i]`, `[u]`, `[s]` and `[color=#rrggbb]` or `[color=#rrggbbaa]`.
+// Tags nest and are closed in reverse order, `[[` is a literal `[`.
+// Text outside of any color tag uses the default color, white unless changed via `color`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RichText {
+    spans: Vec<Span>,
+    color: Color,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Tag {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Color(Color),
+}
+
+impl Tag {
+    fn parse(tag: &str) -> Result<Self, String> {
+        match tag {
+            "b" => Ok(Tag::Bold),
+            "i" => Ok(Tag::Italic),
+            "u" => Ok(Tag::Underline),
+            "s" => Ok(Tag::Strikethrough),
+            _ => match tag.strip_prefix("color=") {
+                Some(color) => parse_color(color).map(Tag::Color),
+                None => Err(format!("unknown tag [{}]", tag)),
+            },
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Tag::Bold => "b",
+            Tag::Italic => "i",
+            Tag::Underline => "u",
+            Tag::Strikethrough => "s",
+            Tag::Color(_) => "color",
+        }
+    }
+}
+
+impl RichText {
+    pub fn parse(markup: &str) -> Result<Self, Error> {
+        let mut spans = Vec::new();
+        let mut text = String::new();
+        // Open tags and where they start
+        let mut open: Vec<(Tag, usize)> = Vec::new();
+        let mut rest = markup;
+        while let Some(start) = rest.find('[') {
+            text.push_str(&rest[..start]);
+            rest = &rest[start..];
+            if let Some(after) = rest.strip_prefix("[[") {
+                text.push('[');
+                rest = after;
+                continue;
+            }
+            let position = markup.len() - rest.len();
+            let invalid = |message| Error::Markup { position, message };
+            let end = rest
+                .find(']')
+                .ok_or_else(|| invalid(format!("unterminated tag {:?}", rest)))?;
+            let tag = &rest[1..end];
+            rest = &rest[end + 1..];
+
+            push_span(&mut spans, &mut text, style_of(&open));
+            match tag.strip_prefix('/') {
+                Some(name) => match open.pop() {
+                    Some((innermost, _)) if innermost.name() == name => {}
+                    Some((innermost, _)) => {
+                        return Err(invalid(format!(
+                            "[/{}] closes [{}]",
+                            name,
+                            innermost.name()
+                        )))
+                    }
+                    None => return Err(invalid(format!("[/{}] was never opened", name))),
+                },
+                None => open.push((Tag::parse(tag).map_err(invalid)?, position)),
+            }
+        }
+        text.push_str(rest);
+        push_span(&mut spans, &mut text, style_of(&open));
+
+        if let Some((tag, position)) = open.last() {
+            return Err(Error::Markup {
+                position: *position,
+                message: format!("[{}] is never closed", tag.name()),
+            });
+        }
+        Ok(RichText {
+            spans,
+            color: Color::WHITE,
+        })
+    }
+
+    // Color of text outside of any color tag
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn spans(&self) -> &[Span] {
+        &self.spans
+    }
+
+    // The text without any markup
+    pub fn text(&self) -> String {
+        self.spans.iter().map(|span| span.text.as_str()).collect()
+    }
+
+    // Breaks the text into lines of styled runs, loading the font once per style via `assets`.
+    //
+    // Lines wrap at spaces to fit the max width of the options, words are never broken even when
+    // their style changes within them. Runs of a line share a baseline. Alignment and line
+    // spacing work as for `TextLayout`, place the resulting box with `LayoutOptions::place`.
+    pub fn layout<'a, P: AsRef<Path>>(
+        &self,
+        assets: &Assets<'a>,
+        font: P,
+        point_size: u16,
+        options: &LayoutOptions,
+    ) -> Result<RichTextLayout<'a>, Error> {
+        let font = font.as_ref();
+        let base = assets.font(font, point_size)?;
+        let mut fragments = fragments(&self.spans, |style| {
+            assets.font_with_options(font, point_size, &style.font_options())
+        })?;
+        for fragment in fragments.iter_mut() {
+            if fragment.class != Class::Newline {
+                fragment.width = measure(&fragment.font, &fragment.text)?;
+            }
+        }
+
+        let lines = break_lines(fragments, options.max_width);
+        let box_width = options
+            .max_width
+            .unwrap_or_else(|| lines.iter().map(|line| line.width()).max().unwrap_or(0));
+
+        let mut runs = Vec::new();
+        let mut y = 0;
+        let mut height = 0;
+        for line in &lines {
+            let fonts = || line.fragments.iter().map(|fragment| &fragment.font);
+            // Runs share a baseline, so smaller fonts sit lower than the tallest one
+            let ascent = fonts()
+                .map(|font| font.ascent())
+                .max()
+                .unwrap_or_else(|| base.ascent());
+            let font_height = fonts()
+                .map(|font| ascent - font.ascent() + font.height())
+                .max()
+                .unwrap_or_else(|| base.height());
+            let line_height = fonts()
+                .map(|font| font.recommended_line_spacing())
+                .max()
+                .unwrap_or_else(|| base.recommended_line_spacing());
+
+            let width = line.width();
+            let justify = options.align == Align::Justify && !line.last;
+            let gaps = line
+                .fragments
+                .iter()
+                .filter(|fragment| fragment.class == Class::Space)
+                .count() as u32;
+            let (mut x, extra) = if justify && gaps > 0 {
+                (0, box_width.saturating_sub(width))
+            } else {
+                (offset(options.align_offset(), box_width, width), 0)
+            };
+
+            let mut gap = 0;
+            for fragment in &line.fragments {
+                let mut advance = fragment.width as i32;
+                if fragment.class == Class::Space {
+                    // Spaces take up their share of the extra width of justified lines
+                    advance += (extra * (gap + 1) / gaps.max(1) - extra * gap / gaps.max(1)) as i32;
+                    gap += 1;
+                }
+                let style = fragment.style;
+                // Spaces only show when underlined or struck through
+                if fragment.class == Class::Word || style.underline || style.strikethrough {
+                    runs.push(StyledRun {
+                        text: fragment.text.clone(),
+                        rect: Rect::new(
+                            x,
+                            y + ascent - fragment.font.ascent(),
+                            fragment.width.max(1),
+                            fragment.font.height().max(1) as u32,
+                        ),
+                        style,
+                        color: style.color.unwrap_or(self.color),
+                        font: Rc::clone(&fragment.font),
+                    });
+                }
+                x += advance;
+            }
+            height = y + font_height;
+            y += line_height + options.line_spacing;
+        }
+
+        Ok(RichTextLayout {
+            runs,
+            width: box_width,
+            height: height.max(0) as u32,
+        })
+    }
+}
+
+// A piece of a line drawn with one font and color
+pub struct StyledRun<'a> {
+    pub text: String,
+    // Relative to the top left of the layout
+    pub rect: Rect,
+    pub style: SpanStyle,
+    pub color: Color,
+    font: Rc<Font<'a, 'static>>,
+}
+
+impl<'a> StyledRun<'a> {
+    // Font loaded with the options of the run's style
+    pub fn font(&self) -> &Font<'a, 'static> {
+        &self.font
+    }
+}
+
+// Rich text broken into lines of styled runs, either drawn into one sprite via `to_sprite` or
+// run by run, i.e. via `Sprite::load_from_rendered_text` with the font of each run.
+pub struct RichTextLayout<'a> {
+    runs: Vec<StyledRun<'a>>,
+    width: u32,
+    height: u32,
+}
+
+impl<'a> RichTextLayout<'a> {
+    pub fn runs(&self) -> &[StyledRun<'a>] {
+        &self.runs
+    }
+
+    // The max width of the layout options if set, otherwise the width of the longest line
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    // Renders all runs into a single texture sized to the layout
+    pub fn to_sprite<'t>(
+        &self,
+        texture_creator: &'t TextureCreator<WindowContext>,
+    ) -> Result<Sprite<'t>, Error> {
+        let mut surface = Surface::new(
+            self.width.max(1),
+            self.height.max(1),
+            PixelFormatEnum::RGBA32,
+        )
+        .map_err(|err| Error::texture_creation(None, err))?;
+        surface
+            .fill_rect(None, Color::RGBA(0, 0, 0, 0))
+            .map_err(|err| Error::video("SDL_FillRect", err))?;
+        for run in &self.runs {
+            let mut text = run
+                .font
+                .render(&run.text)
+                .blended(run.color)
+                .map_err(|err| Error::ttf("TTF_RenderUTF8_Blended", None, err))?;
+            // Blended so runs overlapping the previous one, i.e. italics leaning into it, don't
+            // cut off its glyphs
+            text.set_blend_mode(BlendMode::Blend)
+                .map_err(|err| Error::video("SDL_SetSurfaceBlendMode", err))?;
+            text.blit(None, &mut surface, run.rect)
+                .map_err(|err| Error::video("SDL_BlitSurface", err))?;
+        }
+        Sprite::from_surface(surface, texture_creator)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Class {
+    Word,
+    Space,
+    Newline,
+}
+
+// Consecutive characters of the same class and style, generic over the font so lines can be
+// broken without loading one
+struct Fragment<F> {
+    class: Class,
+    style: SpanStyle,
+    text: String,
+    font: F,
+    width: u32,
+}
+
+struct Line<F> {
+    fragments: Vec<Fragment<F>>,
+    // Ends a paragraph, so isn't stretched when justified
+    last: bool,
+}
+
+impl<F> Line<F> {
+    fn new() -> Self {
+        Line {
+            fragments: Vec::new(),
+            last: false,
+        }
+    }
+
+    fn width(&self) -> u32 {
+        self.fragments.iter().map(|fragment| fragment.width).sum()
+    }
+}
+
+// Splits the spans into fragments with the font of their style, their width is left at 0
+fn fragments<F, L>(spans: &[Span], mut load_font: L) -> Result<Vec<Fragment<F>>, Error>
+where
+    L: FnMut(&SpanStyle) -> Result<F, Error>,
+{
+    let mut fragments: Vec<Fragment<F>> = Vec::new();
+    for span in spans {
+        for ch in span.text.chars() {
+            let class = match ch {
+                '\n' => Class::Newline,
+                ' ' => Class::Space,
+                _ => Class::Word,
+            };
+            match fragments.last_mut() {
+                Some(last)
+                    if last.class == class
+                        && class != Class::Newline
+                        && last.style == span.style =>
+                {
+                    last.text.push(ch)
+                }
+                _ => fragments.push(Fragment {
+                    class,
+                    style: span.style,
+                    text: ch.to_string(),
+                    font: load_font(&span.style)?,
+                    width: 0,
+                }),
+            }
+        }
+    }
+    Ok(fragments)
+}
+
+// Words are made of consecutive word fragments, so a word changing style mid way stays on one
+// line. Spaces at a line break belong to neither line.
+fn break_lines<F>(fragments: Vec<Fragment<F>>, max_width: Option<u32>) -> Vec<Line<F>> {
+    let mut lines = Vec::new();
+    let mut line = Line::new();
+    let mut spaces = Vec::new();
+    let mut fragments = fragments.into_iter().peekable();
+    while let Some(fragment) = fragments.next() {
+        match fragment.class {
+            Class::Newline => {
+                line.last = true;
+                lines.push(line);
+                line = Line::new();
+                spaces.clear();
+            }
+            Class::Space => spaces.push(fragment),
+            Class::Word => {
+                let mut word = vec![fragment];
+                while let Some(next) = fragments.next_if(|next| next.class == Class::Word) {
+                    word.push(next);
+                }
+                let width = |fragments: &[Fragment<F>]| -> u32 {
+                    fragments.iter().map(|fragment| fragment.width).sum()
+                };
+                let needed = line.width() + width(&spaces) + width(&word);
+                if !line.fragments.is_empty() && max_width.is_some_and(|max| needed > max) {
+                    lines.push(line);
+                    line = Line::new();
+                    spaces.clear();
+                } else {
+                    line.fragments.append(&mut spaces);
+                }
+                line.fragments.append(&mut word);
+            }
+        }
+    }
+    line.last = true;
+    lines.push(line);
+    lines
+}
+
+fn style_of(open: &[(Tag, usize)]) -> SpanStyle {
+    open.iter()
+        .fold(SpanStyle::default(), |mut style, (tag, _)| {
+            match tag {
+                Tag::Bold => style.bold = true,
+                Tag::Italic => style.italic = true,
+                Tag::Underline => style.underline = true,
+                Tag::Strikethrough => style.strikethrough = true,
+                Tag::Color(color) => style.color = Some(*color),
+            }
+            style
+        })
+}
+
+fn push_span(spans: &mut Vec<Span>, text: &mut String, style: SpanStyle) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => spans.push(Span {
+            text: text.clone(),
+            style,
+        }),
+    }
+    text.clear();
+}
+
+fn parse_color(color: &str) -> Result<Color, String> {
+    let invalid = || format!("invalid color {:?}, expected #rrggbb", color);
+    let hex = color.strip_prefix('#').ok_or_else(invalid)?;
+    if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    let channel = |idx: usize| u8::from_str_radix(&hex[idx * 2..idx * 2 + 2], 16).unwrap();
+    let alpha = if hex.len() == 8 { channel(3) } else { 0xff };
+    Ok(Color::RGBA(channel(0), channel(1), channel(2), alpha))
+}
+
+fn measure(font: &Font, text: &str) -> Result<u32, Error> {
+    font.size_of(text)
+        .map(|(width, _)| width)
+        .map_err(|err| Error::ttf("TTF_SizeUTF8", None, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(markup: &str) -> Vec<(String, SpanStyle)> {
+        RichText::parse(markup)
+            .unwrap()
+            .spans()
+            .iter()
+            .map(|span| (span.text.clone(), span.style))
+            .collect()
+    }
+
+    // Lines of the markup with every character 10 pixels wide
+    fn lines(markup: &str, max_width: Option<u32>) -> Vec<(String, bool)> {
+        let text = RichText::parse(markup).unwrap();
+        let mut fragments = fragments(text.spans(), |_| Ok(())).unwrap();
+        for fragment in fragments.iter_mut() {
+            fragment.width = fragment.text.chars().count() as u32 * 10;
+        }
+        break_lines(fragments, max_width)
+            .iter()
+            .map(|line| {
+                let text = line
+                    .fragments
+                    .iter()
+                    .map(|fragment| fragment.text.as_str())
+                    .collect();
+                (text, line.last)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse() {
+        let markup = "[color=#ff0000]danger[/color] [b]bold[/b] [i]italic[/i] \
+            [u]underlined words[/u] [s]struck[/s] plain[[ and [b][color=#0000ff]mi[/color]xed[/b]";
+        let text = RichText::parse(markup).unwrap();
+        assert_eq!(
+            text.text(),
+            "danger bold italic underlined words struck plain[ and mixed"
+        );
+        assert_eq!(text.color, Color::WHITE);
+        assert_eq!(text.clone().color(Color::BLACK).color, Color::BLACK);
+        let spans = text.spans();
+        assert_eq!(spans[0].style.color, Some(Color::RED));
+        assert!(spans[2].style.bold);
+        assert!(spans[4].style.italic);
+        assert!(spans[6].style.underline);
+        assert!(spans[8].style.strikethrough);
+        assert_eq!(spans[9].text, " plain[ and ");
+        assert_eq!(spans[10].style.color, Some(Color::BLUE));
+        assert_eq!(spans[11].text, "xed");
+        assert_eq!(spans[11].style.color, None);
+    }
+
+    #[test]
+    fn nested_and_adjacent_tags() {
+        let bold = SpanStyle {
+            bold: true,
+            ..SpanStyle::default()
+        };
+        let bold_italic = SpanStyle {
+            italic: true,
+            ..bold
+        };
+        assert_eq!(
+            spans("a[b]b[i]c[/i][/b]d"),
+            [
+                ("a".to_string(), SpanStyle::default()),
+                ("b".to_string(), bold),
+                ("c".to_string(), bold_italic),
+                ("d".to_string(), SpanStyle::default()),
+            ]
+        );
+        // Spans of the same style are merged, empty ones left out
+        assert_eq!(spans("[b]x[/b][b]y[/b][i][/i]"), [("xy".to_string(), bold)]);
+        assert!(spans("").is_empty());
+    }
+
+    #[test]
+    fn invalid_markup() {
+        for (invalid, at) in [
+            ("[b]open", 0),
+            ("[b]x[/i]", 4),
+            ("x[/b]", 1),
+            ("[color=red]x[/color]", 0),
+            ("[q]x[/q]", 0),
+            ("x[b", 1),
+            ("[[[b]", 2),
+        ]
+        .iter()
+        {
+            match RichText::parse(invalid) {
+                Err(Error::Markup { position, .. }) => assert_eq!(position, *at, "{}", invalid),
+                other => panic!("{:?} parsed to {:?}", invalid, other.map(|_| ())),
+            }
+        }
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(parse_color("#ff0000"), Ok(Color::RED));
+        assert_eq!(parse_color("#00FF0080"), Ok(Color::RGBA(0, 0xff, 0, 0x80)));
+        for invalid in &["ff0000", "#fff", "#ff00001", "#gg0000", "#ffé00", ""] {
+            assert!(parse_color(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn fragments_by_class_and_style() {
+        let text = RichText::parse("ab  c\n\n[b]mi[/b]xed").unwrap();
+        let split = fragments(text.spans(), |_| Ok(())).unwrap();
+        let classes: Vec<_> = split
+            .iter()
+            .map(|fragment| (fragment.class, fragment.text.as_str()))
+            .collect();
+        assert_eq!(
+            classes,
+            [
+                (Class::Word, "ab"),
+                (Class::Space, "  "),
+                (Class::Word, "c"),
+                (Class::Newline, "\n"),
+                (Class::Newline, "\n"),
+                (Class::Word, "mi"),
+                (Class::Word, "xed"),
+            ]
+        );
+
+        // One font per fragment
+        let mut loaded = Vec::new();
+        fragments(text.spans(), |style| {
+            loaded.push(style.bold);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(loaded, [false, false, false, false, false, true, false]);
+    }
+
+    #[test]
+    fn line_breaks() {
+        let line = |text: &str, last| (text.to_string(), last);
+        assert_eq!(
+            lines("aaa bb  cc", Some(60)),
+            [line("aaa bb", false), line("cc", true)]
+        );
+        assert_eq!(lines("aaa bb  cc", None), [line("aaa bb  cc", true)]);
+        // Words changing style midway aren't broken, not even when wider than a line
+        assert_eq!(
+            lines("a [b]mi[/b]xed aa", Some(30)),
+            [line("a", false), line("mixed", false), line("aa", true)]
+        );
+        assert_eq!(
+            lines("a\n\nb c", Some(10)),
+            [
+                line("a", true),
+                line("", true),
+                line("b", false),
+                line("c", true)
+            ]
+        );
+    }
+}
@@ -0,0 +1,483 @@
+use crate::error::Error;
+use sdl2::event::Event;
+use sdl2::sys::{
+    SDL_Haptic, SDL_HapticConstant, SDL_HapticEffect, SDL_HapticPeriodic, SDL_HapticRamp,
+    SDL_HAPTIC_CONSTANT, SDL_HAPTIC_RAMP, SDL_HAPTIC_SINE,
+};
+use sdl2::HapticSubsystem;
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::mem;
+use std::os::raw::c_int;
+use std::rc::Rc;
+use std::time::Duration;
+
+// Something the player feels, strengths go from 0.0 to 1.0
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RumbleEffect {
+    // Runs the gamepad's rumble motors, the low frequency one is usually the stronger one
+    Rumble {
+        low_frequency: f32,
+        high_frequency: f32,
+        duration: Duration,
+    },
+    Constant {
+        level: f32,
+        duration: Duration,
+    },
+    // Strength oscillating with the period, i.e. an engine running
+    Sine {
+        magnitude: f32,
+        period: Duration,
+        duration: Duration,
+    },
+    // Strength going from start to end over the duration, -1.0 to 1.0 for devices that push back
+    Ramp {
+        start: f32,
+        end: f32,
+        duration: Duration,
+    },
+}
+
+impl RumbleEffect {
+    // Runs both rumble motors with the same strength
+    pub fn rumble(strength: f32, duration: Duration) -> Self {
+        RumbleEffect::Rumble {
+            low_frequency: strength,
+            high_frequency: strength,
+            duration,
+        }
+    }
+
+    pub fn constant(level: f32, duration: Duration) -> Self {
+        RumbleEffect::Constant { level, duration }
+    }
+
+    pub fn sine(magnitude: f32, period: Duration, duration: Duration) -> Self {
+        RumbleEffect::Sine {
+            magnitude,
+            period,
+            duration,
+        }
+    }
+
+    pub fn ramp(start: f32, end: f32, duration: Duration) -> Self {
+        RumbleEffect::Ramp {
+            start,
+            end,
+            duration,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            RumbleEffect::Rumble { duration, .. }
+            | RumbleEffect::Constant { duration, .. }
+            | RumbleEffect::Sine { duration, .. }
+            | RumbleEffect::Ramp { duration, .. } => *duration,
+        }
+    }
+
+    // Strongest point of the effect, what gamepads without haptic support rumble with instead
+    pub fn strength(&self) -> f32 {
+        match self {
+            RumbleEffect::Rumble {
+                low_frequency,
+                high_frequency,
+                ..
+            } => low_frequency.max(*high_frequency),
+            RumbleEffect::Constant { level, .. } => *level,
+            RumbleEffect::Sine { magnitude, .. } => *magnitude,
+            RumbleEffect::Ramp { start, end, .. } => start.abs().max(end.abs()),
+        }
+    }
+}
+
+// Plays effects on gamepads, identified by the instance id SDL's controller events call `which`
+pub trait RumbleBackend {
+    fn play(&mut self, which: u32, effect: &RumbleEffect) -> Result<(), Error>;
+
+    fn stop(&mut self, which: u32) -> Result<(), Error>;
+
+    // Releases what the backend holds on to for the gamepad once it was unplugged
+    fn disconnect(&mut self, _which: u32) {}
+}
+
+// Rumbles gamepads opened by `Gamepads`.
+//
+// Constant, sine and ramp effects go through SDL's haptic subsystem if the gamepad supports them
+// and otherwise run the rumble motors with the strength of the effect.
+pub struct SdlRumbleBackend {
+    haptic: Option<HapticSubsystem>,
+    devices: HashMap<u32, HapticDevice>,
+}
+
+impl SdlRumbleBackend {
+    // Without the haptic subsystem all effects run the rumble motors
+    pub fn new(haptic: Option<HapticSubsystem>) -> Self {
+        SdlRumbleBackend {
+            haptic,
+            devices: HashMap::new(),
+        }
+    }
+
+    fn rumble(
+        which: u32,
+        low_frequency: f32,
+        high_frequency: f32,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        let result = unsafe {
+            let controller = sdl2::sys::SDL_GameControllerFromInstanceID(which as i32);
+            if controller.is_null() {
+                return Err(Error::gamepad(
+                    "SDL_GameControllerFromInstanceID",
+                    format!("gamepad {} isn't open", which),
+                ));
+            }
+            sdl2::sys::SDL_GameControllerRumble(
+                controller,
+                motor_strength(low_frequency),
+                motor_strength(high_frequency),
+                millis(duration),
+            )
+        };
+        if result != 0 {
+            return Err(Error::gamepad(
+                "SDL_GameControllerRumble",
+                sdl2::get_error(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Opens the gamepad's haptic device on first use, `None` if it has none
+    fn device(&mut self, which: u32) -> Option<&mut HapticDevice> {
+        self.haptic.as_ref()?;
+        let device = match self.devices.entry(which) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let raw = unsafe {
+                    let joystick = sdl2::sys::SDL_JoystickFromInstanceID(which as i32);
+                    if joystick.is_null() {
+                        return None;
+                    }
+                    sdl2::sys::SDL_HapticOpenFromJoystick(joystick)
+                };
+                if raw.is_null() {
+                    return None;
+                }
+                entry.insert(HapticDevice {
+                    raw,
+                    supported: unsafe { sdl2::sys::SDL_HapticQuery(raw) },
+                    effect: None,
+                })
+            }
+        };
+        Some(device)
+    }
+}
+
+impl RumbleBackend for SdlRumbleBackend {
+    fn play(&mut self, which: u32, effect: &RumbleEffect) -> Result<(), Error> {
+        if let RumbleEffect::Rumble {
+            low_frequency,
+            high_frequency,
+            duration,
+        } = *effect
+        {
+            return SdlRumbleBackend::rumble(which, low_frequency, high_frequency, duration);
+        }
+        match (self.device(which), haptic_effect(effect)) {
+            (Some(device), Some(haptic_effect)) if device.supports(&haptic_effect) => {
+                device.play(haptic_effect)
+            }
+            _ => SdlRumbleBackend::rumble(
+                which,
+                effect.strength(),
+                effect.strength(),
+                effect.duration(),
+            ),
+        }
+    }
+
+    fn stop(&mut self, which: u32) -> Result<(), Error> {
+        if let Some(device) = self.devices.get_mut(&which) {
+            device.stop();
+        }
+        // Zero strength stops the motors, failing only if there are none to stop
+        SdlRumbleBackend::rumble(which, 0.0, 0.0, Duration::from_secs(0)).or(Ok(()))
+    }
+
+    fn disconnect(&mut self, which: u32) {
+        self.devices.remove(&which);
+    }
+}
+
+struct HapticDevice {
+    raw: *mut SDL_Haptic,
+    // Effect types the device supports as SDL_HAPTIC_* bits
+    supported: u32,
+    // Devices only hold a few effects, so we replace the last one instead of adding more
+    effect: Option<c_int>,
+}
+
+impl HapticDevice {
+    fn supports(&self, effect: &SDL_HapticEffect) -> bool {
+        // All effects start with their type
+        self.supported & u32::from(unsafe { effect.type_ }) != 0
+    }
+
+    fn play(&mut self, mut effect: SDL_HapticEffect) -> Result<(), Error> {
+        self.stop();
+        let id = unsafe { sdl2::sys::SDL_HapticNewEffect(self.raw, &mut effect) };
+        if id < 0 {
+            return Err(Error::gamepad("SDL_HapticNewEffect", sdl2::get_error()));
+        }
+        self.effect = Some(id);
+        if unsafe { sdl2::sys::SDL_HapticRunEffect(self.raw, id, 1) } != 0 {
+            return Err(Error::gamepad("SDL_HapticRunEffect", sdl2::get_error()));
+        }
+        Ok(())
+    }
+
+    // Destroying the effect stops it
+    fn stop(&mut self) {
+        if let Some(id) = self.effect.take() {
+            unsafe { sdl2::sys::SDL_HapticDestroyEffect(self.raw, id) };
+        }
+    }
+}
+
+impl Drop for HapticDevice {
+    fn drop(&mut self) {
+        unsafe { sdl2::sys::SDL_HapticClose(self.raw) };
+    }
+}
+
+// A played effect or stop as recorded by `RecordingRumble`
+#[derive(Clone, Debug, PartialEq)]
+pub enum RumbleCall {
+    Play { which: u32, effect: RumbleEffect },
+    Stop { which: u32 },
+}
+
+// Records what would have been played instead of playing it, so tests can check which effects
+// a game triggered. Clones share the recorded calls, so keep one to check what the `Rumble`
+// given the other one played.
+#[derive(Clone, Debug, Default)]
+pub struct RecordingRumble {
+    calls: Rc<RefCell<Vec<RumbleCall>>>,
+}
+
+impl RecordingRumble {
+    pub fn new() -> Self {
+        RecordingRumble::default()
+    }
+
+    pub fn calls(&self) -> Vec<RumbleCall> {
+        self.calls.borrow().clone()
+    }
+
+    pub fn clear(&self) {
+        self.calls.borrow_mut().clear();
+    }
+}
+
+impl RumbleBackend for RecordingRumble {
+    fn play(&mut self, which: u32, effect: &RumbleEffect) -> Result<(), Error> {
+        self.calls.borrow_mut().push(RumbleCall::Play {
+            which,
+            effect: *effect,
+        });
+        Ok(())
+    }
+
+    fn stop(&mut self, which: u32) -> Result<(), Error> {
+        self.calls.borrow_mut().push(RumbleCall::Stop { which });
+        Ok(())
+    }
+}
+
+// Plays rumble and haptic effects on gamepads, i.e. when the player crashes into a wall.
+//
+// Gamepads are identified by instance id, see `Gamepads::instance_id` for the one of a player.
+// Pass events to `handle_event` so unplugged gamepads are released.
+pub struct Rumble {
+    backend: Box<dyn RumbleBackend>,
+}
+
+impl Rumble {
+    pub fn new(haptic: Option<HapticSubsystem>) -> Self {
+        Rumble::with_backend(Box::new(SdlRumbleBackend::new(haptic)))
+    }
+
+    pub fn with_backend(backend: Box<dyn RumbleBackend>) -> Self {
+        Rumble { backend }
+    }
+
+    // Replaces the effect playing on the gamepad, if any
+    pub fn play(&mut self, which: u32, effect: RumbleEffect) -> Result<(), Error> {
+        self.backend.play(which, &effect)
+    }
+
+    pub fn stop(&mut self, which: u32) -> Result<(), Error> {
+        self.backend.stop(which)
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        if let Event::ControllerDeviceRemoved { which, .. } = event {
+            self.backend.disconnect(*which);
+        }
+    }
+}
+
+// The SDL effect for constant, sine and ramp effects
+fn haptic_effect(effect: &RumbleEffect) -> Option<SDL_HapticEffect> {
+    let mut haptic_effect: SDL_HapticEffect = unsafe { mem::zeroed() };
+    match *effect {
+        RumbleEffect::Rumble { .. } => return None,
+        RumbleEffect::Constant { level, duration } => {
+            let mut constant: SDL_HapticConstant = unsafe { mem::zeroed() };
+            constant.type_ = SDL_HAPTIC_CONSTANT as u16;
+            constant.length = millis(duration);
+            constant.level = force(level);
+            haptic_effect.constant = constant;
+        }
+        RumbleEffect::Sine {
+            magnitude,
+            period,
+            duration,
+        } => {
+            let mut periodic: SDL_HapticPeriodic = unsafe { mem::zeroed() };
+            periodic.type_ = SDL_HAPTIC_SINE as u16;
+            periodic.length = millis(duration);
+            periodic.period = millis(period).min(u32::from(u16::MAX)) as u16;
+            periodic.magnitude = force(magnitude);
+            haptic_effect.periodic = periodic;
+        }
+        RumbleEffect::Ramp {
+            start,
+            end,
+            duration,
+        } => {
+            let mut ramp: SDL_HapticRamp = unsafe { mem::zeroed() };
+            ramp.type_ = SDL_HAPTIC_RAMP as u16;
+            ramp.length = millis(duration);
+            ramp.start = force(start);
+            ramp.end = force(end);
+            haptic_effect.ramp = ramp;
+        }
+    }
+    Some(haptic_effect)
+}
+
+fn millis(duration: Duration) -> u32 {
+    duration.as_millis().min(u128::from(u32::MAX)) as u32
+}
+
+fn motor_strength(strength: f32) -> u16 {
+    (strength.clamp(0.0, 1.0) * f32::from(u16::MAX)) as u16
+}
+
+fn force(strength: f32) -> i16 {
+    (strength.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recording() -> Result<(), Error> {
+        let recording = RecordingRumble::new();
+        let mut rumble = Rumble::with_backend(Box::new(recording.clone()));
+        let bump = RumbleEffect::constant(1.0, Duration::from_millis(150));
+        let engine = RumbleEffect::sine(0.75, Duration::from_millis(100), Duration::from_secs(1));
+
+        // A collision of player 1 followed by player 2 starting their engine
+        rumble.play(1, bump)?;
+        rumble.play(2, engine)?;
+        rumble.stop(2)?;
+        assert_eq!(
+            recording.calls(),
+            [
+                RumbleCall::Play {
+                    which: 1,
+                    effect: bump
+                },
+                RumbleCall::Play {
+                    which: 2,
+                    effect: engine
+                },
+                RumbleCall::Stop { which: 2 },
+            ]
+        );
+
+        recording.clear();
+        assert!(recording.calls().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn strength() {
+        let second = Duration::from_secs(1);
+        let rumble = RumbleEffect::Rumble {
+            low_frequency: 0.25,
+            high_frequency: 0.75,
+            duration: second,
+        };
+        assert_eq!(rumble.strength(), 0.75);
+        assert_eq!(RumbleEffect::rumble(0.5, second).strength(), 0.5);
+        assert_eq!(RumbleEffect::constant(0.25, second).strength(), 0.25);
+        assert_eq!(
+            RumbleEffect::sine(0.75, second / 10, second).strength(),
+            0.75
+        );
+        // Ramps pushing back are as strong as ones pulling
+        assert_eq!(RumbleEffect::ramp(-0.25, 0.5, second).strength(), 0.5);
+        assert_eq!(RumbleEffect::ramp(-1.0, 0.5, second).strength(), 1.0);
+
+        let ramp = RumbleEffect::ramp(0.0, 1.0, Duration::from_secs(2));
+        assert_eq!(ramp.duration(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn clamping() {
+        assert_eq!(motor_strength(0.0), 0);
+        assert_eq!(motor_strength(0.5), u16::MAX / 2);
+        assert_eq!(motor_strength(1.0), u16::MAX);
+        assert_eq!(motor_strength(2.0), u16::MAX);
+        assert_eq!(motor_strength(-1.0), 0);
+
+        assert_eq!(force(0.0), 0);
+        assert_eq!(force(1.0), i16::MAX);
+        assert_eq!(force(-1.0), -i16::MAX);
+        assert_eq!(force(1.5), i16::MAX);
+        assert_eq!(force(-1.5), -i16::MAX);
+
+        assert_eq!(millis(Duration::from_millis(1500)), 1500);
+        assert_eq!(millis(Duration::from_secs(u64::MAX)), u32::MAX);
+    }
+
+    #[test]
+    fn haptic_effects() {
+        let second = Duration::from_secs(1);
+        assert!(haptic_effect(&RumbleEffect::rumble(1.0, second)).is_none());
+
+        let effect = haptic_effect(&RumbleEffect::ramp(-2.0, 0.5, second)).unwrap();
+        let ramp = unsafe { effect.ramp };
+        assert_eq!(u32::from(ramp.type_), SDL_HAPTIC_RAMP);
+        assert_eq!(
+            (ramp.length, ramp.start, ramp.end),
+            (1000, -i16::MAX, i16::MAX / 2)
+        );
+
+        // Periods longer than SDL can hold are clamped too
+        let effect = haptic_effect(&RumbleEffect::sine(0.5, second * 100, second)).unwrap();
+        let periodic = unsafe { effect.periodic };
+        assert_eq!(u32::from(periodic.type_), SDL_HAPTIC_SINE);
+        assert_eq!(periodic.period, u16::MAX);
+    }
+}
@@ -0,0 +1,288 @@
+use crate::error::ensure_exists;
+use crate::game::DEFAULT_CLEAR_COLOR;
+use crate::{Error, EventSource, Game, GameLoop, Renderer, RendererConfig};
+use sdl2::image::{LoadSurface, SaveSurface};
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::WindowCanvas;
+use sdl2::surface::Surface;
+use sdl2::Sdl;
+use std::env;
+use std::error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+// Golden-image snapshot testing of rendered scenes.
+//
+// Scenes are rendered with the software renderer into an offscreen target texture, read back
+// and compared against PNGs checked in next to the tests.
+// Run with `UPDATE_SNAPSHOTS=1` to accept the current output as the new golden images, a
+// missing golden image fails the test otherwise.
+
+const PIXEL_FORMAT: PixelFormatEnum = PixelFormatEnum::RGBA32;
+const BYTES_PER_PIXEL: usize = 4;
+const ENV_UPDATE_SNAPSHOTS: &str = "UPDATE_SNAPSHOTS";
+
+pub const DEFAULT_TOLERANCE: u8 = 2;
+
+// SDL can only be initialized once at a time, so tests running in parallel take turns.
+static SDL_LOCK: Mutex<()> = Mutex::new(());
+
+// RGBA32 pixels read back from a canvas or loaded from a PNG.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        assert_eq!(
+            pixels.len(),
+            width as usize * height as usize * BYTES_PER_PIXEL,
+            "pixels don't match {}x{} RGBA image",
+            width,
+            height
+        );
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        ensure_exists(path)?;
+        let surface = Surface::from_file(path)
+            .and_then(|surface| surface.convert_format(PIXEL_FORMAT))
+            .map_err(|err| Error::image_decode(path, err))?;
+        let (width, height) = surface.size();
+        let pitch = surface.pitch() as usize;
+        let row_len = width as usize * BYTES_PER_PIXEL;
+
+        // Rows may be padded, so we only copy the part containing pixels
+        let pixels = surface.with_lock(|data| {
+            data.chunks(pitch)
+                .take(height as usize)
+                .flat_map(|row| row[..row_len].iter().copied())
+                .collect()
+        });
+        Ok(Image::new(width, height, pixels))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| Error::io(dir, err))?;
+        }
+        let mut pixels = self.pixels.clone();
+        let pitch = self.width * BYTES_PER_PIXEL as u32;
+        let surface = Surface::from_data(&mut pixels, self.width, self.height, pitch, PIXEL_FORMAT)
+            .map_err(|err| Error::texture_creation(Some(path), err))?;
+        surface
+            .save(path)
+            .map_err(|err| Error::io(path, io::Error::other(err)))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // Compares each channel of each pixel and returns an image highlighting the pixels which
+    // differ by more than `tolerance` in red, or `None` if all of them are within tolerance.
+    pub fn diff(&self, other: &Image, tolerance: u8) -> Result<Option<Image>, String> {
+        if self.width != other.width || self.height != other.height {
+            return Err(format!(
+                "size mismatch: {}x{} vs. {}x{}",
+                self.width, self.height, other.width, other.height
+            ));
+        }
+
+        let mut mismatches = 0;
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+        let pairs = self
+            .pixels
+            .chunks(BYTES_PER_PIXEL)
+            .zip(other.pixels.chunks(BYTES_PER_PIXEL));
+        for (a, b) in pairs {
+            let differs = a
+                .iter()
+                .zip(b.iter())
+                .any(|(x, y)| (*x as i16 - *y as i16).abs() > tolerance as i16);
+            if differs {
+                mismatches += 1;
+                pixels.extend_from_slice(&[0xff, 0x00, 0x00, 0xff]);
+            } else {
+                // Faded grayscale of the matching pixel so the mismatches stand out
+                let luma = (a[0] as u32 * 299 + a[1] as u32 * 587 + a[2] as u32 * 114) / 1000;
+                let faded = (0xff - (0xff - luma) / 4) as u8;
+                pixels.extend_from_slice(&[faded, faded, faded, 0xff]);
+            }
+        }
+
+        if mismatches == 0 {
+            Ok(None)
+        } else {
+            Ok(Some(Image::new(self.width, self.height, pixels)))
+        }
+    }
+}
+
+// A hidden software rendered window whose canvas renders scenes offscreen.
+// Holds on to the lock which ensures that only one test uses SDL at a time.
+pub struct Headless {
+    pub canvas: WindowCanvas,
+    pub sdl_context: Sdl,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Headless {
+    pub fn new(width: u32, height: u32) -> Result<Self, Error> {
+        // A previous test panicking while holding the lock doesn't affect the next one
+        let lock = SDL_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+        let Renderer {
+            sdl_context,
+            canvas,
+            ..
+        } = RendererConfig::new("Snapshot", width, height)
+            .headless()
+            .init()?;
+        Ok(Headless {
+            canvas,
+            sdl_context,
+            _lock: lock,
+        })
+    }
+
+    // Renders into a target texture the size of the window and reads the pixels back.
+    pub fn render<F>(&mut self, render: F) -> Result<Image, Box<dyn error::Error>>
+    where
+        F: FnOnce(&mut WindowCanvas) -> Result<(), Box<dyn error::Error>>,
+    {
+        let (width, height) = self.canvas.window().size();
+        let texture_creator = self.canvas.texture_creator();
+        let mut target = texture_creator.create_texture_target(PIXEL_FORMAT, width, height)?;
+
+        let mut result: Result<Image, Box<dyn error::Error>> = Err("scene was not rendered".into());
+        self.canvas
+            .with_texture_canvas(&mut target, |target_canvas| {
+                result = render(target_canvas).and_then(|_| {
+                    let pixels = target_canvas.read_pixels(None, PIXEL_FORMAT)?;
+                    Ok(Image::new(width, height, pixels))
+                });
+            })?;
+        result
+    }
+
+    // Renders one frame of the game the way the game loop does, onto the default clear color
+    pub fn render_game<G: Game>(&mut self, game: &mut G) -> Result<Image, Box<dyn error::Error>> {
+        self.render(|canvas| {
+            canvas.set_draw_color(DEFAULT_CLEAR_COLOR);
+            canvas.clear();
+            game.render(canvas)
+        })
+    }
+
+    // Runs the game in the window until the events ran out, i.e. a script of `ScriptedEvents`,
+    // as fast as it goes. Returns the number of frames that were rendered.
+    pub fn run<G: Game, S: EventSource>(
+        &mut self,
+        game: &mut G,
+        mut events: S,
+    ) -> Result<u64, Box<dyn error::Error>> {
+        GameLoop::new().frame_cap(None).run_with_source(
+            &self.sdl_context,
+            &mut self.canvas,
+            game,
+            &mut events,
+        )
+    }
+}
+
+// Directory of golden images for one test suite, i.e. `tests/snapshots`.
+// Actual output and diff images of failing snapshots are written to `output_dir`.
+pub struct Snapshots {
+    golden_dir: PathBuf,
+    output_dir: PathBuf,
+    tolerance: u8,
+}
+
+impl Snapshots {
+    // Expects the crate root, i.e. `env!("CARGO_MANIFEST_DIR")`
+    pub fn new(manifest_dir: &str) -> Self {
+        let manifest_dir = Path::new(manifest_dir);
+        let target_dir = env::var_os("CARGO_TARGET_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| manifest_dir.join("target"));
+        Snapshots {
+            golden_dir: manifest_dir.join("tests").join("snapshots"),
+            output_dir: target_dir.join("snapshots"),
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    pub fn tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    pub fn golden_path(&self, name: &str) -> PathBuf {
+        self.golden_dir.join(format!("{}.png", name))
+    }
+
+    // Compares `actual` against the golden image with the given name.
+    // With `UPDATE_SNAPSHOTS=1` the golden image is written instead, missing ones fail otherwise.
+    pub fn check(&self, name: &str, actual: &Image) -> Result<(), String> {
+        let golden_path = self.golden_path(name);
+        if env::var_os(ENV_UPDATE_SNAPSHOTS).is_some_and(|update| update == "1") {
+            return actual.save(&golden_path).map_err(|err| err.to_string());
+        }
+        if !golden_path.exists() {
+            let actual_path = self.output_dir.join(format!("{}.actual.png", name));
+            actual.save(&actual_path).map_err(|err| err.to_string())?;
+            return Err(format!(
+                "missing snapshot {:?}, see {:?} and run with {}=1 to accept it",
+                golden_path, actual_path, ENV_UPDATE_SNAPSHOTS
+            ));
+        }
+
+        let expected = Image::load(&golden_path).map_err(|err| err.to_string())?;
+        let diff = match expected.diff(actual, self.tolerance) {
+            Ok(None) => return Ok(()),
+            Ok(Some(diff)) => Some(diff),
+            Err(_) => None,
+        };
+
+        let actual_path = self.output_dir.join(format!("{}.actual.png", name));
+        actual.save(&actual_path).map_err(|err| err.to_string())?;
+        match diff {
+            Some(diff) => {
+                let diff_path = self.output_dir.join(format!("{}.diff.png", name));
+                diff.save(&diff_path).map_err(|err| err.to_string())?;
+                Err(format!(
+                    "snapshot {:?} differs, see {:?} and {:?}",
+                    name, actual_path, diff_path
+                ))
+            }
+            None => Err(format!(
+                "snapshot {:?} is {}x{} but scene rendered {}x{}, see {:?}",
+                name, expected.width, expected.height, actual.width, actual.height, actual_path
+            )),
+        }
+    }
+
+    pub fn assert(&self, name: &str, actual: &Image) {
+        if let Err(err) = self.check(name, actual) {
+            panic!("{}", err);
+        }
+    }
+}
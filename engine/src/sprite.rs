@@ -0,0 +1,489 @@
+use crate::atlas::Atlas;
+use crate::draw::DrawParams;
+use crate::error::{ensure_exists, Error};
+#[cfg(feature = "ttf")]
+use crate::text::TextMode;
+#[cfg(feature = "image")]
+use sdl2::image::LoadSurface;
+#[cfg(any(feature = "image", feature = "ttf"))]
+use sdl2::pixels::Color;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::rect::Rect;
+use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
+use sdl2::surface::Surface;
+use sdl2::sys::{SDL_FPoint, SDL_FRect, SDL_Renderer, SDL_Texture};
+#[cfg(feature = "ttf")]
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use std::os::raw::{c_int, c_uint};
+use std::path::Path;
+use std::rc::Rc;
+
+// Color key the lazy-foo assets use for their background
+#[cfg(feature = "image")]
+pub const DEFAULT_COLOR_KEY: Color = Color::RGB(0, 0xff, 0xff);
+
+// load_media method we created in lazy-foo/src/02_image_on_screen.rs adapted to pass in path
+pub fn load_media<'a, P: AsRef<Path>>(
+    path: P,
+    texture_creator: &'a TextureCreator<WindowContext>,
+) -> Result<Texture<'a>, Error> {
+    let path = path.as_ref();
+    ensure_exists(path)?;
+    let surface = Surface::load_bmp(path).map_err(|err| Error::image_decode(path, err))?;
+    texture_creator
+        .create_texture_from_surface(surface)
+        .map_err(|err| Error::texture_creation(Some(path), err))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flip {
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl Flip {
+    // SDL_RendererFlip bits, the sys bindings declare them as an enum which can't hold both
+    fn bits(self) -> c_uint {
+        match self {
+            Flip::None => 0,
+            Flip::Horizontal => 1,
+            Flip::Vertical => 2,
+            Flip::Both => 1 | 2,
+        }
+    }
+}
+
+// Part of the sprite to render, the whole texture, a rect or a named frame of its atlas.
+// Converts from what `render` accepted all along, i.e. `None` or a `Rect`, and from frame names.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clip<'c> {
+    Full,
+    Rect(Rect),
+    Frame(&'c str),
+}
+
+impl<'c> From<Rect> for Clip<'c> {
+    fn from(rect: Rect) -> Self {
+        Clip::Rect(rect)
+    }
+}
+
+impl<'c> From<Option<Rect>> for Clip<'c> {
+    fn from(rect: Option<Rect>) -> Self {
+        rect.map_or(Clip::Full, Clip::Rect)
+    }
+}
+
+impl<'c> From<&'c str> for Clip<'c> {
+    fn from(name: &'c str) -> Self {
+        Clip::Frame(name)
+    }
+}
+
+impl<'c> From<&'c String> for Clip<'c> {
+    fn from(name: &'c String) -> Self {
+        Clip::Frame(name)
+    }
+}
+
+// Describes how images are turned into sprites.
+// The defaults match what the lessons used all along, a cyan color key and straight alpha.
+#[cfg(feature = "image")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct LoadOptions {
+    color_key: Option<Color>,
+    detect_alpha: bool,
+    premultiply_alpha: bool,
+}
+
+#[cfg(feature = "image")]
+impl Default for LoadOptions {
+    fn default() -> Self {
+        LoadOptions::new()
+    }
+}
+
+#[cfg(feature = "image")]
+impl LoadOptions {
+    pub fn new() -> Self {
+        LoadOptions {
+            color_key: Some(DEFAULT_COLOR_KEY),
+            detect_alpha: false,
+            premultiply_alpha: false,
+        }
+    }
+
+    // Pixels of this color become transparent
+    pub fn color_key(mut self, color: Color) -> Self {
+        self.color_key = Some(color);
+        self
+    }
+
+    pub fn no_color_key(mut self) -> Self {
+        self.color_key = None;
+        self
+    }
+
+    // Skips the color key for images which come with their own alpha channel, i.e. most PNGs.
+    pub fn detect_alpha(mut self, detect_alpha: bool) -> Self {
+        self.detect_alpha = detect_alpha;
+        self
+    }
+
+    // Multiplies the color channels by alpha when loading and blends the texture accordingly.
+    // Avoids dark fringes around transparent edges when the sprite is scaled or filtered.
+    pub fn premultiply_alpha(mut self, premultiply_alpha: bool) -> Self {
+        self.premultiply_alpha = premultiply_alpha;
+        self
+    }
+}
+
+// Combination of what we built during lazy-foo tutorials 10-15
+pub struct Sprite<'a> {
+    texture: Texture<'a>,
+    width: u32,
+    height: u32,
+    atlas: Option<Rc<Atlas>>,
+}
+
+impl<'a> Sprite<'a> {
+    pub fn from_surface(
+        surface: Surface,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Error> {
+        Sprite::from_surface_at(surface, None, texture_creator)
+    }
+
+    // Same as `from_surface`, but errors point at the file the surface was loaded from
+    fn from_surface_at(
+        surface: Surface,
+        path: Option<&Path>,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Error> {
+        let width = surface.width();
+        let height = surface.height();
+        let texture = texture_creator
+            .create_texture_from_surface(surface)
+            .map_err(|err| Error::texture_creation(path, err))?;
+
+        Ok(Sprite {
+            texture,
+            width,
+            height,
+            atlas: None,
+        })
+    }
+
+    #[cfg(feature = "image")]
+    pub fn load_from_file(
+        image_path: &Path,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Error> {
+        Sprite::load_from_file_with_options(image_path, &LoadOptions::default(), texture_creator)
+    }
+
+    #[cfg(feature = "image")]
+    pub fn load_from_file_with_options(
+        image_path: &Path,
+        options: &LoadOptions,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Error> {
+        ensure_exists(image_path)?;
+        let mut surface =
+            Surface::from_file(image_path).map_err(|err| Error::image_decode(image_path, err))?;
+
+        let has_alpha = surface.pixel_format_enum().supports_alpha();
+        if let Some(color_key) = options.color_key {
+            if !(options.detect_alpha && has_alpha) {
+                surface
+                    .set_color_key(true, color_key)
+                    .map_err(|err| Error::image_decode(image_path, err))?;
+            }
+        }
+        if !options.premultiply_alpha {
+            return Sprite::from_surface_at(surface, Some(image_path), texture_creator);
+        }
+
+        // Converting to a format with alpha turns the color keyed pixels transparent
+        let mut surface = surface
+            .convert_format(PixelFormatEnum::RGBA32)
+            .map_err(|err| Error::image_decode(image_path, err))?;
+        surface.with_lock_mut(|pixels| {
+            for pixel in pixels.chunks_mut(4) {
+                let alpha = pixel[3] as u16;
+                for channel in &mut pixel[..3] {
+                    *channel = (*channel as u16 * alpha / 0xff) as u8;
+                }
+            }
+        });
+        let sprite = Sprite::from_surface_at(surface, Some(image_path), texture_creator)?;
+        set_premultiplied_blend_mode(&sprite.texture)
+            .map_err(|err| Error::texture_creation(Some(image_path), err))?;
+        Ok(sprite)
+    }
+
+    #[cfg(feature = "ttf")]
+    pub fn load_from_rendered_text(
+        text: &str,
+        color: Color,
+        font: &Font,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Error> {
+        Sprite::load_from_rendered_text_with_mode(
+            text,
+            color,
+            TextMode::Solid,
+            font,
+            texture_creator,
+        )
+    }
+
+    // Style, outline, kerning and hinting are part of the font, see `FontOptions`.
+    #[cfg(feature = "ttf")]
+    pub fn load_from_rendered_text_with_mode(
+        text: &str,
+        color: Color,
+        mode: TextMode,
+        font: &Font,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Error> {
+        let surface = mode.render(text, color, font)?;
+        Sprite::from_surface(surface, texture_creator)
+    }
+
+    // Lets `render` look up clips by frame name, fails if any frame lies outside the texture.
+    pub fn with_atlas(mut self, atlas: Rc<Atlas>) -> Result<Self, Error> {
+        atlas
+            .validate(self.width, self.height)
+            .map_err(|message| Error::Atlas {
+                path: None,
+                message,
+            })?;
+        self.atlas = Some(atlas);
+        Ok(self)
+    }
+
+    pub fn atlas(&self) -> Option<&Atlas> {
+        self.atlas.as_deref()
+    }
+
+    // Rect of the named frame, errors if there is no atlas or it has no such frame.
+    pub fn frame(&self, name: &str) -> Result<Rect, Error> {
+        let atlas = self.atlas.as_ref().ok_or_else(|| Error::Atlas {
+            path: None,
+            message: format!("sprite has no atlas to look up frame {:?}", name),
+        })?;
+        atlas.frame(name).ok_or_else(|| Error::Atlas {
+            path: None,
+            message: format!("unknown frame {:?}", name),
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn set_alpha(&mut self, alpha: u8) {
+        self.texture.set_alpha_mod(alpha)
+    }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.texture.set_blend_mode(blend_mode)
+    }
+
+    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
+        self.texture.set_color_mod(r, g, b);
+    }
+
+    pub fn render<'c, C>(
+        &self,
+        canvas: &mut WindowCanvas,
+        x: i32,
+        y: i32,
+        clip: C,
+    ) -> Result<(), Error>
+    where
+        C: Into<Clip<'c>>,
+    {
+        self.render_ex(canvas, &DrawParams::at(x as f32, y as f32).clip(clip))
+    }
+
+    pub fn render_ex(&self, canvas: &mut WindowCanvas, params: &DrawParams) -> Result<(), Error> {
+        let clip = match params.clip {
+            Clip::Full => None,
+            Clip::Rect(rect) => Some(rect),
+            Clip::Frame(name) => Some(self.frame(name)?),
+        };
+        let (width, height) = match clip {
+            None => (self.width, self.height),
+            Some(clip) => (clip.width(), clip.height()),
+        };
+        let width = width as f32 * params.scale_x;
+        let height = height as f32 * params.scale_y;
+        let center = SDL_FPoint {
+            x: params.origin_x * width,
+            y: params.origin_y * height,
+        };
+        let dst = SDL_FRect {
+            x: params.x - center.x,
+            y: params.y - center.y,
+            w: width,
+            h: height,
+        };
+        let texture = self.texture.raw();
+        let mut previous_color = (0, 0, 0);
+        let mut previous_alpha = 0;
+        let result = unsafe {
+            // Going through the raw texture so shared sprites can be tinted without `&mut`
+            if let Some(color) = params.tint {
+                let (r, g, b) = &mut previous_color;
+                sdl2::sys::SDL_GetTextureColorMod(texture, r, g, b);
+                sdl2::sys::SDL_SetTextureColorMod(texture, color.r, color.g, color.b);
+            }
+            if let Some(alpha) = params.alpha {
+                sdl2::sys::SDL_GetTextureAlphaMod(texture, &mut previous_alpha);
+                sdl2::sys::SDL_SetTextureAlphaMod(texture, alpha);
+            }
+            let result = SDL_RenderCopyExF(
+                canvas.raw(),
+                texture,
+                clip.as_ref().map_or(std::ptr::null(), Rect::raw),
+                &dst,
+                params.degrees,
+                &center,
+                params.flip.bits(),
+            );
+            if params.tint.is_some() {
+                let (r, g, b) = previous_color;
+                sdl2::sys::SDL_SetTextureColorMod(texture, r, g, b);
+            }
+            if params.alpha.is_some() {
+                sdl2::sys::SDL_SetTextureAlphaMod(texture, previous_alpha);
+            }
+            result
+        };
+        if result != 0 {
+            return Err(Error::video("SDL_RenderCopyExF", sdl2::get_error()));
+        }
+        Ok(())
+    }
+
+    // Copies the pixels of the clip into a new RGBA surface, i.e. to create a cursor from a frame.
+    // Textures can't be read directly, so the clip is copied into a target texture of the canvas
+    // without blending and read back from there.
+    pub fn to_surface<'c, C>(
+        &self,
+        canvas: &mut WindowCanvas,
+        clip: C,
+    ) -> Result<Surface<'static>, Error>
+    where
+        C: Into<Clip<'c>>,
+    {
+        let rect = match clip.into() {
+            Clip::Full => Rect::new(0, 0, self.width, self.height),
+            Clip::Rect(rect) => rect,
+            Clip::Frame(name) => self.frame(name)?,
+        };
+        let (width, height) = rect.size();
+        let texture_creator = canvas.texture_creator();
+        let mut target = texture_creator
+            .create_texture_target(PixelFormatEnum::RGBA32, width, height)
+            .map_err(|err| Error::texture_creation(None, err))?;
+
+        let texture = self.texture.raw();
+        let mut blend_mode: c_uint = 0;
+        let mut pixels = Err("clip was not copied".to_string());
+        canvas
+            .with_texture_canvas(&mut target, |target_canvas| unsafe {
+                // Going through the raw texture like `render_ex`, blending would change the alpha
+                SDL_GetTextureBlendMode(texture, &mut blend_mode);
+                SDL_SetTextureBlendMode(texture, 0);
+                pixels = target_canvas
+                    .copy(&self.texture, rect, None)
+                    .and_then(|_| target_canvas.read_pixels(None, PixelFormatEnum::RGBA32));
+                SDL_SetTextureBlendMode(texture, blend_mode);
+            })
+            .map_err(|err| Error::video("SDL_SetRenderTarget", err))?;
+        let pixels = pixels.map_err(|err| Error::video("SDL_RenderReadPixels", err))?;
+
+        let mut surface = Surface::new(width, height, PixelFormatEnum::RGBA32)
+            .map_err(|err| Error::texture_creation(None, err))?;
+        let pitch = surface.pitch() as usize;
+        let row_len = width as usize * 4;
+        // Rows of the surface may be padded
+        surface.with_lock_mut(|data| {
+            for (row, pixels) in data.chunks_mut(pitch).zip(pixels.chunks(row_len)) {
+                row[..row_len].copy_from_slice(pixels);
+            }
+        });
+        Ok(surface)
+    }
+}
+
+// The sdl2 crate only renders at integer coordinates and the sys bindings can't express flipping
+// both ways, so we declare the float variant ourselves with the flip passed as plain bits.
+#[allow(clashing_extern_declarations)]
+extern "C" {
+    fn SDL_RenderCopyExF(
+        renderer: *mut SDL_Renderer,
+        texture: *mut SDL_Texture,
+        srcrect: *const sdl2::sys::SDL_Rect,
+        dstrect: *const SDL_FRect,
+        angle: f64,
+        center: *const SDL_FPoint,
+        flip: c_uint,
+    ) -> c_int;
+}
+
+// The sdl2 crate's BlendMode only covers the predefined modes and the sys bindings declare
+// custom ones as an enum which can't hold them, so we pass them along as plain integers.
+#[allow(clashing_extern_declarations)]
+extern "C" {
+    fn SDL_SetTextureBlendMode(texture: *mut SDL_Texture, blend_mode: c_uint) -> c_int;
+    fn SDL_GetTextureBlendMode(texture: *mut SDL_Texture, blend_mode: *mut c_uint) -> c_int;
+}
+
+#[cfg(feature = "image")]
+#[allow(clashing_extern_declarations)]
+extern "C" {
+    fn SDL_ComposeCustomBlendMode(
+        src_color_factor: sdl2::sys::SDL_BlendFactor,
+        dst_color_factor: sdl2::sys::SDL_BlendFactor,
+        color_operation: sdl2::sys::SDL_BlendOperation,
+        src_alpha_factor: sdl2::sys::SDL_BlendFactor,
+        dst_alpha_factor: sdl2::sys::SDL_BlendFactor,
+        alpha_operation: sdl2::sys::SDL_BlendOperation,
+    ) -> c_uint;
+}
+
+#[cfg(feature = "image")]
+fn set_premultiplied_blend_mode(texture: &Texture) -> Result<(), String> {
+    use sdl2::sys::SDL_BlendFactor::{SDL_BLENDFACTOR_ONE, SDL_BLENDFACTOR_ONE_MINUS_SRC_ALPHA};
+    use sdl2::sys::SDL_BlendOperation::SDL_BLENDOPERATION_ADD;
+
+    let result = unsafe {
+        let blend_mode = SDL_ComposeCustomBlendMode(
+            SDL_BLENDFACTOR_ONE,
+            SDL_BLENDFACTOR_ONE_MINUS_SRC_ALPHA,
+            SDL_BLENDOPERATION_ADD,
+            SDL_BLENDFACTOR_ONE,
+            SDL_BLENDFACTOR_ONE_MINUS_SRC_ALPHA,
+            SDL_BLENDOPERATION_ADD,
+        );
+        SDL_SetTextureBlendMode(texture.raw(), blend_mode)
+    };
+    if result != 0 {
+        // Not every renderer supports custom blend modes
+        return Err(format!(
+            "unable to use premultiplied alpha blending: {}",
+            sdl2::get_error()
+        ));
+    }
+    Ok(())
+}
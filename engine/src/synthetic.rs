@@ -0,0 +1,102 @@
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::{MouseButton, MouseState, MouseWheelDirection};
+
+// Input events as SDL sends them, to script a game in tests via `ScriptedEvents` or to push
+// through `EventSubsystem::push_event`.
+//
+// Timestamps and window ids are 0. The scancodes of key events are looked up in SDL's keymap,
+// which is only filled in once the video subsystem was initialized, i.e. by `Headless::new`.
+
+pub fn key_down(keycode: Keycode) -> Event {
+    Event::KeyDown {
+        timestamp: 0,
+        window_id: 0,
+        keycode: Some(keycode),
+        scancode: Scancode::from_keycode(keycode),
+        keymod: Mod::NOMOD,
+        repeat: false,
+    }
+}
+
+pub fn key_up(keycode: Keycode) -> Event {
+    Event::KeyUp {
+        timestamp: 0,
+        window_id: 0,
+        keycode: Some(keycode),
+        scancode: Scancode::from_keycode(keycode),
+        keymod: Mod::NOMOD,
+        repeat: false,
+    }
+}
+
+// Pressing and releasing the key
+pub fn key_press(keycode: Keycode) -> Vec<Event> {
+    vec![key_down(keycode), key_up(keycode)]
+}
+
+pub fn text_input(text: &str) -> Event {
+    Event::TextInput {
+        timestamp: 0,
+        window_id: 0,
+        text: text.to_string(),
+    }
+}
+
+// Moving the mouse to the position, without relative motion and with no buttons held
+pub fn mouse_motion(x: i32, y: i32) -> Event {
+    Event::MouseMotion {
+        timestamp: 0,
+        window_id: 0,
+        which: 0,
+        mousestate: MouseState::from_sdl_state(0),
+        x,
+        y,
+        xrel: 0,
+        yrel: 0,
+    }
+}
+
+pub fn mouse_down(mouse_btn: MouseButton, x: i32, y: i32) -> Event {
+    Event::MouseButtonDown {
+        timestamp: 0,
+        window_id: 0,
+        which: 0,
+        mouse_btn,
+        clicks: 1,
+        x,
+        y,
+    }
+}
+
+pub fn mouse_up(mouse_btn: MouseButton, x: i32, y: i32) -> Event {
+    Event::MouseButtonUp {
+        timestamp: 0,
+        window_id: 0,
+        which: 0,
+        mouse_btn,
+        clicks: 1,
+        x,
+        y,
+    }
+}
+
+// Moving the mouse to the position and clicking there
+pub fn click(mouse_btn: MouseButton, x: i32, y: i32) -> Vec<Event> {
+    vec![
+        mouse_motion(x, y),
+        mouse_down(mouse_btn, x, y),
+        mouse_up(mouse_btn, x, y),
+    ]
+}
+
+pub fn mouse_wheel(x: i32, y: i32) -> Event {
+    Event::MouseWheel {
+        timestamp: 0,
+        window_id: 0,
+        which: 0,
+        x,
+        y,
+        direction: MouseWheelDirection::Normal,
+    }
+}
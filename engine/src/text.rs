@@ -0,0 +1,132 @@
+use crate::error::Error;
+use sdl2::pixels::Color;
+use sdl2::surface::Surface;
+use sdl2::ttf::{Font, FontStyle, Hinting};
+
+// How SDL_ttf turns text into pixels.
+//
+// `Solid` is fast but aliased, `Shaded` antialiases against an opaque background color and
+// `Blended` antialiases against transparency. `BlendedWrapped` is `Blended` broken into lines
+// at the given width in pixels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextMode {
+    Solid,
+    Shaded(Color),
+    Blended,
+    BlendedWrapped(u32),
+}
+
+impl TextMode {
+    pub(crate) fn render<'s>(
+        self,
+        text: &str,
+        color: Color,
+        font: &Font,
+    ) -> Result<Surface<'s>, Error> {
+        let rendering = font.render(text);
+        match self {
+            TextMode::Solid => rendering
+                .solid(color)
+                .map_err(|err| Error::ttf("TTF_RenderUTF8_Solid", None, err)),
+            TextMode::Shaded(background) => rendering
+                .shaded(color, background)
+                .map_err(|err| Error::ttf("TTF_RenderUTF8_Shaded", None, err)),
+            TextMode::Blended => rendering
+                .blended(color)
+                .map_err(|err| Error::ttf("TTF_RenderUTF8_Blended", None, err)),
+            TextMode::BlendedWrapped(width) => rendering
+                .blended_wrapped(color, width)
+                .map_err(|err| Error::ttf("TTF_RenderUTF8_Blended_Wrapped", None, err)),
+        }
+    }
+}
+
+// Same as sdl2's `Hinting`, which we can't use as part of a cache key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FontHinting {
+    Normal,
+    Light,
+    Mono,
+    None,
+}
+
+impl From<FontHinting> for Hinting {
+    fn from(hinting: FontHinting) -> Self {
+        match hinting {
+            FontHinting::Normal => Hinting::Normal,
+            FontHinting::Light => Hinting::Light,
+            FontHinting::Mono => Hinting::Mono,
+            FontHinting::None => Hinting::None,
+        }
+    }
+}
+
+// Settings SDL_ttf keeps per font, applied right after it is loaded.
+// The defaults match the ones of a freshly loaded font.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct FontOptions {
+    style: FontStyle,
+    outline: u16,
+    kerning: bool,
+    hinting: FontHinting,
+}
+
+impl Default for FontOptions {
+    fn default() -> Self {
+        FontOptions::new()
+    }
+}
+
+impl FontOptions {
+    pub fn new() -> Self {
+        FontOptions {
+            style: FontStyle::NORMAL,
+            outline: 0,
+            kerning: true,
+            hinting: FontHinting::Normal,
+        }
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.style.set(FontStyle::BOLD, bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.style.set(FontStyle::ITALIC, italic);
+        self
+    }
+
+    pub fn underline(mut self, underline: bool) -> Self {
+        self.style.set(FontStyle::UNDERLINE, underline);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.style.set(FontStyle::STRIKETHROUGH, strikethrough);
+        self
+    }
+
+    // Renders only the outline of the glyphs, grown by this many pixels
+    pub fn outline(mut self, width: u16) -> Self {
+        self.outline = width;
+        self
+    }
+
+    pub fn kerning(mut self, kerning: bool) -> Self {
+        self.kerning = kerning;
+        self
+    }
+
+    pub fn hinting(mut self, hinting: FontHinting) -> Self {
+        self.hinting = hinting;
+        self
+    }
+
+    pub fn apply(&self, font: &mut Font) {
+        font.set_style(self.style);
+        font.set_outline_width(self.outline);
+        font.set_kerning(self.kerning);
+        font.set_hinting(self.hinting.into());
+    }
+}
@@ -0,0 +1,698 @@
+use crate::error::Error;
+use crate::sprite::Sprite;
+use crate::text::TextMode;
+use sdl2::clipboard::ClipboardUtil;
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod, TextInputUtil};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::ttf::Font;
+use sdl2::video::WindowContext;
+use sdl2::VideoSubsystem;
+use std::convert::TryFrom;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+// Space between the border and the text
+const PADDING: i32 = 4;
+const CARET_BLINK: Duration = Duration::from_millis(530);
+
+// Single line text field edited via the keyboard, mouse and IME.
+//
+// Text is inserted from SDL `TextInput` events and the string being composed via an IME, which
+// SDL reports via `TextEditing` events, is shown at the caret until it is committed.
+// Supports selecting with the mouse or shift + arrows, moving and deleting by word with ctrl and
+// copy, cut and paste via the SDL clipboard. Clicking into the field starts SDL text input,
+// clicking elsewhere stops it.
+pub struct TextInput<'a> {
+    font: Rc<Font<'a, 'static>>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    text_input: TextInputUtil,
+    clipboard: ClipboardUtil,
+    rect: Rect,
+    editor: Editor,
+    preedit: String,
+    // Byte offset of the IME's cursor into the preedit string
+    preedit_cursor: usize,
+    focused: bool,
+    dragging: bool,
+    // Pixels the text is scrolled to the left to keep the caret inside the field
+    scroll: i32,
+    text_color: Color,
+    background: Color,
+    selection_color: Color,
+    rendered: Option<(String, Sprite<'a>)>,
+    ime_rect: Option<Rect>,
+    blink_since: Instant,
+}
+
+impl<'a> TextInput<'a> {
+    pub fn new(
+        font: Rc<Font<'a, 'static>>,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        video: &VideoSubsystem,
+        rect: Rect,
+    ) -> Self {
+        TextInput {
+            font,
+            texture_creator,
+            text_input: video.text_input(),
+            clipboard: video.clipboard(),
+            rect,
+            editor: Editor::default(),
+            preedit: String::new(),
+            preedit_cursor: 0,
+            focused: false,
+            dragging: false,
+            scroll: 0,
+            text_color: Color::BLACK,
+            background: Color::WHITE,
+            selection_color: Color::RGB(0xb4, 0xd5, 0xfe),
+            rendered: None,
+            ime_rect: None,
+            blink_since: Instant::now(),
+        }
+    }
+
+    // Maximum number of characters, longer input is cut off
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.editor.max_length = Some(max_length);
+        self
+    }
+
+    pub fn text_color(mut self, color: Color) -> Self {
+        self.text_color = color;
+        self.rendered = None;
+        self
+    }
+
+    pub fn background(mut self, color: Color) -> Self {
+        self.background = color;
+        self
+    }
+
+    pub fn selection_color(mut self, color: Color) -> Self {
+        self.selection_color = color;
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.editor.text
+    }
+
+    // Replaces the text and moves the caret to its end
+    pub fn set_text(&mut self, text: &str) {
+        self.editor.set_text(text);
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    // Byte offset of the caret into the text
+    pub fn caret(&self) -> usize {
+        self.editor.caret
+    }
+
+    pub fn selected_text(&self) -> Option<&str> {
+        self.editor.selected_text()
+    }
+
+    // The string currently composed via the IME, not part of the text until committed
+    pub fn preedit(&self) -> &str {
+        &self.preedit
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    // Starts SDL text input so typing produces `TextInput` events
+    pub fn focus(&mut self) {
+        if !self.focused {
+            self.focused = true;
+            self.ime_rect = None;
+            self.text_input.start();
+            self.blink_since = Instant::now();
+        }
+    }
+
+    pub fn blur(&mut self) {
+        if self.focused {
+            self.focused = false;
+            self.dragging = false;
+            self.preedit.clear();
+            self.text_input.stop();
+        }
+    }
+
+    pub fn select_all(&mut self) {
+        self.editor.select_all();
+    }
+
+    pub fn copy(&self) -> Result<(), Error> {
+        match self.selected_text() {
+            Some(selected) => self
+                .clipboard
+                .set_clipboard_text(selected)
+                .map_err(|err| Error::video("SDL_SetClipboardText", err)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn cut(&mut self) -> Result<(), Error> {
+        self.copy()?;
+        self.editor.delete_selection();
+        Ok(())
+    }
+
+    // Inserts the first line of the clipboard text
+    pub fn paste(&mut self) -> Result<(), Error> {
+        if !self.clipboard.has_clipboard_text() {
+            return Ok(());
+        }
+        let text = self
+            .clipboard
+            .clipboard_text()
+            .map_err(|err| Error::video("SDL_GetClipboardText", err))?;
+        self.editor.insert(text.lines().next().unwrap_or(""));
+        Ok(())
+    }
+
+    // Returns true if the event was meant for this field.
+    // Mouse events are only handled when they hit the field, keyboard events only while focused.
+    pub fn handle_event(&mut self, event: &Event) -> Result<bool, Error> {
+        let handled = match event {
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                clicks,
+                x,
+                y,
+                ..
+            } => {
+                if !self.rect.contains_point((*x, *y)) {
+                    self.blur();
+                    return Ok(false);
+                }
+                self.focus();
+                let idx = self.index_at(*x)?;
+                if *clicks >= 2 {
+                    self.editor.select_word(idx);
+                } else {
+                    self.editor.move_caret(idx, false);
+                    self.dragging = true;
+                }
+                true
+            }
+            Event::MouseMotion { x, .. } if self.dragging => {
+                let idx = self.index_at(*x)?;
+                self.editor.move_caret(idx, true);
+                true
+            }
+            Event::MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                ..
+            } if self.dragging => {
+                self.dragging = false;
+                true
+            }
+            _ if !self.focused => false,
+            Event::TextInput { text, .. } => {
+                self.preedit.clear();
+                self.editor.insert(text);
+                true
+            }
+            Event::TextEditing { text, start, .. } => {
+                self.preedit = text.clone();
+                self.preedit_cursor = char_offset(text, *start);
+                true
+            }
+            // The IME handles keys while composing
+            Event::KeyDown { .. } if !self.preedit.is_empty() => true,
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } => self.handle_key(*keycode, *keymod)?,
+            _ => false,
+        };
+        if handled {
+            self.blink_since = Instant::now();
+        }
+        Ok(handled)
+    }
+
+    fn handle_key(&mut self, keycode: Keycode, keymod: Mod) -> Result<bool, Error> {
+        let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+        let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+        // Cmd on macOS
+        let command = ctrl || keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD);
+        match keycode {
+            Keycode::A if command => self.select_all(),
+            Keycode::C if command => self.copy()?,
+            Keycode::X if command => self.cut()?,
+            Keycode::V if command => self.paste()?,
+            _ => return Ok(self.editor.edit(keycode, shift, ctrl)),
+        }
+        Ok(true)
+    }
+
+    // Text with the preedit string at the caret
+    fn display_text(&self) -> String {
+        let mut text = self.editor.text.clone();
+        text.insert_str(self.editor.caret, &self.preedit);
+        text
+    }
+
+    fn width_of(&self, text: &str) -> Result<i32, Error> {
+        if text.is_empty() {
+            return Ok(0);
+        }
+        self.font
+            .size_of(text)
+            .map(|(width, _)| width as i32)
+            .map_err(|err| Error::ttf("TTF_SizeUTF8", None, err))
+    }
+
+    fn text_x(&self) -> i32 {
+        self.rect.x() + PADDING - self.scroll
+    }
+
+    // Character boundary closest to the window x coordinate
+    fn index_at(&self, x: i32) -> Result<usize, Error> {
+        closest_index(&self.editor.text, x - self.text_x(), |text| {
+            self.width_of(text)
+        })
+    }
+
+    pub fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Error> {
+        let draw_color = canvas.draw_color();
+        let clip_rect = canvas.clip_rect();
+        let result = self.render_field(canvas);
+        canvas.set_clip_rect(clip_rect);
+        canvas.set_draw_color(draw_color);
+        result
+    }
+
+    fn render_field(&mut self, canvas: &mut WindowCanvas) -> Result<(), Error> {
+        let display = self.display_text();
+        let caret = self.editor.caret;
+        let caret_offset = caret + self.preedit_cursor.min(self.preedit.len());
+        let caret_x = self.width_of(&display[..caret_offset])?;
+
+        // Keeps the caret inside the field
+        let inner_width = self.rect.width() as i32 - 2 * PADDING;
+        if caret_x - self.scroll > inner_width {
+            self.scroll = caret_x - inner_width;
+        } else if caret_x < self.scroll {
+            self.scroll = caret_x;
+        }
+        let text_x = self.text_x();
+        let text_y = self.rect.y() + (self.rect.height() as i32 - self.font.height()) / 2;
+        let line_height = self.font.height().max(1) as u32;
+
+        canvas.set_draw_color(self.background);
+        canvas
+            .fill_rect(self.rect)
+            .map_err(|err| Error::video("SDL_RenderFillRect", err))?;
+        canvas.set_draw_color(if self.focused {
+            self.text_color
+        } else {
+            Color::GRAY
+        });
+        canvas
+            .draw_rect(self.rect)
+            .map_err(|err| Error::video("SDL_RenderDrawRect", err))?;
+        canvas.set_clip_rect(Rect::new(
+            self.rect.x() + 1,
+            self.rect.y() + 1,
+            self.rect.width().saturating_sub(2),
+            self.rect.height().saturating_sub(2),
+        ));
+
+        let (start, end) = self.editor.selection();
+        if start != end && self.preedit.is_empty() {
+            let start_x = self.width_of(&self.editor.text[..start])?;
+            let end_x = self.width_of(&self.editor.text[..end])?;
+            canvas.set_draw_color(self.selection_color);
+            canvas
+                .fill_rect(Rect::new(
+                    text_x + start_x,
+                    text_y,
+                    (end_x - start_x) as u32,
+                    line_height,
+                ))
+                .map_err(|err| Error::video("SDL_RenderFillRect", err))?;
+        }
+
+        if !display.is_empty() {
+            let is_current = matches!(&self.rendered, Some((text, _)) if *text == display);
+            if !is_current {
+                let surface = TextMode::Blended.render(&display, self.text_color, &self.font)?;
+                let sprite = Sprite::from_surface(surface, self.texture_creator)?;
+                self.rendered = Some((display.clone(), sprite));
+            }
+            if let Some((_, sprite)) = &self.rendered {
+                sprite.render(canvas, text_x, text_y, None)?;
+            }
+        }
+
+        canvas.set_draw_color(self.text_color);
+        if !self.preedit.is_empty() {
+            // Underlines the string being composed
+            let start_x = self.width_of(&display[..caret])?;
+            let end_x = self.width_of(&display[..caret + self.preedit.len()])?;
+            let y = text_y + self.font.ascent() + 2;
+            canvas
+                .draw_line((text_x + start_x, y), (text_x + end_x, y))
+                .map_err(|err| Error::video("SDL_RenderDrawLine", err))?;
+        }
+
+        if self.focused {
+            let blinks = self.blink_since.elapsed().as_millis() / CARET_BLINK.as_millis();
+            if blinks % 2 == 0 {
+                canvas
+                    .fill_rect(Rect::new(text_x + caret_x, text_y, 1, line_height))
+                    .map_err(|err| Error::video("SDL_RenderFillRect", err))?;
+            }
+            // Lets the IME show its candidate window next to the caret
+            let ime_rect = Rect::new(text_x + caret_x, self.rect.y(), 1, self.rect.height());
+            if self.ime_rect != Some(ime_rect) {
+                self.text_input.set_rect(ime_rect);
+                self.ime_rect = Some(ime_rect);
+            }
+        }
+        Ok(())
+    }
+}
+
+// The text being edited with its caret and selection, all of a `TextInput` that works without SDL
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Editor {
+    text: String,
+    // Byte offsets into the text, the selection spans from the anchor to the caret
+    caret: usize,
+    anchor: usize,
+    max_length: Option<usize>,
+}
+
+impl Editor {
+    fn set_text(&mut self, text: &str) {
+        self.text.clear();
+        self.caret = 0;
+        self.anchor = 0;
+        self.insert(text);
+    }
+
+    fn selection(&self) -> (usize, usize) {
+        (self.caret.min(self.anchor), self.caret.max(self.anchor))
+    }
+
+    fn selected_text(&self) -> Option<&str> {
+        let (start, end) = self.selection();
+        if start == end {
+            None
+        } else {
+            Some(&self.text[start..end])
+        }
+    }
+
+    fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.text.len();
+    }
+
+    // Selects the word at the index, or the one after it when the index is between words
+    fn select_word(&mut self, idx: usize) {
+        self.anchor = prev_word(&self.text, next_word(&self.text, idx));
+        self.caret = next_word_end(&self.text, self.anchor);
+    }
+
+    // Moves the caret for arrow, home and end keys and deletes for backspace and delete,
+    // ctrl moves and deletes by word and shift extends the selection. Returns false for other
+    // keys.
+    fn edit(&mut self, keycode: Keycode, shift: bool, ctrl: bool) -> bool {
+        let (start, end) = self.selection();
+        match keycode {
+            // Without shift a selection collapses to the side moved towards
+            Keycode::Left if !shift && start != end => self.move_caret(start, false),
+            Keycode::Right if !shift && start != end => self.move_caret(end, false),
+            Keycode::Left if ctrl => self.move_caret(prev_word(&self.text, self.caret), shift),
+            Keycode::Right if ctrl => self.move_caret(next_word(&self.text, self.caret), shift),
+            Keycode::Left => self.move_caret(prev_char(&self.text, self.caret), shift),
+            Keycode::Right => self.move_caret(next_char(&self.text, self.caret), shift),
+            Keycode::Home | Keycode::Up => self.move_caret(0, shift),
+            Keycode::End | Keycode::Down => self.move_caret(self.text.len(), shift),
+            Keycode::Backspace if start == end => {
+                let from = if ctrl {
+                    prev_word(&self.text, self.caret)
+                } else {
+                    prev_char(&self.text, self.caret)
+                };
+                self.delete(from, self.caret);
+            }
+            Keycode::Delete if start == end => {
+                let to = if ctrl {
+                    next_word(&self.text, self.caret)
+                } else {
+                    next_char(&self.text, self.caret)
+                };
+                self.delete(self.caret, to);
+            }
+            Keycode::Backspace | Keycode::Delete => self.delete_selection(),
+            _ => return false,
+        }
+        true
+    }
+
+    // Replaces the selection with the text, dropping control characters and whatever exceeds
+    // the max length
+    fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let available = self.max_length.map_or(usize::MAX, |max| {
+            max.saturating_sub(self.text.chars().count())
+        });
+        let text: String = text
+            .chars()
+            .filter(|ch| !ch.is_control())
+            .take(available)
+            .collect();
+        self.text.insert_str(self.caret, &text);
+        self.move_caret(self.caret + text.len(), false);
+    }
+
+    fn delete_selection(&mut self) {
+        let (start, end) = self.selection();
+        self.delete(start, end);
+    }
+
+    fn delete(&mut self, start: usize, end: usize) {
+        self.text.replace_range(start..end, "");
+        self.move_caret(start, false);
+    }
+
+    fn move_caret(&mut self, caret: usize, extend_selection: bool) {
+        self.caret = caret;
+        if !extend_selection {
+            self.anchor = caret;
+        }
+    }
+}
+
+// Character boundary of the text closest to x, measured from the start of the text
+fn closest_index<M>(text: &str, x: i32, width_of: M) -> Result<usize, Error>
+where
+    M: Fn(&str) -> Result<i32, Error>,
+{
+    let mut closest = (0, i32::MAX);
+    for idx in text
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(Some(text.len()))
+    {
+        let distance = (width_of(&text[..idx])? - x).abs();
+        if distance < closest.1 {
+            closest = (idx, distance);
+        }
+    }
+    Ok(closest.0)
+}
+
+// Byte offset of the character at the index, the end of the text if it is out of range
+fn char_offset(text: &str, chars: i32) -> usize {
+    usize::try_from(chars)
+        .ok()
+        .and_then(|chars| text.char_indices().nth(chars))
+        .map_or(text.len(), |(idx, _)| idx)
+}
+
+fn prev_char(text: &str, idx: usize) -> usize {
+    text[..idx].char_indices().last().map_or(0, |(idx, _)| idx)
+}
+
+fn next_char(text: &str, idx: usize) -> usize {
+    text[idx..]
+        .chars()
+        .next()
+        .map_or(idx, |ch| idx + ch.len_utf8())
+}
+
+// Start of the word before the index, skipping whitespace in between
+fn prev_word(text: &str, idx: usize) -> usize {
+    let before = text[..idx].trim_end();
+    before
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| ch.is_whitespace())
+        .map_or(0, |(idx, ch)| idx + ch.len_utf8())
+}
+
+// Start of the word after the index, skipping the rest of the current word
+fn next_word(text: &str, idx: usize) -> usize {
+    let rest = &text[next_word_end(text, idx)..];
+    text.len() - rest.trim_start().len()
+}
+
+// End of the word at the index
+fn next_word_end(text: &str, idx: usize) -> usize {
+    text[idx..]
+        .char_indices()
+        .find(|(_, ch)| ch.is_whitespace())
+        .map_or(text.len(), |(offset, _)| idx + offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn editor(text: &str) -> Editor {
+        let mut editor = Editor::default();
+        editor.set_text(text);
+        editor
+    }
+
+    #[test]
+    fn words() {
+        let text = "hello  big world";
+        assert_eq!(prev_word(text, 16), 11);
+        assert_eq!(prev_word(text, 11), 7);
+        assert_eq!(prev_word(text, 9), 7);
+        assert_eq!(prev_word(text, 7), 0);
+        assert_eq!(prev_word(text, 0), 0);
+        assert_eq!(next_word(text, 0), 7);
+        assert_eq!(next_word(text, 5), 7);
+        assert_eq!(next_word(text, 7), 11);
+        assert_eq!(next_word(text, 11), 16);
+        assert_eq!(next_word(text, 16), 16);
+        assert_eq!(next_word_end(text, 7), 10);
+        assert_eq!(next_word_end(text, 11), 16);
+    }
+
+    #[test]
+    fn chars() {
+        let text = "über";
+        assert_eq!(next_char(text, 0), 2);
+        assert_eq!(prev_char(text, 2), 0);
+        assert_eq!(prev_char(text, 0), 0);
+        assert_eq!(next_char(text, 5), 5);
+
+        assert_eq!(char_offset("かな", 1), 3);
+        assert_eq!(char_offset("かな", 2), 6);
+        assert_eq!(char_offset("かな", 5), 6);
+        assert_eq!(char_offset("かな", -1), 6);
+    }
+
+    #[test]
+    fn caret_stays_inside_the_text() {
+        let mut editor = editor("ab");
+        assert_eq!(editor.caret, 2);
+        assert!(editor.edit(Keycode::Right, false, false));
+        assert!(editor.edit(Keycode::Right, false, true));
+        assert_eq!(editor.caret, 2);
+        assert!(editor.edit(Keycode::Delete, false, false));
+        assert_eq!(editor.text, "ab");
+        editor.edit(Keycode::Up, false, false);
+        assert_eq!(editor.caret, 0);
+        editor.edit(Keycode::Left, false, true);
+        editor.edit(Keycode::Backspace, false, false);
+        assert_eq!((editor.text.as_str(), editor.caret), ("ab", 0));
+        editor.edit(Keycode::Down, true, false);
+        assert_eq!(editor.selected_text(), Some("ab"));
+        assert!(!editor.edit(Keycode::A, false, true));
+    }
+
+    #[test]
+    fn editing() {
+        let mut editor = editor("hello world");
+        editor.edit(Keycode::Left, false, true);
+        assert_eq!(editor.caret, 6);
+        editor.edit(Keycode::Right, true, false);
+        editor.edit(Keycode::Right, true, false);
+        assert_eq!(editor.selected_text(), Some("wo"));
+        editor.insert("WO");
+        assert_eq!(editor.text, "hello WOrld");
+
+        // Without shift the selection collapses to the side moved towards
+        editor.edit(Keycode::Left, true, true);
+        assert_eq!(editor.selected_text(), Some("WO"));
+        editor.edit(Keycode::Right, false, false);
+        assert_eq!((editor.selected_text(), editor.caret), (None, 8));
+
+        editor.edit(Keycode::End, false, false);
+        editor.edit(Keycode::Backspace, false, true);
+        assert_eq!(editor.text, "hello ");
+        editor.edit(Keycode::Home, false, false);
+        editor.edit(Keycode::Delete, false, false);
+        assert_eq!(editor.text, "ello ");
+        editor.edit(Keycode::Delete, false, true);
+        assert_eq!(editor.text, "");
+
+        editor.set_text("one two");
+        editor.select_all();
+        editor.edit(Keycode::Backspace, false, false);
+        assert_eq!((editor.text.as_str(), editor.caret), ("", 0));
+    }
+
+    #[test]
+    fn select_word() {
+        let mut editor = editor("hello big world");
+        editor.select_word(2);
+        assert_eq!(editor.selected_text(), Some("hello"));
+        editor.select_word(12);
+        assert_eq!(editor.selected_text(), Some("world"));
+        // Right after a word, that word is selected
+        editor.select_word(9);
+        assert_eq!(editor.selected_text(), Some("big"));
+        editor.select_word(0);
+        assert_eq!(editor.selected_text(), Some("hello"));
+    }
+
+    #[test]
+    fn max_length() {
+        let mut editor = Editor {
+            max_length: Some(5),
+            ..Editor::default()
+        };
+        editor.set_text("a text longer than the max length");
+        assert_eq!(editor.text, "a tex");
+        editor.insert("more");
+        assert_eq!(editor.text, "a tex");
+        // Replacing a selection makes room
+        editor.edit(Keycode::Left, true, false);
+        editor.insert("\tüb");
+        assert_eq!((editor.text.as_str(), editor.caret), ("a teü", 6));
+    }
+
+    #[test]
+    fn hit_testing() {
+        let width_of = |text: &str| Ok(text.chars().count() as i32 * 10);
+        assert_eq!(closest_index("abc", -5, width_of).unwrap(), 0);
+        assert_eq!(closest_index("abc", 14, width_of).unwrap(), 1);
+        assert_eq!(closest_index("abc", 16, width_of).unwrap(), 2);
+        assert_eq!(closest_index("abc", 100, width_of).unwrap(), 3);
+        assert_eq!(closest_index("äb", 10, width_of).unwrap(), 2);
+        assert_eq!(closest_index("", 10, width_of).unwrap(), 0);
+    }
+}
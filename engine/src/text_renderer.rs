@@ -0,0 +1,57 @@
+use crate::error::Error;
+use crate::layout::{LayoutOptions, TextLayout};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+
+// Draws text with a particular font, implemented for TrueType fonts via `GlyphCache` and for
+// pixel fonts via `BitmapFont`, so code drawing text doesn't need to care which one it got.
+pub trait TextRenderer {
+    // Height of a single line of glyphs
+    fn font_height(&self) -> i32;
+
+    // Distance from one line to the next
+    fn line_height(&self) -> i32;
+
+    // Width of a single line of text
+    fn text_width(&self, text: &str) -> Result<u32, Error>;
+
+    // Draws each glyph where the layout put it, the layout should be made with the same font.
+    fn draw_layout(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        layout: &TextLayout,
+        color: Color,
+    ) -> Result<(), Error>;
+
+    fn layout(
+        &self,
+        text: &str,
+        bounds: Rect,
+        options: &LayoutOptions,
+    ) -> Result<TextLayout, Error> {
+        TextLayout::with_measure(
+            text,
+            bounds,
+            options,
+            self.font_height(),
+            self.line_height(),
+            |text| self.text_width(text),
+        )
+    }
+
+    // Draws the text with its top left at x/y, `\n` starts a new line.
+    fn draw(
+        &mut self,
+        canvas: &mut WindowCanvas,
+        text: &str,
+        x: i32,
+        y: i32,
+        color: Color,
+    ) -> Result<(), Error> {
+        // Never wraps since Rect clamps the size to the largest one SDL supports
+        let bounds = Rect::new(x, y, u32::MAX, u32::MAX);
+        let layout = self.layout(text, bounds, &LayoutOptions::new())?;
+        self.draw_layout(canvas, &layout, color)
+    }
+}
@@ -2,6 +2,7 @@ use std::error::Error;
 use std::thread::sleep;
 use std::time::Duration;
 
+use lazy_foo::{asset_path, RendererConfig};
 use sdl2::render::{Texture, TextureCreator, WindowCanvas};
 use sdl2::surface::Surface;
 use sdl2::video::WindowContext;
@@ -11,34 +12,40 @@ const WIDTH: u32 = 640;
 const HEIGHT: u32 = 480;
 
 fn init() -> Result<(Sdl, WindowCanvas), Box<dyn Error>> {
-    // Initialize SDL
-    let sdl_context: Sdl = sdl2::init()?;
-    let video = sdl_context.video()?;
-
-    // Create Window
-    let window = video
-        .window("SDL Tutorial", WIDTH, HEIGHT)
-        .position_centered()
-        // .allow_highdpi() creates the window in high-DPI mode if available
-        .build()?;
-
-    // Create Canvas to render
-    let canvas = window
-        .into_canvas()
+    // Describes the Window and the Canvas to render to. Under the hood this initializes SDL,
+    // creates the Window via `video.window(title, width, height).position_centered()` and
+    // the Canvas via `window.into_canvas()`.
+    let config = RendererConfig::new("SDL Tutorial", WIDTH, HEIGHT)
+        // .resizable(true) if the user should be able to resize the window
+        // .hidden(true)    to not show the window at all, i.e. when running tests
+        //
         // .accelerated()   if we want to leverage the GPU to render
-        // .software()      to enforce Software rendering (default and opposite of accelerated)
-        // .present_vsync() if we want to synchronize renderer `present` with display refresh rate
+        // .software()      to enforce Software rendering (opposite of accelerated)
+        // .software_fallback(false) to fail instead of rendering in software without a GPU
+        .software()
+        // .vsync(true)     if we want to synchronize renderer `present` with display refresh rate
         //                  it only has an effect in accelerated mode.
-        .build()?;
+        .vsync(false)
+        // Allows overriding any of the above via `AMA_SDL_*` env vars or flags like `--headless`
+        .with_env_and_args()?;
+
+    // Without a GPU an accelerated renderer falls back to software rendering, unless
+    // `.software_fallback(false)` was set, and `fallback` holds the reason.
+    let renderer = config.init()?;
+    if let Some(err) = &renderer.fallback {
+        eprintln!("WARN: rendering in software: {}", err);
+    }
 
     // window is still accessible via `canvas.window()` since it 'owns' it, so returning
     // sdl_context and canvas is sufficient
-    Ok((sdl_context, canvas))
+    Ok((renderer.sdl_context, renderer.canvas))
 }
 
-fn load_media(texture_creator: &TextureCreator<WindowContext>) -> Result<Texture, Box<dyn Error>> {
+fn load_media(
+    texture_creator: &TextureCreator<WindowContext>,
+) -> Result<Texture<'_>, Box<dyn Error>> {
     // Load Image
-    let surface = Surface::load_bmp("assets/hello_rust_sdl.bmp")?;
+    let surface = Surface::load_bmp(asset_path("hello_rust_sdl.bmp"))?;
     // Convert to Texture so we can render it
     // Same as: let texture = surface.as_texture(texture_creator)?;
     let texture = texture_creator.create_texture_from_surface(surface)?;
@@ -1,11 +1,23 @@
-use lazy_foo::{init_renderer, load_media};
+use lazy_foo::{asset_path, init_renderer, load_media, Renderer};
 use sdl2::event::Event;
+use sdl2::render::{Texture, WindowCanvas};
+
+// Redraw the image on each loop iteration.
+// In a game things would update state/position, etc. and redraw differently each time.
+fn render(canvas: &mut WindowCanvas, img_x: &Texture) -> Result<(), String> {
+    canvas.clear();
+    canvas.copy(img_x, None, None)
+}
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     let texture_creator = canvas.texture_creator();
-    let img_x = load_media("assets/x.bmp", &texture_creator).expect("FATAL: failed to load image.");
+    let img_x =
+        load_media(asset_path("x.bmp"), &texture_creator).expect("FATAL: failed to load image.");
 
     let mut event_pump = sdl_context
         .event_pump()
@@ -19,12 +31,7 @@ fn main() {
             break 'running;
         }
 
-        // Redraw the image on each loop iteration.
-        // In a game things would update state/position, etc. and redraw differently each time.
-        canvas.clear();
-        canvas
-            .copy(&img_x, None, None)
-            .expect("FATAL: failed to draw to canvas.");
+        render(&mut canvas, &img_x).expect("FATAL: failed to draw to canvas.");
         canvas.present();
     }
 }
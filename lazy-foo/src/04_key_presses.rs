@@ -1,26 +1,25 @@
-use lazy_foo::{init_renderer, load_media};
+use lazy_foo::{init_renderer, Assets, Game, GameLoop, Renderer};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
-use sdl2::render::{Texture, TextureCreator};
-use sdl2::video::WindowContext;
+use sdl2::render::{Texture, WindowCanvas};
+use sdl2::EventPump;
 use std::error::Error;
+use std::rc::Rc;
 
 struct Textures<'a> {
-    default: Texture<'a>,
-    up: Texture<'a>,
-    right: Texture<'a>,
-    down: Texture<'a>,
-    left: Texture<'a>,
+    default: Rc<Texture<'a>>,
+    up: Rc<Texture<'a>>,
+    right: Rc<Texture<'a>>,
+    down: Rc<Texture<'a>>,
+    left: Rc<Texture<'a>>,
 }
 
-fn load_all_media<'a>(
-    texture_creator: &'a TextureCreator<WindowContext>,
-) -> Result<Textures<'a>, Box<dyn Error>> {
-    let default = load_media("assets/04_key_presses/press.bmp", texture_creator)?;
-    let up = load_media("assets/04_key_presses/up.bmp", texture_creator)?;
-    let right = load_media("assets/04_key_presses/right.bmp", texture_creator)?;
-    let down = load_media("assets/04_key_presses/down.bmp", texture_creator)?;
-    let left = load_media("assets/04_key_presses/left.bmp", texture_creator)?;
+fn load_all_media<'a>(assets: &Assets<'a>) -> Result<Textures<'a>, Box<dyn Error>> {
+    let default = assets.texture("04_key_presses/press.bmp")?;
+    let up = assets.texture("04_key_presses/up.bmp")?;
+    let right = assets.texture("04_key_presses/right.bmp")?;
+    let down = assets.texture("04_key_presses/down.bmp")?;
+    let left = assets.texture("04_key_presses/left.bmp")?;
     Ok(Textures {
         default,
         up,
@@ -30,54 +29,48 @@ fn load_all_media<'a>(
     })
 }
 
-fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
-    let texture_creator = canvas.texture_creator();
-    let textures = load_all_media(&texture_creator).expect("FATAL: failed to load images.");
-
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
-    let mut img = &textures.default;
+struct KeyPresses<'a> {
+    textures: Textures<'a>,
+    pressed: Option<Keycode>,
+}
 
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Up),
-                    ..
-                } => img = &textures.up,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Right),
-                    ..
-                } => img = &textures.right,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Down),
-                    ..
-                } => img = &textures.down,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Left),
-                    ..
-                } => img = &textures.left,
-                Event::KeyDown { .. } => img = &textures.default,
-                _ => {}
-            }
-        }
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
+impl<'a> Game for KeyPresses<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        if let Event::KeyDown { keycode, .. } = event {
+            self.pressed = *keycode;
         }
+    }
 
-        canvas.clear();
-        canvas
-            .copy(&img, None, None)
-            .expect("FATAL: failed to draw to canvas.");
-        canvas.present();
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let img = match self.pressed {
+            Some(Keycode::Up) => &self.textures.up,
+            Some(Keycode::Right) => &self.textures.right,
+            Some(Keycode::Down) => &self.textures.down,
+            Some(Keycode::Left) => &self.textures.left,
+            _ => &self.textures.default,
+        };
+        canvas.copy(img, None, None)?;
+        Ok(())
     }
 }
+
+fn main() {
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let texture_creator = canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    let textures = load_all_media(&assets).expect("FATAL: failed to load images.");
+
+    let mut key_presses = KeyPresses {
+        textures,
+        pressed: None,
+    };
+    GameLoop::new()
+        .with_env()
+        .expect("FATAL: invalid game loop config")
+        .run(&sdl_context, &mut canvas, &mut key_presses)
+        .expect("FATAL: something failed in the game loop");
+}
@@ -1,17 +1,19 @@
 use std::error::Error;
 
-use sdl2::event::Event;
 use sdl2::pixels::PixelFormat;
 use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
 use sdl2::surface::Surface;
+use sdl2::video::WindowContext;
 
-use lazy_foo::{init_renderer, HEIGHT, WIDTH};
+use lazy_foo::{asset_path, init_renderer, Game, GameLoop, Renderer, HEIGHT, WIDTH};
 use std::convert::TryFrom;
+use std::path::Path;
 
-pub fn load_surface<'a>(
-    path: &'a str,
+pub fn load_surface(
+    path: &Path,
     window_pixel_format: PixelFormat,
-) -> Result<Surface<'a>, Box<dyn Error>> {
+) -> Result<Surface<'static>, Box<dyn Error>> {
     let loaded_surface = Surface::load_bmp(path)?;
 
     // Convert surface to screen format
@@ -22,14 +24,22 @@ pub fn load_surface<'a>(
     Ok(optimized_surface)
 }
 
-fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+struct Stretched<'a> {
+    texture: Texture<'a>,
+}
 
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
+impl<'a> Game for Stretched<'a> {
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        canvas.copy(&self.texture, None, None)?;
+        Ok(())
+    }
+}
 
+// Loads the image and stretches it onto a surface the size of the window
+fn stretched_texture<'a>(
+    canvas: &WindowCanvas,
+    texture_creator: &'a TextureCreator<WindowContext>,
+) -> Result<Texture<'a>, Box<dyn Error>> {
     // Getting window pixel format from its surface did not work here and failed with:
     // "No hardware accelerated renderers available"
     // let window_surface = canvas.window().surface(&event_pump).expect("..");
@@ -40,42 +50,41 @@ fn main() {
     let window_pixel_format_enum = canvas.window().window_pixel_format();
     // In my environment I could see in the debugger that window BitsPerPixel is indeed 32
     // while the surface loaded via `load_bmp` above had 24 BitsPerPixel
-    let window_pixel_format = PixelFormat::try_from(window_pixel_format_enum)
-        .expect("FATAL: unable to get window pixel format");
+    let window_pixel_format = PixelFormat::try_from(window_pixel_format_enum)?;
 
     let optimized_surface = load_surface(
-        "assets/05_optimized_loading_soft_stretching/stretch.bmp",
+        &asset_path("05_optimized_loading_soft_stretching/stretch.bmp"),
         window_pixel_format,
-    )
-    .expect("FATAL: failed to load image surface.");
+    )?;
 
     // Create a stretched version of the surface
     // (NOTE: here we are stretching to half the window width to verify that
     // it actually is stretching, as by default the image already fills the window)
     let stretch_rect = Rect::new(0, 0, WIDTH / 2, HEIGHT);
-    let mut stretched_surface = Surface::new(WIDTH, HEIGHT, window_pixel_format_enum)
-        .expect("FATAL: failed to created stretch surface");
-    optimized_surface
-        .blit_scaled(None, &mut stretched_surface, Some(stretch_rect))
-        .expect("FATAL: failed to blit surface");
+    let mut stretched_surface = Surface::new(WIDTH, HEIGHT, window_pixel_format_enum)?;
+    optimized_surface.blit_scaled(None, &mut stretched_surface, Some(stretch_rect))?;
 
     // We could operate on the window surface instead via `WindowSurfaceRef::update_window`
     // which would be closer to `SDL_UpdateWindowSurface` used in the tutorial, however
     // we like to stick to `canvas` operations
-    let texture_creator = canvas.texture_creator();
-    let texture = texture_creator
-        .create_texture_from_surface(&stretched_surface)
-        .expect("FATAL: failed to create texture.");
+    let texture = texture_creator.create_texture_from_surface(&stretched_surface)?;
+    Ok(texture)
+}
 
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
+fn main() {
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
 
-        canvas.clear();
-        canvas
-            .copy(&texture, None, None)
-            .expect("FATAL: failed to draw to canvas.");
-        canvas.present();
-    }
+    let texture_creator = canvas.texture_creator();
+    let texture = stretched_texture(&canvas, &texture_creator)
+        .expect("FATAL: failed to create stretched texture.");
+
+    GameLoop::new()
+        .with_env()
+        .expect("FATAL: invalid game loop config")
+        .run(&sdl_context, &mut canvas, &mut Stretched { texture })
+        .expect("FATAL: something failed in the game loop");
 }
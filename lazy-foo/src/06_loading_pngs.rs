@@ -1,19 +1,19 @@
-use lazy_foo::init_renderer;
-use sdl2::event::Event;
+use lazy_foo::{asset_path, init_renderer, Game, GameLoop, Renderer};
 use sdl2::image;
 use sdl2::image::{LoadSurface, LoadTexture};
 use sdl2::pixels::PixelFormat;
-use sdl2::render::{Texture, TextureCreator};
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
 use sdl2::surface::Surface;
 use sdl2::video::WindowContext;
 use std::convert::TryFrom;
 use std::error::Error;
+use std::path::Path;
 
 // The way we've been loading images and converting to textures all along.
 // A bit more verbose than the `direct_texture_load` way, but allows converting
 // surface to PixelFormat
 fn load_image<'a>(
-    path: &str,
+    path: &Path,
     format: PixelFormat,
     texture_creator: &'a TextureCreator<WindowContext>,
 ) -> Result<Texture<'a>, Box<dyn Error>> {
@@ -28,44 +28,54 @@ fn load_image<'a>(
 // Does not allow setting the PixelFormat.
 #[allow(dead_code)]
 fn direct_texture_load<'a>(
-    path: &str,
+    path: &Path,
     texture_creator: &'a TextureCreator<WindowContext>,
 ) -> Result<Texture<'a>, String> {
     texture_creator.load_texture(path)
 }
 
+struct LoadedPng<'a> {
+    texture: Texture<'a>,
+}
+
+impl<'a> Game for LoadedPng<'a> {
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        canvas.copy(&self.texture, None, None)?;
+        Ok(())
+    }
+}
+
+// Loads the PNG converted to the pixel format of the window
+fn load_png<'a>(
+    canvas: &WindowCanvas,
+    texture_creator: &'a TextureCreator<WindowContext>,
+) -> Result<Texture<'a>, Box<dyn Error>> {
+    // Alternative to the below lines is to use the shortcut to load a texture:
+    // let texture = direct_texture_load(&asset_path("06_loading_pngs/loaded.png"), &texture_creator)
+    let window_pixel_format = PixelFormat::try_from(canvas.window().window_pixel_format())?;
+    load_image(
+        &asset_path("06_loading_pngs/fixed.png"),
+        window_pixel_format,
+        texture_creator,
+    )
+}
+
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
 
     let init_flags = image::InitFlag::PNG;
     let _image_ctx = image::init(init_flags).expect("FATAL: failed to initialize sdl2:image");
 
     let texture_creator = canvas.texture_creator();
+    let texture = load_png(&canvas, &texture_creator).expect("FATAL: failed to load png texture");
 
-    // Alternative to the below 7 lines is to use the shortcut to load a texture:
-    // let texture = direct_texture_load("assets/06_loading_pngs/loaded.png", &texture_creator)
-    let window_pixel_format = PixelFormat::try_from(canvas.window().window_pixel_format())
-        .expect("FATAL: failed to obtain window pixel format");
-    let texture = load_image(
-        "assets/06_loading_pngs/fixed.png",
-        window_pixel_format,
-        &texture_creator,
-    )
-    .expect("FATAL: failed to load png texture");
-
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
-
-        canvas.clear();
-        canvas
-            .copy(&texture, None, None)
-            .expect("FATAL: failed to draw to canvas.");
-        canvas.present();
-    }
+    GameLoop::new()
+        .with_env()
+        .expect("FATAL: invalid game loop config")
+        .run(&sdl_context, &mut canvas, &mut LoadedPng { texture })
+        .expect("FATAL: something failed in the game loop");
 }
@@ -1,27 +1,36 @@
-use lazy_foo::{init_renderer, load_media};
-use sdl2::event::Event;
+use lazy_foo::{init_renderer, Game, GameLoop, Renderer};
+use sdl2::render::{Texture, WindowCanvas};
+use std::error::Error;
+use std::rc::Rc;
+
+struct Textures<'a> {
+    img_x: Rc<Texture<'a>>,
+}
+
+impl<'a> Game for Textures<'a> {
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        canvas.copy(&self.img_x, None, None)?;
+        Ok(())
+    }
+}
 
 // As indicated in the Readme, not much new here, basically the same for us as tutorial
-// 03_event_driven.rs
+// 03_event_driven.rs, except that the game loop is driven by the engine's GameLoop
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     let texture_creator = canvas.texture_creator();
-    let img_x = load_media("assets/x.bmp", &texture_creator).expect("FATAL: failed to load image.");
-
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
+    let assets = lazy_foo::assets(&texture_creator);
+    let img_x = assets
+        .texture("x.bmp")
+        .expect("FATAL: failed to load image.");
 
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
-
-        canvas.clear();
-        canvas
-            .copy(&img_x, None, None)
-            .expect("FATAL: failed to draw to canvas.");
-        canvas.present();
-    }
+    GameLoop::new()
+        .with_env()
+        .expect("FATAL: invalid game loop config")
+        .run(&sdl_context, &mut canvas, &mut Textures { img_x })
+        .expect("FATAL: something failed in the game loop");
 }
@@ -1,24 +1,14 @@
-use lazy_foo::{init_renderer, HEIGHT, WIDTH};
-use sdl2::event::Event;
+use lazy_foo::{init_renderer, Game, GameLoop, Renderer, HEIGHT, WIDTH};
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
 use sdl2::render::WindowCanvas;
 use sdl2::Sdl;
 use std::error::Error;
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
-
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
+struct Geometry;
 
+impl Game for Geometry {
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
         // Render red filled quad
         let rect: Rect = Rect::new(
             (WIDTH / 4) as i32,
@@ -50,13 +40,22 @@ fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error
         for y in (0..HEIGHT).step_by(4) {
             canvas.draw_point(Point::new((WIDTH / 2) as i32, y as i32))?;
         }
-        canvas.present();
+        Ok(())
     }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut Geometry)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
@@ -1,48 +1,61 @@
-use lazy_foo::{init_renderer, HEIGHT, WIDTH};
-use sdl2::event::Event;
-use sdl2::image::LoadTexture;
-use sdl2::pixels::Color;
+use lazy_foo::{init_renderer, Assets, Game, GameLoop, Renderer, HEIGHT, WIDTH};
 use sdl2::rect::Rect;
-use sdl2::render::WindowCanvas;
+use sdl2::render::{Texture, WindowCanvas};
 use sdl2::Sdl;
 use std::error::Error;
+use std::rc::Rc;
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
-    let texture_creator = canvas.texture_creator();
-    let texture = texture_creator.load_texture("assets/09_viewport/viewport.png")?;
+struct Viewports<'a> {
+    texture: Rc<Texture<'a>>,
+    top_left: Rect,
+    top_right: Rect,
+    bottom: Rect,
+}
 
-    let top_left = Rect::new(0, 0, WIDTH / 2, HEIGHT / 2);
-    let top_right = Rect::new((WIDTH / 2) as i32, 0, WIDTH / 2, HEIGHT / 2);
-    let bottom = Rect::new(0, (HEIGHT / 2) as i32, WIDTH, HEIGHT / 2);
+impl<'a> Viewports<'a> {
+    fn load(assets: &Assets<'a>) -> Result<Self, Box<dyn Error>> {
+        Ok(Viewports {
+            texture: assets.texture("09_viewport/viewport.png")?,
+            top_left: Rect::new(0, 0, WIDTH / 2, HEIGHT / 2),
+            top_right: Rect::new((WIDTH / 2) as i32, 0, WIDTH / 2, HEIGHT / 2),
+            bottom: Rect::new(0, (HEIGHT / 2) as i32, WIDTH, HEIGHT / 2),
+        })
+    }
+}
 
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
+impl<'a> Game for Viewports<'a> {
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        canvas.set_viewport(self.top_left);
+        canvas.copy(&self.texture, None, None)?;
 
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
+        canvas.set_viewport(self.top_right);
+        canvas.copy(&self.texture, None, None)?;
 
-        canvas.set_viewport(top_left);
-        canvas.copy(&texture, None, None)?;
+        canvas.set_viewport(self.bottom);
+        canvas.copy(&self.texture, None, None)?;
 
-        canvas.set_viewport(top_right);
-        canvas.copy(&texture, None, None)?;
+        // Reset so the game loop clears the entire window
+        canvas.set_viewport(None);
+        Ok(())
+    }
+}
 
-        canvas.set_viewport(bottom);
-        canvas.copy(&texture, None, None)?;
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    let mut viewports = Viewports::load(&assets)?;
 
-        canvas.present();
-    }
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut viewports)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
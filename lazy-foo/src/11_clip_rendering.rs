@@ -1,99 +1,71 @@
 use std::error::Error;
-use std::path::Path;
+use std::rc::Rc;
 
-use sdl2::event::Event;
-use sdl2::image::LoadSurface;
-use sdl2::pixels::Color;
-use sdl2::render::{Texture, TextureCreator, WindowCanvas};
-use sdl2::surface::Surface;
-use sdl2::video::WindowContext;
+use sdl2::render::WindowCanvas;
 use sdl2::Sdl;
 
-use lazy_foo::{init_renderer, HEIGHT, WIDTH};
-use sdl2::rect::Rect;
+use lazy_foo::{init_renderer, Assets, Game, GameLoop, Renderer, Sprite, HEIGHT, WIDTH};
 
-struct Sprite<'a> {
-    texture: Texture<'a>,
+struct ClipRendering<'a> {
+    sprite_sheet: Rc<Sprite<'a>>,
 }
 
-impl<'a> Sprite<'a> {
-    pub fn new(
-        image_path: &Path,
-        texture_creator: &'a TextureCreator<WindowContext>,
-    ) -> Result<Self, Box<dyn Error>> {
-        let mut surface = Surface::from_file(image_path).expect(&format!(
-            "FATAL: unable to load surface from file {:?}",
-            image_path
-        ));
-
-        surface.set_color_key(true, Color::RGB(0, 0xff, 0xff))?;
-
-        let texture = texture_creator.create_texture_from_surface(surface)?;
-
-        Ok(Sprite { texture })
-    }
-
-    pub fn render(
-        &self,
-        canvas: &mut WindowCanvas,
-        x: i32,
-        y: i32,
-        clip: &Rect,
-    ) -> Result<(), String> {
-        let rect = Rect::new(x, y, clip.width(), clip.height());
-        canvas.copy(&self.texture, *clip, rect)
+impl<'a> ClipRendering<'a> {
+    fn load(assets: &Assets<'a>) -> Result<Self, Box<dyn Error>> {
+        let sprite_sheet = assets.sprite("11_clip_rendering/dots.png")?;
+        Ok(ClipRendering { sprite_sheet })
     }
 }
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
-    let texture_creator = canvas.texture_creator();
-    let sprite_sheet = Sprite::new(
-        "assets/11_clip_rendering/dots.png".as_ref(),
-        &texture_creator,
-    )?;
-    let clips = (
-        Rect::new(0, 0, 100, 100),
-        Rect::new(100, 0, 100, 100),
-        Rect::new(0, 100, 100, 100),
-        Rect::new(100, 100, 100, 100),
-    );
-
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
-
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
+impl<'a> Game for ClipRendering<'a> {
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        // The clip rects are defined in ./assets/11_clip_rendering/dots.toml
+        let sheet = &self.sprite_sheet;
 
         // Render top left sprite
-        sprite_sheet.render(canvas, 0, 0, &clips.0)?;
+        sheet.render(canvas, 0, 0, "top-left")?;
 
         // Render top right sprite
-        sprite_sheet.render(canvas, (WIDTH - clips.1.width()) as i32, 0, &clips.1)?;
+        let top_right = sheet.frame("top-right")?;
+        sheet.render(canvas, (WIDTH - top_right.width()) as i32, 0, "top-right")?;
 
         // Render bottom left sprite
-        sprite_sheet.render(canvas, 0, (HEIGHT - clips.2.height()) as i32, &clips.2)?;
+        let bottom_left = sheet.frame("bottom-left")?;
+        sheet.render(
+            canvas,
+            0,
+            (HEIGHT - bottom_left.height()) as i32,
+            "bottom-left",
+        )?;
 
         // Render bottom right sprite
-        sprite_sheet.render(
+        let bottom_right = sheet.frame("bottom-right")?;
+        sheet.render(
             canvas,
-            (WIDTH - clips.3.width()) as i32,
-            (HEIGHT - clips.3.height()) as i32,
-            &clips.3,
+            (WIDTH - bottom_right.width()) as i32,
+            (HEIGHT - bottom_right.height()) as i32,
+            "bottom-right",
         )?;
-
-        canvas.present();
+        Ok(())
     }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    let mut clip_rendering = ClipRendering::load(&assets)?;
+
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut clip_rendering)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
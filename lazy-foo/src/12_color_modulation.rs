@@ -1,124 +1,81 @@
 use std::error::Error;
-use std::path::Path;
 
 use sdl2::event::Event;
-use sdl2::image::LoadSurface;
 use sdl2::pixels::Color;
-use sdl2::render::{Texture, TextureCreator, WindowCanvas};
-use sdl2::surface::Surface;
-use sdl2::video::WindowContext;
-use sdl2::Sdl;
+use sdl2::render::WindowCanvas;
+use sdl2::{EventPump, Sdl};
 
-use lazy_foo::init_renderer;
-use sdl2::keyboard::Keycode;
-use sdl2::rect::Rect;
+use lazy_foo::{init_renderer, Assets, DrawParams, Game, GameLoop, InputMap, Renderer, Sprite};
 use std::cmp::{max, min};
+use std::rc::Rc;
 
-struct Sprite<'a> {
-    texture: Texture<'a>,
-    width: u32,
-    height: u32,
+struct ColorModulation<'a> {
+    sprite: Rc<Sprite<'a>>,
+    input_map: InputMap,
+    // Making them i32 so we don't panic when we over/under flow during
+    // an operation before max/min applies.
+    r: i32,
+    g: i32,
+    b: i32,
 }
 
-impl<'a> Sprite<'a> {
-    pub fn new(
-        image_path: &Path,
-        texture_creator: &'a TextureCreator<WindowContext>,
-    ) -> Result<Self, Box<dyn Error>> {
-        let mut surface = Surface::from_file(image_path).expect(&format!(
-            "FATAL: unable to load surface from file {:?}",
-            image_path
-        ));
-
-        surface.set_color_key(true, Color::RGB(0, 0xff, 0xff))?;
-
-        let width = surface.width();
-        let height = surface.height();
-        let texture = texture_creator.create_texture_from_surface(surface)?;
-
-        Ok(Sprite {
-            texture,
-            width,
-            height,
+impl<'a> ColorModulation<'a> {
+    fn load(assets: &Assets<'a>) -> Result<Self, Box<dyn Error>> {
+        let sprite = assets.sprite("12_color_modulation/colors.png")?;
+        // Q/W/E raise and A/S/D lower red, green and blue unless rebound in the file
+        let input_map = InputMap::load(&assets.path("12_color_modulation/bindings.toml"))?;
+        Ok(ColorModulation {
+            sprite,
+            input_map,
+            r: 255,
+            g: 255,
+            b: 255,
         })
     }
+}
 
-    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
-        self.texture.set_color_mod(r, g, b);
+impl<'a> Game for ColorModulation<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        for action in self.input_map.action_events(event) {
+            if !action.pressed {
+                continue;
+            }
+            match action.action {
+                "RedUp" => self.r = min(self.r + 32, 255),
+                "GreenUp" => self.g = min(self.g + 32, 255),
+                "BlueUp" => self.b = min(self.b + 32, 255),
+                "RedDown" => self.r = max(self.r - 32, 0),
+                "GreenDown" => self.g = max(self.g - 32, 0),
+                "BlueDown" => self.b = max(self.b - 32, 0),
+                _ => {}
+            }
+        }
     }
 
-    pub fn render(&self, canvas: &mut WindowCanvas, x: i32, y: i32) -> Result<(), String> {
-        let rect = Rect::new(x, y, self.width, self.height);
-        canvas.copy(&self.texture, None, rect)
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let tint = Color::RGB(self.r as u8, self.g as u8, self.b as u8);
+        self.sprite
+            .render_ex(canvas, &DrawParams::at(0.0, 0.0).tint(tint))?;
+        Ok(())
     }
 }
 
 fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
     let texture_creator = canvas.texture_creator();
-    let mut sprite = Sprite::new(
-        "assets/12_color_modulation/colors.png".as_ref(),
-        &texture_creator,
-    )?;
+    let assets = lazy_foo::assets(&texture_creator);
+    let mut color_modulation = ColorModulation::load(&assets)?;
 
-    // Making them i32 so we don't panic when we over/under flow during
-    // an operation before max/min applies.
-    let mut r: i32 = 255;
-    let mut g: i32 = 255;
-    let mut b: i32 = 255;
-
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                Event::KeyDown {
-                    keycode: Some(Keycode::Q),
-                    ..
-                } => r = min(r + 32, 255),
-                Event::KeyDown {
-                    keycode: Some(Keycode::W),
-                    ..
-                } => g = min(g + 32, 255),
-                Event::KeyDown {
-                    keycode: Some(Keycode::E),
-                    ..
-                } => b = min(b + 32, 255),
-                Event::KeyDown {
-                    keycode: Some(Keycode::A),
-                    ..
-                } => r = max(r - 32, 0),
-                Event::KeyDown {
-                    keycode: Some(Keycode::S),
-                    ..
-                } => g = max(g - 32, 0),
-                Event::KeyDown {
-                    keycode: Some(Keycode::D),
-                    ..
-                } => b = max(b - 32, 0),
-                _ => {}
-            }
-        }
-
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
-
-        sprite.set_color(r as u8, g as u8, b as u8);
-        sprite.render(canvas, 0, 0)?;
-
-        canvas.present();
-    }
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut color_modulation)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
@@ -1,101 +1,142 @@
 use std::error::Error;
-use std::path::Path;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 use sdl2::event::Event;
-use sdl2::image::LoadSurface;
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
-use sdl2::render::{Texture, TextureCreator, WindowCanvas};
-use sdl2::surface::Surface;
+use sdl2::render::{TextureCreator, WindowCanvas};
 use sdl2::video::WindowContext;
-use sdl2::Sdl;
-
-use lazy_foo::{init_renderer, HEIGHT, WIDTH};
-use sdl2::keyboard::Keycode;
-use sdl2::rect::Rect;
-
-struct SpriteSheet<'a> {
-    texture: Texture<'a>,
+use sdl2::{EventPump, Sdl};
+
+use lazy_foo::{
+    init_renderer, Animation, AnimationEvent, Assets, Game, GameLoop, GlyphCache, Playback,
+    Renderer, Sprite, TextRenderer, HEIGHT, WIDTH,
+};
+
+// The tutorial advanced one frame every 4 rendered frames, which at 60Hz is 15 frames a second.
+// Driving the animation by elapsed time keeps that pace no matter the refresh rate.
+const FRAME_DURATION: Duration = Duration::from_millis(1000 / 15);
+
+struct AnimatedSprites<'a> {
+    sprite_sheet: Rc<Sprite<'a>>,
+    animation: Animation,
+    // Draws the status line, which changes too often to render it via `load_from_rendered_text`
+    glyphs: GlyphCache<'a>,
+    fps: u32,
+    frames: u32,
+    fps_since: Instant,
 }
 
-impl<'a> SpriteSheet<'a> {
-    pub fn new(
-        image_path: &Path,
+impl<'a> AnimatedSprites<'a> {
+    // Expects assets with TTF support for the status line
+    fn load(
+        assets: &Assets<'a>,
         texture_creator: &'a TextureCreator<WindowContext>,
     ) -> Result<Self, Box<dyn Error>> {
-        let mut surface = Surface::from_file(image_path).expect(&format!(
-            "FATAL: unable to load surface from file {:?}",
-            image_path
-        ));
-
-        surface.set_color_key(true, Color::RGB(0, 0xff, 0xff))?;
-
-        let texture = texture_creator.create_texture_from_surface(surface)?;
-
-        Ok(SpriteSheet { texture })
-    }
-
-    pub fn render(
-        &self,
-        canvas: &mut WindowCanvas,
-        x: i32,
-        y: i32,
-        clip: &Rect,
-    ) -> Result<(), String> {
-        let rect = Rect::new(x, y, clip.width(), clip.height());
-        canvas.copy(&self.texture, *clip, rect)
+        let sprite_sheet = assets.sprite("14_animated_sprites/foo.png")?;
+
+        // Walk cycle frames in the order TexturePacker exported them to
+        // ./assets/14_animated_sprites/foo.json
+        let atlas = sprite_sheet
+            .atlas()
+            .ok_or("missing sprite sheet descriptor")?;
+        let animation = Animation::from_atlas(atlas, FRAME_DURATION)?;
+
+        let font = assets.font("16_true_type_fonts/lazy.ttf", 20)?;
+        let glyphs = GlyphCache::new(font, texture_creator);
+
+        Ok(AnimatedSprites {
+            sprite_sheet,
+            animation,
+            glyphs,
+            fps: 0,
+            frames: 0,
+            fps_since: Instant::now(),
+        })
     }
 }
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
-    let texture_creator = canvas.texture_creator();
-    let sprite_sheet = SpriteSheet::new(
-        "assets/14_animated_sprites/foo.png".as_ref(),
-        &texture_creator,
-    )?;
-
-    let clips = [
-        Rect::new(0, 0, 64, 205),
-        Rect::new(64, 0, 64, 205),
-        Rect::new(128, 0, 64, 205),
-        Rect::new(196, 0, 64, 205),
-    ];
-
-    let mut frame = 0;
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
+impl<'a> Game for AnimatedSprites<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        if let Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } = event
+        {
+            let animation = &mut self.animation;
+            match keycode {
+                Keycode::Space => {
+                    let playback = match animation.current_playback() {
+                        Playback::Loop => Playback::PingPong,
+                        Playback::PingPong => Playback::Reverse,
+                        Playback::Reverse => Playback::Once,
+                        Playback::Once => Playback::Loop,
+                    };
+                    eprintln!("playback: {:?}", playback);
+                    animation.set_playback(playback);
+                }
+                Keycode::Up => animation.set_speed(animation.current_speed() * 2.0),
+                Keycode::Down => animation.set_speed(animation.current_speed() / 2.0),
                 _ => {}
             }
         }
+    }
 
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
+    fn update(&mut self, dt: Duration, _event_pump: &EventPump) -> Result<(), Box<dyn Error>> {
+        self.animation.update(dt);
+        for event in self.animation.drain_events() {
+            if event == AnimationEvent::Finished {
+                eprintln!("animation finished, press Space to switch playback");
+            }
+        }
+        Ok(())
+    }
 
-        let current_clip = clips[frame / 4];
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let current_clip = self.sprite_sheet.frame(self.animation.current_frame())?;
         let x = (WIDTH - current_clip.width()) / 2;
         let y = (HEIGHT - current_clip.height()) / 2;
-        sprite_sheet.render(canvas, x as i32, y as i32, &current_clip)?;
-        canvas.present();
-
-        frame += 1;
-        if frame / 4 >= clips.len() {
-            frame = 0
-        };
+        self.animation
+            .render(&self.sprite_sheet, canvas, x as i32, y as i32)?;
+
+        // With VSync on this matches the refresh rate of the monitor
+        self.frames += 1;
+        if self.fps_since.elapsed() >= Duration::from_secs(1) {
+            self.fps = self.frames;
+            self.frames = 0;
+            self.fps_since = Instant::now();
+        }
+        let status = format!(
+            "{:?} x{} - {} fps",
+            self.animation.current_playback(),
+            self.animation.current_speed(),
+            self.fps
+        );
+        self.glyphs.draw(canvas, &status, 10, 10, Color::BLACK)?;
+        Ok(())
     }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+    let mut animated_sprites = AnimatedSprites::load(&assets, &texture_creator)?;
+
+    eprintln!("Press Space to switch playback and Up/Down to change the speed.");
+
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut animated_sprites)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
@@ -1,62 +1,73 @@
 use std::error::Error;
+use std::rc::Rc;
 
 use sdl2::event::Event;
-use sdl2::pixels::Color;
 use sdl2::render::WindowCanvas;
-use sdl2::Sdl;
+use sdl2::{EventPump, Sdl};
 
-use lazy_foo::{init_renderer, Flip, Sprite, HEIGHT, WIDTH};
+use lazy_foo::{
+    init_renderer, Assets, DrawParams, Flip, Game, GameLoop, Renderer, Sprite, HEIGHT, WIDTH,
+};
 use sdl2::keyboard::Keycode;
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
+struct RotationFlipping<'a> {
+    sprite: Rc<Sprite<'a>>,
+    degrees: f64,
+    flip: Flip,
+}
 
-    let texture_creator = canvas.texture_creator();
-    let sprite = Sprite::load_from_file(
-        "assets/15_rotation_flipping/arrow.png".as_ref(),
-        &texture_creator,
-    )?;
-
-    let mut degrees: f64 = 0.0;
-    let mut flip = Flip::None;
-    let center_x = ((WIDTH - sprite.width()) / 2) as i32;
-    let center_y = ((HEIGHT - sprite.height()) / 2) as i32;
-
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                Event::KeyDown { keycode: k, .. } => match k {
-                    Some(Keycode::A) => degrees -= 30.0,
-                    Some(Keycode::D) => degrees += 30.0,
-                    Some(Keycode::Q) => flip = Flip::Horizontal,
-                    Some(Keycode::W) => flip = Flip::None,
-                    Some(Keycode::E) => flip = Flip::Vertical,
-                    _ => {}
-                },
+impl<'a> RotationFlipping<'a> {
+    fn load(assets: &Assets<'a>) -> Result<Self, Box<dyn Error>> {
+        Ok(RotationFlipping {
+            sprite: assets.sprite("15_rotation_flipping/arrow.png")?,
+            degrees: 0.0,
+            flip: Flip::None,
+        })
+    }
+}
+
+impl<'a> Game for RotationFlipping<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        if let Event::KeyDown { keycode: k, .. } = event {
+            match k {
+                Some(Keycode::A) => self.degrees -= 30.0,
+                Some(Keycode::D) => self.degrees += 30.0,
+                Some(Keycode::Q) => self.flip = Flip::Horizontal,
+                Some(Keycode::W) => self.flip = Flip::None,
+                Some(Keycode::E) => self.flip = Flip::Vertical,
+                Some(Keycode::R) => self.flip = Flip::Both,
                 _ => {}
             }
         }
+    }
 
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        // Render arrow centered on screen, possibly rotated around its center and/or flipped
+        let params = DrawParams::at(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0)
+            .origin(0.5, 0.5)
+            .rotation_degrees(self.degrees)
+            .flip(self.flip);
+        self.sprite.render_ex(canvas, &params)?;
+        Ok(())
+    }
+}
 
-        // Render arrow possibly rotated and/or flipped
-        sprite.render_ex(canvas, center_x, center_y, None, degrees, None, &flip)?;
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    let mut rotation_flipping = RotationFlipping::load(&assets)?;
 
-        canvas.present();
-    }
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut rotation_flipping)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
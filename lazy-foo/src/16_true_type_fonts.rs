@@ -1,43 +1,172 @@
-use lazy_foo::{init_renderer, Sprite, HEIGHT, WIDTH};
+use lazy_foo::{
+    init_renderer, Align, Assets, FontOptions, FontStack, Game, GameLoop, LayoutOptions, Renderer,
+    RichText, Sprite, TextMode, VerticalAlign, HEIGHT, WIDTH,
+};
 use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
-use sdl2::render::WindowCanvas;
-use sdl2::Sdl;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use sdl2::{EventPump, Sdl};
 use std::error::Error;
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
+const FONT: &str = "16_true_type_fonts/lazy.ttf";
+const FALLBACK_FONT: &str = "16_true_type_fonts/DejaVuSans-Symbols.ttf";
+// lazy.ttf has the accents but lacks the symbols, which come from the fallback font
+const SYMBOLS: &str = "Crème brûlée ★ → ♥";
+const TEXT: &str = "The quick brown fox jumps over the lazy dog";
+const LEGEND: &str = "[color=#c00000][b]Space[/b][/color] mode   \
+    [color=#c00000][b]B[/b][/color] [b]bold[/b]   \
+    [color=#c00000][b]I[/b][/color] [i]italic[/i]   \
+    [color=#c00000][b]U[/b][/color] [u]underline[/u]   \
+    [color=#c00000][b]O[/b][/color] outline";
 
-    let texture_creator = canvas.texture_creator();
-    let ttf = sdl2::ttf::init()?;
-    let font = ttf.load_font("assets/16_true_type_fonts/lazy.ttf", 24)?;
-    let text = Sprite::load_from_rendered_text(
-        "The quick brown fox jumps over the lazy dog",
-        Color::BLACK,
-        &font,
-        &texture_creator,
-    )?;
-
-    let center_x = ((WIDTH - text.width()) / 2) as i32;
-    let center_y = ((HEIGHT - text.height()) / 2) as i32;
-
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
+const MODES: [TextMode; 4] = [
+    TextMode::Solid,
+    TextMode::Shaded(Color::RGB(0xff, 0xff, 0)),
+    TextMode::Blended,
+    TextMode::BlendedWrapped(WIDTH / 2),
+];
+
+struct TrueTypeFonts<'s, 'a> {
+    assets: &'s Assets<'a>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    mode: usize,
+    bold: bool,
+    italic: bool,
+    underline: bool,
+    outline: bool,
+    text: Sprite<'a>,
+    legend: Sprite<'a>,
+    symbols: Sprite<'a>,
+}
+
+impl<'s, 'a> TrueTypeFonts<'s, 'a> {
+    // Expects assets with TTF support
+    fn load(
+        assets: &'s Assets<'a>,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let font = assets.font(FONT, 24)?;
+        let text = Sprite::load_from_rendered_text(TEXT, Color::BLACK, &font, texture_creator)?;
+        // Styles and colors are mixed within the lines, which wrap to fit the window
+        let legend = RichText::parse(LEGEND)?
+            .color(Color::BLACK)
+            .layout(
+                assets,
+                FONT,
+                16,
+                &LayoutOptions::new()
+                    .align(Align::Center)
+                    .max_width(WIDTH - 20),
+            )?
+            .to_sprite(texture_creator)?;
+        let symbols = FontStack::new(assets.font(FONT, 24)?)
+            .fallback(assets.font(FALLBACK_FONT, 20)?)
+            .render(SYMBOLS, Color::BLACK, texture_creator)?;
+
+        Ok(TrueTypeFonts {
+            assets,
+            texture_creator,
+            mode: 0,
+            bold: false,
+            italic: false,
+            underline: false,
+            outline: false,
+            text,
+            legend,
+            symbols,
+        })
+    }
+
+    fn render_text(&mut self) -> Result<(), lazy_foo::Error> {
+        let options = FontOptions::new()
+            .bold(self.bold)
+            .italic(self.italic)
+            .underline(self.underline)
+            .outline(if self.outline { 1 } else { 0 });
+        let font = self.assets.font_with_options(FONT, 24, &options)?;
+        self.text = Sprite::load_from_rendered_text_with_mode(
+            TEXT,
+            Color::BLACK,
+            MODES[self.mode],
+            &font,
+            self.texture_creator,
+        )?;
+        Ok(())
+    }
+}
+
+impl<'s, 'a> Game for TrueTypeFonts<'s, 'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        if let Event::KeyDown {
+            keycode: Some(keycode),
+            ..
+        } = event
+        {
+            match keycode {
+                Keycode::Space => self.mode = (self.mode + 1) % MODES.len(),
+                Keycode::B => self.bold = !self.bold,
+                Keycode::I => self.italic = !self.italic,
+                Keycode::U => self.underline = !self.underline,
+                Keycode::O => self.outline = !self.outline,
+                _ => return,
+            }
+            if let Err(err) = self.render_text() {
+                eprintln!("WARN: failed to render text: {}", err);
+            }
         }
+    }
+
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let centered = LayoutOptions::new()
+            .align(Align::Center)
+            .vertical_align(VerticalAlign::Middle)
+            .place(
+                self.text.width(),
+                self.text.height(),
+                Rect::new(0, 0, WIDTH, HEIGHT),
+            );
+        self.text.render(canvas, centered.x(), centered.y(), None)?;
 
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
-        text.render(canvas, center_x, center_y, None)?;
-        canvas.present();
+        let bottom = LayoutOptions::new()
+            .align(Align::Center)
+            .vertical_align(VerticalAlign::Bottom)
+            .place(
+                self.legend.width(),
+                self.legend.height(),
+                Rect::new(0, 0, WIDTH, HEIGHT - 10),
+            );
+        self.legend.render(canvas, bottom.x(), bottom.y(), None)?;
+
+        let top = LayoutOptions::new().align(Align::Center).place(
+            self.symbols.width(),
+            self.symbols.height(),
+            Rect::new(0, 10, WIDTH, HEIGHT),
+        );
+        self.symbols.render(canvas, top.x(), top.y(), None)?;
+        Ok(())
     }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+    let mut true_type_fonts = TrueTypeFonts::load(&assets, &texture_creator)?;
+
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut true_type_fonts)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
@@ -1,161 +1,142 @@
-use lazy_foo::{init_renderer, Sprite, HEIGHT, WIDTH};
+use lazy_foo::{
+    init_renderer, Assets, Button, ButtonSkin, ButtonState, Cursors, Game, GameLoop, InputState,
+    Renderer, HEIGHT, WIDTH,
+};
 use sdl2::event::Event;
-use sdl2::mouse::{MouseButton, MouseState};
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::SystemCursor;
 use sdl2::pixels::Color;
-use sdl2::rect::{Point, Rect};
+use sdl2::rect::Rect;
 use sdl2::render::WindowCanvas;
-use sdl2::Sdl;
-use std::collections::HashSet;
+use sdl2::{EventPump, Sdl};
 use std::error::Error;
 
 const BUTTON_WIDTH: u32 = 300;
 const BUTTON_HEIGHT: u32 = 200;
-
-enum ButtonType {
-    MouseOut,
-    MouseOver,
-    MouseDown,
-    MouseUp,
+const BUTTON_SPRITE: &str = "17_mouse_events/button.png";
+
+// Frames of the button sprite, see ./assets/17_mouse_events/button.toml
+fn button_skin() -> ButtonSkin {
+    ButtonSkin::new("mouse-out")
+        .frame(ButtonState::Hovered, "mouse-over")
+        .frame(ButtonState::Pressed, "mouse-down")
+        .frame(ButtonState::Released, "mouse-up")
 }
 
-struct Button<'a> {
-    rect: Rect,
-    button_type: ButtonType,
-    sprite: &'a Sprite<'a>,
+struct MouseEvents<'a> {
+    buttons: [Button<'a>; 4],
+    input: InputState,
+    cursors: Cursors,
 }
 
-impl<'a> Button<'a> {
-    pub fn new(x: i32, y: i32, width: u32, height: u32, sprite: &'a Sprite) -> Button<'a> {
-        let rect = Rect::new(x, y, width, height);
-
-        Button {
-            rect,
-            button_type: ButtonType::MouseOut,
-            sprite,
-        }
+impl<'a> MouseEvents<'a> {
+    fn load(assets: &Assets<'a>, sdl_context: &Sdl) -> Result<Self, Box<dyn Error>> {
+        // All buttons share the same sprite which the assets registry loads only once
+        let sprite = assets.sprite(BUTTON_SPRITE)?;
+        let (right, bottom) = (
+            (WIDTH - BUTTON_WIDTH) as i32,
+            (HEIGHT - BUTTON_HEIGHT) as i32,
+        );
+        let button = |x, y| {
+            let rect = Rect::new(x, y, BUTTON_WIDTH, BUTTON_HEIGHT);
+            Button::new(sprite.clone(), button_skin(), rect)
+        };
+        Ok(MouseEvents {
+            buttons: [
+                button(0, 0),
+                button(right, 0),
+                button(0, bottom),
+                button(right, bottom),
+            ],
+            input: InputState::new(),
+            cursors: Cursors::new(sdl_context.mouse()),
+        })
     }
 
-    pub fn render(&self, canvas: &mut WindowCanvas) -> Result<(), String> {
-        let clip = match self.button_type {
-            ButtonType::MouseOut => Rect::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT),
-            ButtonType::MouseOver => {
-                Rect::new(0, BUTTON_HEIGHT as i32, BUTTON_WIDTH, BUTTON_HEIGHT)
-            }
-            ButtonType::MouseDown => {
-                Rect::new(0, BUTTON_HEIGHT as i32 * 2, BUTTON_WIDTH, BUTTON_HEIGHT)
+    // Moves keyboard focus to the next button, or the first if none has it
+    fn focus_next(&mut self) {
+        let next = self
+            .buttons
+            .iter()
+            .position(|button| button.is_focused())
+            .map_or(0, |idx| (idx + 1) % self.buttons.len());
+        for (idx, button) in self.buttons.iter_mut().enumerate() {
+            if idx == next {
+                button.focus();
+            } else {
+                button.blur();
             }
-            ButtonType::MouseUp => {
-                Rect::new(0, BUTTON_HEIGHT as i32 * 3, BUTTON_WIDTH, BUTTON_HEIGHT)
-            }
-        };
-        self.sprite.render(canvas, self.rect.x, self.rect.y, clip)
+        }
     }
+}
 
-    pub fn on_mouse_event(&mut self, event: &Event, mouse_state: &MouseState) {
-        if self.is_mouse_inside(mouse_state.x(), mouse_state.y()) {
-            match event {
-                Event::MouseMotion { .. } => self.button_type = ButtonType::MouseOver,
-                Event::MouseButtonDown { .. } => self.button_type = ButtonType::MouseDown,
-                Event::MouseButtonUp { .. } => self.button_type = ButtonType::MouseUp,
-                _ => {}
+impl<'a> Game for MouseEvents<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        if let Event::KeyDown {
+            keycode: Some(Keycode::Tab),
+            ..
+        } = event
+        {
+            self.focus_next();
+        }
+        for (idx, button) in self.buttons.iter_mut().enumerate() {
+            if button.handle_event(event) {
+                eprintln!("clicked button {}", idx);
             }
+        }
+
+        // A hand shows the mouse is over something to click
+        let cursor = if self.buttons.iter().any(Button::is_hovered) {
+            SystemCursor::Hand
         } else {
-            self.button_type = ButtonType::MouseOut;
+            SystemCursor::Arrow
+        };
+        if let Err(err) = self.cursors.set_system(cursor) {
+            eprintln!("warning: unable to change cursor: {}", err);
+        }
+
+        // Logging mouse state whenever button state changed, the input state already includes
+        // the event
+        if let Event::MouseButtonDown { .. } | Event::MouseButtonUp { .. } = event {
+            let (x, y) = self.input.mouse_position();
+            let held: Vec<_> = self.input.held_mouse_buttons().collect();
+            eprintln!("X = {:?}, Y = {:?} : {:?}", x, y, held);
         }
     }
 
-    fn is_mouse_inside(&self, x: i32, y: i32) -> bool {
-        self.rect.contains_point(Point::new(x, y))
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        for button in self.buttons.iter() {
+            button.render(canvas)?;
+            // The sprite has no frame for focus, so we outline the focused button instead
+            if button.is_focused() {
+                canvas.set_draw_color(Color::RGB(0x00, 0x80, 0xff));
+                canvas.draw_rect(button.rect())?;
+            }
+        }
+        Ok(())
     }
-}
 
-fn log_mouse_button_state(
-    mouse_state: &MouseState,
-    prev_buttons: HashSet<MouseButton>,
-) -> HashSet<MouseButton> {
-    let buttons = mouse_state.pressed_mouse_buttons().collect();
-    let new_buttons = &buttons - &prev_buttons;
-    let old_buttons = &prev_buttons - &buttons;
-    if !new_buttons.is_empty() || !old_buttons.is_empty() {
-        eprintln!(
-            "X = {:?}, Y = {:?} : {:?} -> {:?}",
-            mouse_state.x(),
-            mouse_state.y(),
-            new_buttons,
-            old_buttons
-        );
+    fn input_state(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.input)
     }
-    buttons
 }
 
 fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
     let texture_creator = canvas.texture_creator();
-    let sprite = Sprite::load_from_file(
-        "assets/17_mouse_events/button.png".as_ref(),
-        &texture_creator,
-    )?;
-    let mut buttons = [
-        Button::new(0, 0, BUTTON_WIDTH, BUTTON_HEIGHT, &sprite),
-        Button::new(
-            (WIDTH - BUTTON_WIDTH) as i32,
-            0,
-            BUTTON_WIDTH,
-            BUTTON_HEIGHT,
-            &sprite,
-        ),
-        Button::new(
-            0,
-            (HEIGHT - BUTTON_HEIGHT) as i32,
-            BUTTON_WIDTH,
-            BUTTON_HEIGHT,
-            &sprite,
-        ),
-        Button::new(
-            (WIDTH - BUTTON_WIDTH) as i32,
-            (HEIGHT - BUTTON_HEIGHT) as i32,
-            BUTTON_WIDTH,
-            BUTTON_HEIGHT,
-            &sprite,
-        ),
-    ];
+    let assets = lazy_foo::assets(&texture_creator);
+    let mut mouse_events = MouseEvents::load(&assets, sdl_context)?;
 
-    let mut prev_buttons = HashSet::new();
-
-    'running: loop {
-        let mouse_state: MouseState = event_pump.mouse_state();
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => break 'running,
-                event => {
-                    for button in buttons.iter_mut() {
-                        button.on_mouse_event(&event, &mouse_state);
-                    }
-                }
-            }
-        }
-
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
-        for button in buttons.iter() {
-            button.render(canvas)?;
-        }
-        canvas.present();
-
-        // Logging mouse state whenever button state changed
-        prev_buttons = log_mouse_button_state(&mouse_state, prev_buttons);
-    }
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut mouse_events)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
@@ -1,39 +1,27 @@
-use lazy_foo::{init_renderer, Sprite, HEIGHT, WIDTH};
-use sdl2::event::Event;
+use lazy_foo::{
+    init_renderer, Align, Game, GameLoop, GlyphCache, InputState, LayoutOptions, Renderer,
+    TextRenderer, VerticalAlign, HEIGHT, WIDTH,
+};
 use sdl2::keyboard::Scancode;
 use sdl2::pixels::Color;
+use sdl2::rect::Rect;
 use sdl2::render::WindowCanvas;
-use sdl2::Sdl;
-use std::collections::HashSet;
+use sdl2::{EventPump, Sdl};
 use std::error::Error;
+use std::time::Duration;
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
-    let texture_creator = canvas.texture_creator();
-
-    let ttf = sdl2::ttf::init()?;
-    let font = ttf.load_font("assets/16_true_type_fonts/lazy.ttf", 28)?;
-    let text = Sprite::load_from_rendered_text(
-        "Logging Key States in the Terminal",
-        Color::BLACK,
-        &font,
-        &texture_creator,
-    )?;
-
-    let center_x = ((WIDTH - text.width()) / 2) as i32;
-    let center_y = ((HEIGHT - text.height()) / 2) as i32;
+const TITLE: &str = "Logging Key States in the Terminal";
 
-    'running: loop {
-        if let Some(Event::Quit { .. }) = event_pump.poll_event() {
-            break 'running;
-        }
+struct KeyStates<'a> {
+    glyphs: GlyphCache<'a>,
+    input: InputState,
+    pressed: Vec<String>,
+}
 
-        let scan_code: HashSet<Scancode> =
-            event_pump.keyboard_state().pressed_scancodes().collect();
-        for key in scan_code {
+impl<'a> Game for KeyStates<'a> {
+    fn update(&mut self, _dt: Duration, _event_pump: &EventPump) -> Result<(), Box<dyn Error>> {
+        self.pressed.clear();
+        for key in self.input.held_scancodes() {
             match key {
                 Scancode::Right => eprintln!("Right"),
                 Scancode::Left => eprintln!("Left"),
@@ -41,19 +29,56 @@ fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error
                 Scancode::Up => eprintln!("Up"),
                 _ => eprintln!("Pressed unhandled {:?}", key),
             }
+            self.pressed.push(key.name().to_string());
         }
+        self.pressed.sort();
+        Ok(())
+    }
 
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
-        text.render(canvas, center_x, center_y, None)?;
-        canvas.present();
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        // Title and pressed keys centered on screen, wrapped when too many keys are held
+        let text = format!("{}\n{}", TITLE, self.pressed.join(" "));
+        let options = LayoutOptions::new()
+            .align(Align::Center)
+            .vertical_align(VerticalAlign::Middle)
+            .line_spacing(8);
+        let bounds = Rect::new(20, 0, WIDTH - 40, HEIGHT);
+        let layout = self.glyphs.layout(&text, bounds, &options)?;
+        self.glyphs.draw_layout(canvas, &layout, Color::BLACK)?;
+        Ok(())
     }
+
+    fn input_state(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.input)
+    }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+
+    let ttf = sdl2::ttf::init()?;
+    let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+    let font = assets.font("16_true_type_fonts/lazy.ttf", 28)?;
+    let glyphs = GlyphCache::new(font, &texture_creator);
+
+    GameLoop::new().with_env()?.run(
+        sdl_context,
+        canvas,
+        &mut KeyStates {
+            glyphs,
+            input: InputState::new(),
+            pressed: Vec::new(),
+        },
+    )?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
 
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
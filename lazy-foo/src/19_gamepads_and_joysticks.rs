@@ -0,0 +1,102 @@
+use std::error::Error;
+use std::rc::Rc;
+
+use sdl2::controller::Axis;
+use sdl2::event::Event;
+use sdl2::render::WindowCanvas;
+use sdl2::{EventPump, Sdl};
+
+use lazy_foo::{
+    init_renderer, DrawParams, Game, GameLoop, Gamepads, InputState, Renderer, Sprite, HEIGHT,
+    WIDTH,
+};
+
+const PLAYER: usize = 0;
+
+struct GamepadsAndJoysticks<'a> {
+    sprite: Rc<Sprite<'a>>,
+    gamepads: Gamepads,
+    input: InputState,
+}
+
+impl<'a> GamepadsAndJoysticks<'a> {
+    // Direction the first player's left stick points at, 0 when it rests inside the dead zone
+    fn degrees(&self) -> f64 {
+        let which = match self.gamepads.instance_id(PLAYER) {
+            Some(which) => which,
+            None => return 0.0,
+        };
+        let x = self.input.gamepad_axis(which, Axis::LeftX);
+        let y = self.input.gamepad_axis(which, Axis::LeftY);
+        if x == 0 && y == 0 {
+            0.0
+        } else {
+            f64::from(y).atan2(f64::from(x)).to_degrees()
+        }
+    }
+}
+
+impl<'a> Game for GamepadsAndJoysticks<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        // The gamepads already opened or closed the device
+        if let Event::ControllerDeviceAdded { .. } | Event::ControllerDeviceRemoved { .. } = event {
+            let players: Vec<_> = self.gamepads.players().collect();
+            eprintln!("gamepads: {:?}", players);
+        }
+    }
+
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let params = DrawParams::at(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0)
+            .origin(0.5, 0.5)
+            .rotation_degrees(self.degrees());
+        self.sprite.render_ex(canvas, &params)?;
+        Ok(())
+    }
+
+    fn input_state(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.input)
+    }
+
+    fn gamepads(&mut self) -> Option<&mut Gamepads> {
+        Some(&mut self.gamepads)
+    }
+
+    fn gamepad_error(&mut self, err: lazy_foo::Error) {
+        eprintln!("warning: unable to open gamepad: {}", err);
+    }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    // Same arrow as ./15_rotation_flipping.rs
+    let sprite = assets.sprite("15_rotation_flipping/arrow.png")?;
+
+    let controller_subsystem = sdl_context.game_controller().map_err(|err| {
+        lazy_foo::Error::sdl_init("SDL_InitSubSystem(SDL_INIT_GAMECONTROLLER)", err)
+    })?;
+    let mut gamepads = Gamepads::new(controller_subsystem);
+    gamepads.load_mappings(&lazy_foo::asset_path(
+        "19_gamepads_and_joysticks/gamecontrollerdb.txt",
+    ))?;
+
+    let mut gamepads_and_joysticks = GamepadsAndJoysticks {
+        sprite,
+        gamepads,
+        input: InputState::new(),
+    };
+
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut gamepads_and_joysticks)?;
+    Ok(())
+}
+
+fn main() {
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
+}
@@ -0,0 +1,141 @@
+use std::error::Error;
+use std::rc::Rc;
+use std::time::Duration;
+
+use sdl2::controller::{Axis, Button};
+use sdl2::event::Event;
+use sdl2::render::WindowCanvas;
+use sdl2::{EventPump, Sdl};
+
+use lazy_foo::{
+    init_renderer, DrawParams, Game, GameLoop, Gamepads, InputState, Renderer, Rumble,
+    RumbleEffect, Sprite, HEIGHT, WIDTH,
+};
+
+const PLAYER: usize = 0;
+// Pixels per second when the stick is pushed all the way
+const SPEED: f32 = 400.0;
+
+struct ForceFeedback<'a> {
+    sprite: Rc<Sprite<'a>>,
+    gamepads: Gamepads,
+    input: InputState,
+    rumble: Rumble,
+    x: f32,
+    y: f32,
+    touching_wall: bool,
+}
+
+impl<'a> ForceFeedback<'a> {
+    fn play(&mut self, which: u32, effect: RumbleEffect) {
+        // Not every gamepad can rumble, which is no reason to stop the game
+        if let Err(err) = self.rumble.play(which, effect) {
+            eprintln!("warning: unable to rumble: {}", err);
+        }
+    }
+}
+
+impl<'a> Game for ForceFeedback<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        self.rumble.handle_event(event);
+        if let Event::ControllerButtonDown { which, button, .. } = event {
+            let effect = match button {
+                Button::A => RumbleEffect::rumble(0.75, Duration::from_millis(500)),
+                Button::B => RumbleEffect::constant(0.5, Duration::from_millis(500)),
+                Button::X => RumbleEffect::sine(
+                    0.75,
+                    Duration::from_millis(100),
+                    Duration::from_millis(1000),
+                ),
+                Button::Y => RumbleEffect::ramp(0.0, 1.0, Duration::from_millis(1000)),
+                _ => return,
+            };
+            self.play(*which, effect);
+        }
+    }
+
+    // The arrow follows the left stick and bumps into the edges of the window
+    fn update(&mut self, dt: Duration, _event_pump: &EventPump) -> Result<(), Box<dyn Error>> {
+        let which = match self.gamepads.instance_id(PLAYER) {
+            Some(which) => which,
+            None => return Ok(()),
+        };
+        let speed = SPEED * dt.as_secs_f32() / f32::from(i16::MAX);
+        let x = self.x + f32::from(self.input.gamepad_axis(which, Axis::LeftX)) * speed;
+        let y = self.y + f32::from(self.input.gamepad_axis(which, Axis::LeftY)) * speed;
+        let (half_width, half_height) = (
+            self.sprite.width() as f32 / 2.0,
+            self.sprite.height() as f32 / 2.0,
+        );
+        self.x = x.clamp(half_width, WIDTH as f32 - half_width);
+        self.y = y.clamp(half_height, HEIGHT as f32 - half_height);
+
+        let touching_wall = self.x != x || self.y != y;
+        if touching_wall && !self.touching_wall {
+            self.play(
+                which,
+                RumbleEffect::constant(1.0, Duration::from_millis(150)),
+            );
+        }
+        self.touching_wall = touching_wall;
+        Ok(())
+    }
+
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let params = DrawParams::at(self.x, self.y).origin(0.5, 0.5);
+        self.sprite.render_ex(canvas, &params)?;
+        Ok(())
+    }
+
+    fn input_state(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.input)
+    }
+
+    fn gamepads(&mut self) -> Option<&mut Gamepads> {
+        Some(&mut self.gamepads)
+    }
+
+    fn gamepad_error(&mut self, err: lazy_foo::Error) {
+        eprintln!("warning: unable to open gamepad: {}", err);
+    }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    // Same arrow as ./15_rotation_flipping.rs
+    let sprite = assets.sprite("15_rotation_flipping/arrow.png")?;
+
+    let controller_subsystem = sdl_context.game_controller().map_err(|err| {
+        lazy_foo::Error::sdl_init("SDL_InitSubSystem(SDL_INIT_GAMECONTROLLER)", err)
+    })?;
+    // Gamepads without haptic support still rumble, so we go on without the haptic subsystem
+    let haptic_subsystem = sdl_context
+        .haptic()
+        .map_err(|err| eprintln!("warning: haptic effects unavailable: {}", err))
+        .ok();
+
+    let mut force_feedback = ForceFeedback {
+        sprite,
+        gamepads: Gamepads::new(controller_subsystem),
+        input: InputState::new(),
+        rumble: Rumble::new(haptic_subsystem),
+        x: WIDTH as f32 / 2.0,
+        y: HEIGHT as f32 / 2.0,
+        touching_wall: false,
+    };
+
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut force_feedback)?;
+    Ok(())
+}
+
+fn main() {
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
+}
@@ -1,11 +1,11 @@
-use lazy_foo::{init_renderer, Sprite};
-use sdl2::audio::{AudioCVT, AudioCallback, AudioDevice, AudioSpecDesired, AudioSpecWAV};
+use lazy_foo::{init_renderer, Assets, Game, GameLoop, Renderer, Sprite};
+use sdl2::audio::{AudioCVT, AudioCallback, AudioDevice, AudioSpecDesired};
 use sdl2::event::Event;
 use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
 use sdl2::render::WindowCanvas;
-use sdl2::Sdl;
+use sdl2::{EventPump, Sdl};
 use std::error::Error;
+use std::rc::Rc;
 
 // Mostly adapted from: https://github.com/Rust-SDL2/rust-sdl2/blob/master/examples/audio-wav.rs
 struct Sound {
@@ -30,43 +30,88 @@ impl AudioCallback for Sound {
 
 fn load_audio(
     wav_file: &str,
+    assets: &Assets,
     desired_spec: &AudioSpecDesired,
     sdl_context: &Sdl,
-) -> Result<AudioDevice<Sound>, String> {
-    let audio_subsystem = sdl_context.audio()?;
-    audio_subsystem.open_playback(None, desired_spec, |spec| {
-        let wav = AudioSpecWAV::load_wav(&wav_file).expect("FATAL: Could not load test WAV file");
-
-        let cvt = AudioCVT::new(
-            wav.format,
-            wav.channels,
-            wav.freq,
-            spec.format,
-            spec.channels,
-            spec.freq,
-        )
-        .expect("FATAL: Could not convert WAV file");
-
-        let data = cvt.convert(wav.buffer().to_vec());
-
-        Sound {
-            data,
-            volume: 0.5,
-            pos: 0,
+) -> Result<AudioDevice<Sound>, lazy_foo::Error> {
+    let wav = assets.sound(wav_file)?;
+
+    let audio_subsystem = sdl_context
+        .audio()
+        .map_err(|err| lazy_foo::Error::sdl_init("SDL_InitSubSystem(SDL_INIT_AUDIO)", err))?;
+
+    // The spec of the opened device is only known inside the callback, so we convert there and
+    // hand any failure back out
+    let mut conversion = Ok(());
+    let device = audio_subsystem
+        .open_playback(None, desired_spec, |spec| {
+            let data = AudioCVT::new(
+                wav.format,
+                wav.channels,
+                wav.freq,
+                spec.format,
+                spec.channels,
+                spec.freq,
+            )
+            .map(|cvt| cvt.convert(wav.buffer().to_vec()))
+            .unwrap_or_else(|err| {
+                conversion = Err(lazy_foo::Error::audio_conversion(
+                    Some(&assets.path(wav_file)),
+                    err,
+                ));
+                Vec::new()
+            });
+
+            Sound {
+                data,
+                volume: 0.5,
+                pos: 0,
+            }
+        })
+        .map_err(|err| lazy_foo::Error::audio_device("SDL_OpenAudioDevice", err))?;
+    conversion.map(|_| device)
+}
+
+const PROMPT: &str = "21_sound_effects_and_music/prompt.png";
+
+// Tells which keys play which sound
+fn render_prompt(prompt: &Sprite, canvas: &mut WindowCanvas) -> Result<(), lazy_foo::Error> {
+    prompt.render(canvas, 0, 0, None)
+}
+
+struct SoundEffects<'a> {
+    sprite: Rc<Sprite<'a>>,
+    beat: AudioDevice<Sound>,
+    high: AudioDevice<Sound>,
+    low: AudioDevice<Sound>,
+    medium: AudioDevice<Sound>,
+    scratch: AudioDevice<Sound>,
+}
+
+impl<'a> Game for SoundEffects<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        if let Event::KeyDown { keycode: k, .. } = event {
+            match k {
+                Some(Keycode::Num1) => self.beat.resume(),
+                Some(Keycode::Num2) => self.high.resume(),
+                Some(Keycode::Num3) => self.low.resume(),
+                Some(Keycode::Num4) => self.medium.resume(),
+                Some(Keycode::Num5) => self.scratch.resume(),
+                _ => {}
+            }
         }
-    })
+    }
+
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        render_prompt(&self.sprite, canvas)?;
+        Ok(())
+    }
 }
 
 fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
-
     let texture_creator = canvas.texture_creator();
-    let sprite = Sprite::load_from_file(
-        "assets/21_sound_effects_and_music/prompt.png".as_ref(),
-        &texture_creator,
-    )?;
+    let assets = lazy_foo::assets(&texture_creator);
+    let sprite = assets.sprite(PROMPT)?;
 
     let desired_spec = AudioSpecDesired {
         freq: Some(44_100),
@@ -74,57 +119,56 @@ fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error
         samples: None,     // default
     };
     let beat = load_audio(
-        "assets/21_sound_effects_and_music/beat.wav",
+        "21_sound_effects_and_music/beat.wav",
+        &assets,
         &desired_spec,
-        &sdl_context,
+        sdl_context,
     )?;
     let high = load_audio(
-        "assets/21_sound_effects_and_music/high.wav",
+        "21_sound_effects_and_music/high.wav",
+        &assets,
         &desired_spec,
-        &sdl_context,
+        sdl_context,
     )?;
     let low = load_audio(
-        "assets/21_sound_effects_and_music/low.wav",
+        "21_sound_effects_and_music/low.wav",
+        &assets,
         &desired_spec,
-        &sdl_context,
+        sdl_context,
     )?;
     let medium = load_audio(
-        "assets/21_sound_effects_and_music/medium.wav",
+        "21_sound_effects_and_music/medium.wav",
+        &assets,
         &desired_spec,
-        &sdl_context,
+        sdl_context,
     )?;
     let scratch = load_audio(
-        "assets/21_sound_effects_and_music/scratch.wav",
+        "21_sound_effects_and_music/scratch.wav",
+        &assets,
         &desired_spec,
-        &sdl_context,
+        sdl_context,
     )?;
 
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. } => break 'running,
-                Event::KeyDown { keycode: k, .. } => match k {
-                    Some(Keycode::Num1) => beat.resume(),
-                    Some(Keycode::Num2) => high.resume(),
-                    Some(Keycode::Num3) => low.resume(),
-                    Some(Keycode::Num4) => medium.resume(),
-                    Some(Keycode::Num5) => scratch.resume(),
-                    _ => {}
-                },
-                _ => {}
-            }
-        }
+    let mut sound_effects = SoundEffects {
+        sprite,
+        beat,
+        high,
+        low,
+        medium,
+        scratch,
+    };
 
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
-        sprite.render(canvas, 0, 0, None)?;
-        canvas.present();
-    }
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut sound_effects)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
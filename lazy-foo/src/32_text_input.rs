@@ -0,0 +1,103 @@
+use lazy_foo::{
+    init_renderer, Assets, Cursors, Game, GameLoop, GlyphCache, Renderer, TextInput, TextRenderer,
+    WIDTH,
+};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::mouse::SystemCursor;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use sdl2::{EventPump, Sdl};
+use std::error::Error;
+
+const FONT: &str = "16_true_type_fonts/lazy.ttf";
+
+struct TextInputs<'a> {
+    glyphs: GlyphCache<'a>,
+    input: TextInput<'a>,
+    cursors: Cursors,
+}
+
+impl<'a> TextInputs<'a> {
+    // Expects assets with TTF support
+    fn load(
+        assets: &Assets<'a>,
+        texture_creator: &'a TextureCreator<WindowContext>,
+        sdl_context: &Sdl,
+    ) -> Result<Self, Box<dyn Error>> {
+        let glyphs = GlyphCache::new(assets.font(FONT, 28)?, texture_creator);
+
+        let video = sdl_context.video()?;
+        let rect = Rect::new(40, 200, WIDTH - 80, 40);
+        let mut input = TextInput::new(assets.font(FONT, 24)?, texture_creator, &video, rect);
+        input.set_text("Some Text");
+        input.focus();
+
+        Ok(TextInputs {
+            glyphs,
+            input,
+            cursors: Cursors::new(sdl_context.mouse()),
+        })
+    }
+}
+
+impl<'a> Game for TextInputs<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        // An I-beam shows the text can be clicked into
+        if let Event::MouseMotion { x, y, .. } = event {
+            let cursor = if self.input.rect().contains_point((*x, *y)) {
+                SystemCursor::IBeam
+            } else {
+                SystemCursor::Arrow
+            };
+            if let Err(err) = self.cursors.set_system(cursor) {
+                eprintln!("WARN: failed to change cursor: {}", err);
+            }
+        }
+        match self.input.handle_event(event) {
+            Ok(true) => {}
+            Ok(false) => {
+                if let Event::KeyDown {
+                    keycode: Some(Keycode::Return),
+                    ..
+                } = event
+                {
+                    eprintln!("entered: {:?}", self.input.text());
+                }
+            }
+            Err(err) => eprintln!("WARN: failed to handle text input: {}", err),
+        }
+    }
+
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let rect = self.input.rect();
+        self.glyphs
+            .draw(canvas, "Enter Text:", rect.x(), rect.y() - 40, Color::BLACK)?;
+        self.input.render(canvas)?;
+        Ok(())
+    }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+    let mut text_inputs = TextInputs::load(&assets, &texture_creator, sdl_context)?;
+
+    eprintln!("Type into the field and press Return to log its text.");
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut text_inputs)?;
+    Ok(())
+}
+
+fn main() {
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
+}
@@ -0,0 +1,72 @@
+use lazy_foo::{
+    init_renderer, Align, Assets, BitmapFont, Game, GameLoop, LayoutOptions, Renderer,
+    TextRenderer, VerticalAlign, HEIGHT, WIDTH,
+};
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use sdl2::Sdl;
+use std::error::Error;
+
+const TEXT: &str = "BITMAP FONTS\nTHE QUICK BROWN FOX\nJUMPS OVER THE LAZY DOG";
+
+struct BitmapFonts<'a> {
+    font: BitmapFont<'a>,
+    grid_font: BitmapFont<'a>,
+}
+
+impl<'a> BitmapFonts<'a> {
+    fn load(
+        assets: &Assets<'a>,
+        texture_creator: &'a TextureCreator<WindowContext>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let font = BitmapFont::load(&assets.path("41_bitmap_fonts/pixel.fnt"), texture_creator)?;
+        let grid_font =
+            BitmapFont::load(&assets.path("41_bitmap_fonts/grid.toml"), texture_creator)?;
+        Ok(BitmapFonts { font, grid_font })
+    }
+}
+
+impl<'a> Game for BitmapFonts<'a> {
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        let screen = Rect::new(0, 0, WIDTH, HEIGHT);
+        let centered = LayoutOptions::new()
+            .align(Align::Center)
+            .vertical_align(VerticalAlign::Middle)
+            .line_spacing(6);
+        let layout = self.font.layout(TEXT, screen, &centered)?;
+        self.font.draw_layout(canvas, &layout, Color::BLACK)?;
+
+        // Same API for the fixed grid font, tinted via the sprite's color modulation
+        let bottom = LayoutOptions::new()
+            .align(Align::Center)
+            .vertical_align(VerticalAlign::Bottom);
+        let layout = self
+            .grid_font
+            .layout("0123456789 - GRID FONT!", screen, &bottom)?;
+        self.grid_font
+            .draw_layout(canvas, &layout, Color::RGB(0, 0x80, 0xff))?;
+        Ok(())
+    }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+    let texture_creator = canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    let mut bitmap_fonts = BitmapFonts::load(&assets, &texture_creator)?;
+
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut bitmap_fonts)?;
+    Ok(())
+}
+
+fn main() {
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
+}
@@ -1,171 +1,40 @@
-use sdl2::image::LoadSurface;
-use sdl2::pixels::Color;
-use sdl2::rect::{Point, Rect};
-use sdl2::render::{BlendMode, Texture, TextureCreator, WindowCanvas};
-use sdl2::surface::Surface;
-use sdl2::ttf::Font;
+pub use engine::{
+    load_media, Align, Animation, AnimationEvent, Assets, AxisDirection, Binding, BitmapFont,
+    Button, ButtonSkin, ButtonState, Cursors, DrawParams, Error, EventSource, Flip, FontOptions,
+    FontStack, Game, GameLoop, Gamepad, GamepadBackend, Gamepads, GlyphCache, InputMap, InputState,
+    LayoutOptions, LiveEvents, LoadOptions, Playback, RecordedFrame, Recorder, Recording,
+    RecordingRumble, Renderer, RendererConfig, Replay, RichText, Rumble, RumbleCall, RumbleEffect,
+    ScriptedEvents, Sprite, TextInput, TextLayout, TextMode, TextRenderer, VerticalAlign,
+    DEFAULT_DEAD_ZONE,
+};
+use sdl2::render::TextureCreator;
 use sdl2::video::WindowContext;
-use sdl2::Sdl;
-use std::error::Error;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub const WIDTH: u32 = 640;
 pub const HEIGHT: u32 = 480;
 
-// init method we created in ./02_image_on_screen.rs
-pub fn init_renderer() -> Result<(Sdl, WindowCanvas), Box<dyn Error>> {
-    let sdl_context: Sdl = sdl2::init()?;
-    let video = sdl_context.video()?;
-
-    let window = video
-        .window("SDL Tutorial", WIDTH, HEIGHT)
-        // .position(-600, 0)
-        .position_centered()
-        .build()?;
-
-    let canvas = window.into_canvas().accelerated().present_vsync().build()?;
-
-    Ok((sdl_context, canvas))
+// Window and canvas setup shared by all lessons, see ./02_image_on_screen.rs for what each
+// option does
+pub fn renderer_config() -> RendererConfig {
+    RendererConfig::new("SDL Tutorial", WIDTH, HEIGHT)
 }
 
-// load_media method we created in ./02_image_on_screen.rs adapted to pass in path
-pub fn load_media<'a>(
-    path: &'a str,
-    texture_creator: &'a TextureCreator<WindowContext>,
-) -> Result<Texture<'a>, Box<dyn Error>> {
-    let surface = Surface::load_bmp(path)?;
-    let texture = texture_creator.create_texture_from_surface(surface)?;
-    Ok(texture)
-}
+// Resolves assets relative to this crate, so lessons also find them when launched from
+// elsewhere, i.e. `./target/debug/10_color_keying`
+pub const ASSETS_ROOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/assets");
 
-pub enum Flip {
-    None,
-    Horizontal,
-    Vertical,
+pub fn assets(texture_creator: &TextureCreator<WindowContext>) -> Assets<'_> {
+    Assets::new(texture_creator).root(ASSETS_ROOT)
 }
 
-// Combination of what we built during tutorials 11-14
-pub struct Sprite<'a> {
-    texture: Texture<'a>,
-    width: u32,
-    height: u32,
+// For lessons which load assets by hand instead of going through `Assets`
+pub fn asset_path(key: &str) -> PathBuf {
+    Path::new(ASSETS_ROOT).join(key)
 }
 
-impl<'a> Sprite<'a> {
-    pub fn from_surface(
-        surface: Surface,
-        texture_creator: &'a TextureCreator<WindowContext>,
-    ) -> Result<Self, Box<dyn Error>> {
-        let width = surface.width();
-        let height = surface.height();
-        let texture = texture_creator.create_texture_from_surface(surface)?;
-
-        Ok(Sprite {
-            texture,
-            width,
-            height,
-        })
-    }
-
-    pub fn load_from_file(
-        image_path: &Path,
-        texture_creator: &'a TextureCreator<WindowContext>,
-    ) -> Result<Self, Box<dyn Error>> {
-        let mut surface = Surface::from_file(image_path).expect(&format!(
-            "FATAL: unable to load surface from file {:?}",
-            image_path
-        ));
-
-        surface.set_color_key(true, Color::RGB(0, 0xff, 0xff))?;
-        Sprite::from_surface(surface, texture_creator)
-    }
-
-    pub fn load_from_rendered_text(
-        text: &str,
-        color: Color,
-        font: &Font,
-        texture_creator: &'a TextureCreator<WindowContext>,
-    ) -> Result<Self, Box<dyn Error>> {
-        let surface = font.render(text).solid(color)?;
-        Sprite::from_surface(surface, texture_creator)
-    }
-
-    pub fn width(&self) -> u32 {
-        self.width
-    }
-    pub fn height(&self) -> u32 {
-        self.height
-    }
-
-    pub fn set_alpha(&mut self, alpha: u8) {
-        self.texture.set_alpha_mod(alpha)
-    }
-
-    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
-        self.texture.set_blend_mode(blend_mode)
-    }
-
-    pub fn set_color(&mut self, r: u8, g: u8, b: u8) {
-        self.texture.set_color_mod(r, g, b);
-    }
-
-    pub fn render<R2>(
-        &self,
-        canvas: &mut WindowCanvas,
-        x: i32,
-        y: i32,
-        clip: R2,
-    ) -> Result<(), String>
-    where
-        R2: Into<Option<Rect>>,
-    {
-        self.render_ex(canvas, x, y, clip, 0.0, None, &Flip::None)
-    }
-
-    pub fn render_ex<R2, P>(
-        &self,
-        canvas: &mut WindowCanvas,
-        x: i32,
-        y: i32,
-        clip: R2,
-        angle: f64,
-        center: P,
-        flip: &Flip,
-    ) -> Result<(), String>
-    where
-        R2: Into<Option<Rect>>,
-        P: Into<Option<Point>>,
-    {
-        let (flip_horizontal, flip_vertical) = match flip {
-            Flip::None => (false, false),
-            Flip::Horizontal => (true, false),
-            Flip::Vertical => (false, true),
-        };
-        match clip.into() {
-            None => {
-                let rect = Rect::new(x, y, self.width, self.height);
-                canvas.copy_ex(
-                    &self.texture,
-                    None,
-                    rect,
-                    angle,
-                    center,
-                    flip_horizontal,
-                    flip_vertical,
-                )
-            }
-            Some(clip) => {
-                let rect = Rect::new(x, y, clip.width(), clip.height());
-                canvas.copy_ex(
-                    &self.texture,
-                    clip,
-                    rect,
-                    angle,
-                    center,
-                    flip_horizontal,
-                    flip_vertical,
-                )
-            }
-        }
-    }
+// init method we created in ./02_image_on_screen.rs
+// Falls back to the software renderer on machines where no accelerated one is available.
+pub fn init_renderer() -> Result<Renderer, Error> {
+    engine::init_renderer(renderer_config())
 }
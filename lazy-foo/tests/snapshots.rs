@@ -0,0 +1,920 @@
+// Renders the scene of each lesson offscreen and compares it against the golden images in
+// ./snapshots. Run with `UPDATE_SNAPSHOTS=1 cargo test` to accept changed output.
+use engine::snapshot::{Headless, Snapshots};
+use engine::synthetic;
+use lazy_foo::{
+    Align, BitmapFont, Button, ButtonSkin, ButtonState, FontStack, Game, GameLoop, GlyphCache,
+    InputState, LayoutOptions, RichText, TextInput, TextLayout, TextRenderer, VerticalAlign,
+    HEIGHT, WIDTH,
+};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod, Scancode};
+use sdl2::mouse::MouseButton;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::WindowCanvas;
+use sdl2::EventPump;
+use std::cell::Cell;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::rc::Rc;
+use std::time::Duration;
+
+fn snapshots() -> Snapshots {
+    Snapshots::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+// The lessons are included so their snapshots render the scenes with the lessons' own code
+
+mod event_driven {
+    #![allow(dead_code)]
+    include!("../src/03_event_driven.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use lazy_foo::{HEIGHT, WIDTH};
+    use std::error::Error;
+
+    #[test]
+    fn lesson_03_event_driven() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let img_x = load_media(asset_path("x.bmp"), &texture_creator)?;
+
+        let image = headless.render(|canvas| Ok(render(canvas, &img_x)?))?;
+        snapshots().assert("03_event_driven", &image);
+        Ok(())
+    }
+}
+
+mod key_presses {
+    #![allow(dead_code)]
+    include!("../src/04_key_presses.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use lazy_foo::{HEIGHT, WIDTH};
+
+    #[test]
+    fn lesson_04_key_presses() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let assets = lazy_foo::assets(&texture_creator);
+        let mut key_presses = KeyPresses {
+            textures: load_all_media(&assets)?,
+            pressed: None,
+        };
+
+        let image = headless.render_game(&mut key_presses)?;
+        snapshots().assert("04_key_presses", &image);
+        Ok(())
+    }
+}
+
+mod optimized_loading_soft_stretching {
+    #![allow(dead_code)]
+    include!("../src/05_optimized_loading_soft_stretching.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+
+    #[test]
+    fn lesson_05_optimized_loading_soft_stretching() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let texture = stretched_texture(&headless.canvas, &texture_creator)?;
+
+        let image = headless.render_game(&mut Stretched { texture })?;
+        snapshots().assert("05_optimized_loading_soft_stretching", &image);
+        Ok(())
+    }
+}
+
+mod loading_pngs {
+    #![allow(dead_code)]
+    include!("../src/06_loading_pngs.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use lazy_foo::{HEIGHT, WIDTH};
+
+    #[test]
+    fn lesson_06_loading_pngs() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let texture = load_png(&headless.canvas, &texture_creator)?;
+
+        let image = headless.render_game(&mut LoadedPng { texture })?;
+        snapshots().assert("06_loading_pngs", &image);
+        Ok(())
+    }
+}
+
+mod geometry {
+    #![allow(dead_code)]
+    include!("../src/08_geometry.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+
+    #[test]
+    fn lesson_08_geometry() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let image = headless.render_game(&mut Geometry)?;
+        snapshots().assert("08_geometry", &image);
+        Ok(())
+    }
+}
+
+mod viewport {
+    #![allow(dead_code)]
+    include!("../src/09_viewport.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+
+    #[test]
+    fn lesson_09_viewport() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let mut viewports = Viewports::load(&lazy_foo::assets(&texture_creator))?;
+
+        let image = headless.render_game(&mut viewports)?;
+        snapshots().assert("09_viewport", &image);
+        Ok(())
+    }
+}
+
+mod color_keying {
+    #![allow(dead_code)]
+    include!("../src/10_color_keying.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use lazy_foo::{HEIGHT, WIDTH};
+
+    #[test]
+    fn lesson_10_color_keying() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let mut color_keying = ColorKeying::load(&lazy_foo::assets(&texture_creator))?;
+
+        let image = headless.render_game(&mut color_keying)?;
+        snapshots().assert("10_color_keying", &image);
+        Ok(())
+    }
+}
+
+mod clip_rendering {
+    #![allow(dead_code)]
+    include!("../src/11_clip_rendering.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+
+    #[test]
+    fn lesson_11_clip_rendering() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let mut clip_rendering = ClipRendering::load(&lazy_foo::assets(&texture_creator))?;
+
+        let image = headless.render_game(&mut clip_rendering)?;
+        snapshots().assert("11_clip_rendering", &image);
+        Ok(())
+    }
+}
+
+// The lesson itself, so its game can be driven by scripted input and its state checked
+mod color_modulation {
+    #![allow(dead_code)]
+    include!("../src/12_color_modulation.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use engine::synthetic::{key_down, key_press};
+    use lazy_foo::{ScriptedEvents, HEIGHT, WIDTH};
+    use sdl2::keyboard::Keycode;
+
+    #[test]
+    fn lesson_12_color_modulation() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let mut color_modulation = ColorModulation::load(&lazy_foo::assets(&texture_creator))?;
+
+        let events = ScriptedEvents::new()
+            .frame(key_press(Keycode::Q))
+            .frame(key_press(Keycode::S))
+            .frame(key_press(Keycode::S));
+        headless.run(&mut color_modulation, events)?;
+        let image = headless.render_game(&mut color_modulation)?;
+        snapshots().assert("12_color_modulation", &image);
+        Ok(())
+    }
+
+    #[test]
+    fn lesson_12_color_modulation_bindings() -> Result<(), Box<dyn Error>> {
+        let headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let color_modulation = ColorModulation::load(&lazy_foo::assets(&texture_creator))?;
+        for action in [
+            "RedUp",
+            "GreenUp",
+            "BlueUp",
+            "RedDown",
+            "GreenDown",
+            "BlueDown",
+        ]
+        .iter()
+        {
+            assert!(!color_modulation.input_map.bindings(action).is_empty());
+        }
+        assert!(color_modulation
+            .input_map
+            .just_pressed("GreenUp", &key_down(Keycode::W)));
+        Ok(())
+    }
+
+    #[test]
+    fn lesson_12_color_modulation_clamping() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let mut color_modulation = ColorModulation::load(&lazy_foo::assets(&texture_creator))?;
+
+        // Lowering red more often than it takes to get to 0, raising blue past 255
+        let mut events = ScriptedEvents::new();
+        for _ in 0..10 {
+            events = events.frame(key_press(Keycode::A));
+        }
+        let events = events
+            .frame(key_press(Keycode::E))
+            .frame(key_press(Keycode::S));
+        assert_eq!(headless.run(&mut color_modulation, events)?, 12);
+        let rgb = |game: &ColorModulation| (game.r, game.g, game.b);
+        assert_eq!(rgb(&color_modulation), (0, 255 - 32, 255));
+
+        let events = ScriptedEvents::new().frame(key_press(Keycode::Q));
+        headless.run(&mut color_modulation, events)?;
+        assert_eq!(rgb(&color_modulation), (32, 255 - 32, 255));
+        Ok(())
+    }
+}
+
+mod alpha_blending {
+    #![allow(dead_code)]
+    include!("../src/13_alpha_blending.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use lazy_foo::{HEIGHT, WIDTH};
+
+    #[test]
+    fn lesson_13_alpha_blending() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let assets = lazy_foo::assets(&texture_creator);
+        let mut alpha_blending = AlphaBlending::load(&assets, &texture_creator)?;
+        alpha_blending.a = 128;
+
+        let image = headless.render_game(&mut alpha_blending)?;
+        snapshots().assert("13_alpha_blending", &image);
+        Ok(())
+    }
+}
+
+mod animated_sprites {
+    #![allow(dead_code)]
+    include!("../src/14_animated_sprites.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+
+    #[test]
+    fn lesson_14_animated_sprites() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let ttf = sdl2::ttf::init()?;
+        let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+        let mut animated_sprites = AnimatedSprites::load(&assets, &texture_creator)?;
+        assert_eq!(animated_sprites.animation.frames().len(), 4);
+
+        for frame in 0..4 {
+            // Keeps the status line at 0 fps however long rendering takes
+            animated_sprites.fps_since = Instant::now();
+            let image = headless.render_game(&mut animated_sprites)?;
+            snapshots().assert(&format!("14_animated_sprites_{}", frame), &image);
+            animated_sprites.animation.update(FRAME_DURATION);
+        }
+        Ok(())
+    }
+}
+
+mod rotation_flipping {
+    #![allow(dead_code)]
+    include!("../src/15_rotation_flipping.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+
+    #[test]
+    fn lesson_15_rotation_flipping() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let mut rotation_flipping = RotationFlipping::load(&lazy_foo::assets(&texture_creator))?;
+
+        let scenes = [
+            ("15_rotation_flipping", 0.0, Flip::None),
+            ("15_rotation_flipping_rotated", -30.0, Flip::None),
+            ("15_rotation_flipping_horizontal", 60.0, Flip::Horizontal),
+            ("15_rotation_flipping_vertical", 0.0, Flip::Vertical),
+            ("15_rotation_flipping_both", 0.0, Flip::Both),
+        ];
+        for (name, degrees, flip) in scenes.iter() {
+            rotation_flipping.degrees = *degrees;
+            rotation_flipping.flip = *flip;
+            let image = headless.render_game(&mut rotation_flipping)?;
+            snapshots().assert(name, &image);
+        }
+        Ok(())
+    }
+}
+
+mod true_type_fonts {
+    #![allow(dead_code)]
+    include!("../src/16_true_type_fonts.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use std::rc::Rc;
+
+    #[test]
+    fn lesson_16_true_type_fonts() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let ttf = sdl2::ttf::init()?;
+        let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+        let mut true_type_fonts = TrueTypeFonts::load(&assets, &texture_creator)?;
+
+        let image = headless.render_game(&mut true_type_fonts)?;
+        snapshots().assert("16_true_type_fonts", &image);
+        Ok(())
+    }
+
+    #[test]
+    fn lesson_16_true_type_fonts_modes() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let ttf = sdl2::ttf::init()?;
+        let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+        let options = FontOptions::new().bold(true).underline(true).outline(1);
+        assert!(!Rc::ptr_eq(
+            &assets.font_with_options(FONT, 24, &options)?,
+            &assets.font(FONT, 24)?
+        ));
+
+        let mut true_type_fonts = TrueTypeFonts::load(&assets, &texture_creator)?;
+        true_type_fonts.bold = true;
+        true_type_fonts.underline = true;
+        true_type_fonts.outline = true;
+        let scenes = [
+            "16_true_type_fonts_shaded",
+            "16_true_type_fonts_blended",
+            "16_true_type_fonts_wrapped",
+        ];
+        for (mode, name) in scenes.iter().enumerate() {
+            true_type_fonts.mode = mode + 1;
+            true_type_fonts.render_text()?;
+            let image = headless.render_game(&mut true_type_fonts)?;
+            snapshots().assert(name, &image);
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn glyph_cache() -> Result<(), Box<dyn Error>> {
+    let mut headless = Headless::new(WIDTH, HEIGHT)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let font = lazy_foo::assets(&texture_creator)
+        .with_ttf(&ttf)
+        .font("16_true_type_fonts/lazy.ttf", 24)?;
+    // Small enough that the glyphs of both lines take more than one page
+    let mut glyphs = GlyphCache::new(Rc::clone(&font), &texture_creator).page_size(64, 64);
+
+    let image = headless.render(|canvas| {
+        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
+        canvas.clear();
+        glyphs.draw(canvas, "The quick brown", 10, 10, Color::BLACK)?;
+        glyphs.draw(canvas, "fox jumps", 10, 60, Color::RED)?;
+        Ok(())
+    })?;
+    snapshots().assert("glyph_cache", &image);
+    assert!(glyphs.pages() > 1);
+    assert!(glyphs.contains('T') && glyphs.contains('s'));
+
+    // A single page evicts the least recently drawn glyphs once it is full
+    let mut glyphs = GlyphCache::new(font, &texture_creator)
+        .page_size(64, 64)
+        .max_pages(1);
+    let alphabet = "abcdefghijklmnopqrstuvwxyz";
+    for ch in alphabet.chars() {
+        glyphs.draw(&mut headless.canvas, &ch.to_string(), 0, 0, Color::BLACK)?;
+    }
+    assert_eq!(glyphs.pages(), 1);
+    assert!(glyphs.len() < alphabet.len());
+    assert!(!glyphs.contains('a') && glyphs.contains('z'));
+    // Unless all of them are part of the text drawn
+    assert!(matches!(
+        glyphs.draw(&mut headless.canvas, alphabet, 0, 0, Color::BLACK),
+        Err(lazy_foo::Error::GlyphCache { ch: None, .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn text_layout() -> Result<(), Box<dyn Error>> {
+    let mut headless = Headless::new(WIDTH, HEIGHT)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let font = lazy_foo::assets(&texture_creator)
+        .with_ttf(&ttf)
+        .font("16_true_type_fonts/lazy.ttf", 24)?;
+    let text = "The quick brown fox jumps over the lazy dog.\nThe end";
+    let bounds = Rect::new(170, 0, 300, HEIGHT);
+    let options = LayoutOptions::new()
+        .align(Align::Justify)
+        .vertical_align(VerticalAlign::Middle)
+        .line_spacing(4);
+    let layout = TextLayout::new(text, &font, bounds, &options)?;
+
+    let lines = layout.lines();
+    assert!(lines.len() > 2);
+    assert_eq!(lines.last().map(|line| line.text.as_str()), Some("The end"));
+    for line in &lines[..lines.len() - 2] {
+        assert_eq!(line.rect.x(), bounds.x());
+        assert_eq!(line.rect.right(), bounds.right());
+    }
+    for glyph in layout.glyphs() {
+        assert_eq!(text[glyph.index..].chars().next(), Some(glyph.ch));
+    }
+    let first = lines[0].glyphs[0].rect.center();
+    assert_eq!(
+        layout
+            .hit_test(first.x(), first.y())
+            .map(|glyph| glyph.index),
+        Some(0)
+    );
+    assert!(layout.hit_test(0, 0).is_none());
+
+    let mut glyphs = GlyphCache::new(font, &texture_creator);
+    let image = headless.render(|canvas| {
+        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
+        canvas.clear();
+        canvas.set_draw_color(Color::RGBA(0xcc, 0xcc, 0xcc, 0xff));
+        canvas.draw_rect(bounds)?;
+        glyphs.draw_layout(canvas, &layout, Color::BLACK)?;
+        Ok(())
+    })?;
+    snapshots().assert("text_layout", &image);
+    Ok(())
+}
+
+// The lesson itself, so its buttons can be driven by scripted input and their states checked
+mod mouse_events {
+    #![allow(dead_code)]
+    include!("../src/17_mouse_events.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use engine::synthetic::{key_down, key_press, mouse_down, mouse_motion, mouse_up};
+    use lazy_foo::ScriptedEvents;
+    use sdl2::mouse::MouseButton;
+
+    #[test]
+    fn lesson_17_mouse_events() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let assets = lazy_foo::assets(&texture_creator);
+        let mut mouse_events = MouseEvents::load(&assets, &headless.sdl_context)?;
+
+        // Hovering the top right button while the focused top left one is held via the keyboard
+        let script = ScriptedEvents::new()
+            .frame(vec![mouse_motion(630, 10)])
+            .frame(key_press(Keycode::Tab))
+            .frame(vec![key_down(Keycode::Space)]);
+        headless.run(&mut mouse_events, script)?;
+        let image = headless.render_game(&mut mouse_events)?;
+        snapshots().assert("17_mouse_events", &image);
+        Ok(())
+    }
+
+    #[test]
+    fn lesson_17_mouse_events_button_states() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let assets = lazy_foo::assets(&texture_creator);
+        let mut mouse_events = MouseEvents::load(&assets, &headless.sdl_context)?;
+
+        let (out, over) = (ButtonState::Normal, ButtonState::Hovered);
+        let (down, up) = (ButtonState::Pressed, ButtonState::Released);
+        let left = MouseButton::Left;
+        let steps = [
+            (vec![mouse_motion(10, 10)], [over, out, out, out]),
+            (vec![mouse_down(left, 10, 10)], [down, out, out, out]),
+            (vec![mouse_up(left, 10, 10)], [up, out, out, out]),
+            // Each event is judged by its own position, not where the mouse ended up
+            (
+                vec![mouse_motion(630, 470), mouse_down(left, 630, 470)],
+                [out, out, out, down],
+            ),
+            (vec![mouse_motion(630, 10)], [out, out, out, out]),
+            (vec![mouse_up(left, 630, 10)], [out, over, out, out]),
+            // Tab moves focus on from the last button pressed to the first, space presses it
+            (vec![mouse_motion(320, 240)], [out, out, out, out]),
+            (key_press(Keycode::Tab), [out, out, out, out]),
+            (vec![key_down(Keycode::Space)], [down, out, out, out]),
+        ];
+        for (events, expected) in steps.iter() {
+            let script = ScriptedEvents::new().frame(events.clone());
+            headless.run(&mut mouse_events, script)?;
+            let states: Vec<_> = mouse_events
+                .buttons
+                .iter()
+                .map(|button| button.state())
+                .collect();
+            assert_eq!(&states, expected, "after {:?}", events);
+        }
+        let frames: Vec<_> = mouse_events
+            .buttons
+            .iter()
+            .map(|button| button.skin().frame_name(button.state(), false))
+            .collect();
+        assert_eq!(
+            frames,
+            ["mouse-down", "mouse-out", "mouse-out", "mouse-out"]
+        );
+        Ok(())
+    }
+}
+
+// How the button reacts to input is unit tested in the engine, this checks it calls back
+#[test]
+fn button() -> Result<(), Box<dyn Error>> {
+    let headless = Headless::new(WIDTH, HEIGHT)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let sprite = lazy_foo::assets(&texture_creator).sprite("17_mouse_events/button.png")?;
+    let skin = ButtonSkin::new("mouse-out").frame(ButtonState::Pressed, "mouse-down");
+
+    let clicks = Rc::new(Cell::new(0));
+    let counter = clicks.clone();
+    let mut button = Button::new(sprite, skin, Rect::new(100, 100, 50, 20))
+        .on_click(move || counter.set(counter.get() + 1));
+    let left = MouseButton::Left;
+    for event in synthetic::click(left, 110, 110) {
+        button.handle_event(&event);
+    }
+    button.handle_event(&synthetic::key_down(Keycode::Return));
+    assert!(button.handle_event(&synthetic::key_up(Keycode::Return)));
+    button.set_disabled(true);
+    for event in synthetic::click(left, 110, 110) {
+        assert!(!button.handle_event(&event));
+    }
+    assert_eq!(clicks.get(), 2);
+    Ok(())
+}
+
+#[test]
+fn rich_text() -> Result<(), Box<dyn Error>> {
+    let mut headless = Headless::new(WIDTH, HEIGHT)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+
+    let markup = "[color=#ff0000]danger[/color] [b]bold[/b] [i]italic[/i] \
+        [u]underlined words[/u] [s]struck[/s] plain[[ and [b][color=#0000ff]mi[/color]xed[/b]";
+    let text = RichText::parse(markup)?.color(Color::BLACK);
+
+    let layout = text.layout(
+        &assets,
+        "16_true_type_fonts/lazy.ttf",
+        24,
+        &LayoutOptions::new()
+            .align(Align::Center)
+            .max_width(WIDTH / 2),
+    )?;
+    assert_eq!(layout.width(), WIDTH / 2);
+    let mut rows = layout
+        .runs()
+        .iter()
+        .map(|run| run.rect.y())
+        .collect::<Vec<_>>();
+    rows.dedup();
+    assert!(rows.len() > 1, "expected the text to wrap");
+    // Words changing style midway stay on one line
+    let mixed = layout.runs().iter().rev().take(2).collect::<Vec<_>>();
+    assert_eq!(mixed[0].text, "xed");
+    assert_eq!(mixed[1].text, "mi");
+    assert_eq!(mixed[0].rect.y(), mixed[1].rect.y());
+    assert_eq!(mixed[1].color, Color::BLUE);
+    assert_eq!(mixed[0].color, Color::BLACK);
+    // Runs following each other on a line share a baseline
+    for pair in layout.runs().windows(2) {
+        if pair[1].rect.x() > pair[0].rect.x() {
+            assert_eq!(
+                pair[0].rect.y() + pair[0].font().ascent(),
+                pair[1].rect.y() + pair[1].font().ascent()
+            );
+        }
+    }
+    let sprite = layout.to_sprite(&texture_creator)?;
+
+    let image = headless.render(|canvas| {
+        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
+        canvas.clear();
+        let centered = LayoutOptions::new()
+            .align(Align::Center)
+            .vertical_align(VerticalAlign::Middle)
+            .place(
+                sprite.width(),
+                sprite.height(),
+                Rect::new(0, 0, WIDTH, HEIGHT),
+            );
+        sprite.render(canvas, centered.x(), centered.y(), None)?;
+        Ok(())
+    })?;
+    snapshots().assert("rich_text", &image);
+    Ok(())
+}
+
+mod sound_effects_and_music {
+    #![allow(dead_code)]
+    include!("../src/21_sound_effects_and_music.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use lazy_foo::{HEIGHT, WIDTH};
+
+    // Only the prompt, the lesson's sounds need an audio device to play on
+    #[test]
+    fn lesson_21_sound_effects_and_music() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let prompt = lazy_foo::assets(&texture_creator).sprite(PROMPT)?;
+
+        let image = headless.render(|canvas| {
+            canvas.set_draw_color(sdl2::pixels::Color::WHITE);
+            canvas.clear();
+            Ok(render_prompt(&prompt, canvas)?)
+        })?;
+        snapshots().assert("21_sound_effects_and_music", &image);
+        Ok(())
+    }
+}
+
+#[test]
+fn font_stack() -> Result<(), Box<dyn Error>> {
+    let mut headless = Headless::new(WIDTH, HEIGHT)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let ttf = sdl2::ttf::init()?;
+    let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+    let stack = FontStack::new(assets.font("16_true_type_fonts/lazy.ttf", 24)?)
+        .fallback(assets.font("16_true_type_fonts/DejaVuSans-Symbols.ttf", 20)?);
+
+    let text = "Crème brûlée ★ → ♥";
+    assert_eq!(stack.font_for('è'), 0);
+    assert_eq!(stack.font_for('★'), 1);
+    // Missing from all fonts, so the primary font renders its placeholder
+    assert_eq!(stack.font_for('日'), 0);
+    let runs = stack.runs(text);
+    assert_eq!(runs.len(), 2);
+    assert_eq!(&text[runs[0].range.clone()], "Crème brûlée ");
+    assert_eq!(runs[1].font, 1);
+    assert_eq!(&text[runs[1].range.clone()], "★ → ♥");
+
+    let sprite = stack.render(text, Color::BLACK, &texture_creator)?;
+    assert_eq!((sprite.width(), sprite.height()), stack.size_of(text)?);
+    let image = headless.render(|canvas| {
+        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
+        canvas.clear();
+        let center_x = ((WIDTH - sprite.width()) / 2) as i32;
+        let center_y = ((HEIGHT - sprite.height()) / 2) as i32;
+        sprite.render(canvas, center_x, center_y, None)?;
+        Ok(())
+    })?;
+    snapshots().assert("font_stack", &image);
+    Ok(())
+}
+
+// Keeps track of what the loop drove it to do while replaying a recording
+#[derive(Default)]
+struct ReplayedGame {
+    input: InputState,
+    frames: u64,
+    updates: u32,
+    presses: Vec<(u64, Keycode)>,
+    text: String,
+}
+
+impl Game for ReplayedGame {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat: false,
+                ..
+            } => self.presses.push((self.frames, *keycode)),
+            Event::TextInput { text, .. } => self.text.push_str(text),
+            _ => {}
+        }
+    }
+
+    fn update(&mut self, _dt: Duration, _event_pump: &EventPump) -> Result<(), Box<dyn Error>> {
+        self.updates += 1;
+        Ok(())
+    }
+
+    fn render(&mut self, _canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        self.frames += 1;
+        Ok(())
+    }
+
+    fn input_state(&mut self) -> Option<&mut InputState> {
+        Some(&mut self.input)
+    }
+}
+
+#[test]
+fn input_recording() -> Result<(), Box<dyn Error>> {
+    let src = format!(
+        "ama-sdl2-recording 1 10
+frame 0 1
+120 keydown 1 {q} {q_scancode} 0 0
+frame 2 3
+150 keyup 1 {q} {q_scancode} 0 0
+155 textinput 1 \"Crème brûlée\"
+frame 4 0
+frame 5 2
+160 mousedown 1 0 1 1 10 20
+",
+        q = Keycode::Q as i32,
+        q_scancode = Scancode::Q as i32
+    );
+
+    // Replaying drives the game the same way independent of the frame rate
+    let dir = env::temp_dir().join(format!("ama-sdl2-recording-{}", std::process::id()));
+    fs::create_dir_all(&dir)?;
+    let (replayed, rerecorded) = (dir.join("replayed.rec"), dir.join("rerecorded.rec"));
+    fs::write(&replayed, &src)?;
+
+    let mut headless = Headless::new(WIDTH, HEIGHT)?;
+    let mut game = ReplayedGame::default();
+    let frames = GameLoop::new()
+        .frame_cap(None)
+        .replay(&replayed)
+        .record(&rerecorded)
+        .run(&headless.sdl_context, &mut headless.canvas, &mut game)?;
+    assert_eq!(frames, 10);
+    assert_eq!(game.frames, 10);
+    assert_eq!(game.updates, 7 + 3 + 2);
+    assert_eq!(game.presses, [(0, Keycode::Q)]);
+    assert_eq!(game.text, "Crème brûlée");
+    assert!(game.input.is_mouse_held(MouseButton::Left));
+    assert!(!game.input.is_key_held(Keycode::Q));
+
+    // Recording the replay records the same input
+    assert_eq!(fs::read_to_string(&rerecorded)?, src);
+    fs::remove_dir_all(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn text_input() -> Result<(), Box<dyn Error>> {
+    let headless = Headless::new(WIDTH, HEIGHT)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let video = headless.sdl_context.video()?;
+    let ttf = sdl2::ttf::init()?;
+    let font = lazy_foo::assets(&texture_creator)
+        .with_ttf(&ttf)
+        .font("16_true_type_fonts/lazy.ttf", 24)?;
+    let rect = Rect::new(40, 200, WIDTH - 80, 40);
+    let mut input = TextInput::new(font, &texture_creator, &video, rect).max_length(24);
+
+    let key = |keycode, keymod| Event::KeyDown {
+        timestamp: 0,
+        window_id: 0,
+        keycode: Some(keycode),
+        scancode: None,
+        keymod,
+        repeat: false,
+    };
+    let text = |text: &str| Event::TextInput {
+        timestamp: 0,
+        window_id: 0,
+        text: text.to_string(),
+    };
+
+    // Keyboard events are ignored until the field is focused, editing itself is unit tested in
+    // the engine
+    assert!(!input.handle_event(&text("ignored"))?);
+    input.focus();
+    input.handle_event(&text("hello world"))?;
+    assert_eq!(input.text(), "hello world");
+    input.handle_event(&key(Keycode::Left, Mod::LCTRLMOD))?;
+    assert_eq!(input.caret(), 6);
+
+    // Composed text only becomes part of the text once the IME commits it
+    input.handle_event(&Event::TextEditing {
+        timestamp: 0,
+        window_id: 0,
+        text: "ka".to_string(),
+        start: 2,
+        length: 0,
+    })?;
+    assert_eq!(input.preedit(), "ka");
+    assert!(input.handle_event(&key(Keycode::Backspace, Mod::NOMOD))?);
+    assert_eq!(input.text(), "hello world");
+    input.handle_event(&text("\u{304b}"))?;
+    assert_eq!(input.preedit(), "");
+    assert_eq!(input.text(), "hello \u{304b}world");
+
+    input.set_text("a text longer than the max length");
+    assert_eq!(input.text().chars().count(), 24);
+    input.handle_event(&key(Keycode::A, Mod::LCTRLMOD))?;
+    assert_eq!(input.selected_text(), Some(input.text()));
+    input.blur();
+    assert!(!input.handle_event(&key(Keycode::Delete, Mod::NOMOD))?);
+
+    Ok(())
+}
+
+mod text_input {
+    #![allow(dead_code)]
+    include!("../src/32_text_input.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+    use lazy_foo::HEIGHT;
+
+    #[test]
+    fn lesson_32_text_input() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let ttf = sdl2::ttf::init()?;
+        let assets = lazy_foo::assets(&texture_creator).with_ttf(&ttf);
+        let mut text_inputs = TextInputs::load(&assets, &texture_creator, &headless.sdl_context)?;
+        // Without focus there is no blinking caret
+        text_inputs.input.blur();
+
+        let image = headless.render_game(&mut text_inputs)?;
+        snapshots().assert("32_text_input", &image);
+        Ok(())
+    }
+}
+
+#[test]
+fn bitmap_font() -> Result<(), Box<dyn Error>> {
+    let headless = Headless::new(WIDTH, HEIGHT)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let assets = lazy_foo::assets(&texture_creator);
+    let font = BitmapFont::load(&assets.path("41_bitmap_fonts/pixel.fnt"), &texture_creator)?;
+    let binary = BitmapFont::load(
+        &assets.path("41_bitmap_fonts/pixel-binary.fnt"),
+        &texture_creator,
+    )?;
+    let grid_font = BitmapFont::load(&assets.path("41_bitmap_fonts/grid.toml"), &texture_creator)?;
+
+    for ch in " !-.:0123456789?ABCDEFGHIJKLMNOPQRSTUVWXYZ".chars() {
+        assert!(font.glyph(ch).is_some(), "missing {:?}", ch);
+        assert_eq!(font.glyph(ch), binary.glyph(ch));
+        assert_eq!(grid_font.text_width(&ch.to_string())?, 18);
+    }
+    assert!(font.glyph('a').is_none());
+    assert_eq!(font.line_height(), binary.line_height());
+    assert_eq!(font.kerning('A', 'V'), -3);
+    assert_eq!(binary.kerning('A', 'V'), -3);
+    let advance = |ch| font.glyph(ch).map_or(0, |glyph| glyph.advance) as u32;
+    assert_eq!(font.text_width("AV")?, advance('A') + advance('V') - 3);
+
+    Ok(())
+}
+
+mod bitmap_fonts {
+    #![allow(dead_code)]
+    include!("../src/41_bitmap_fonts.rs");
+
+    use super::snapshots;
+    use engine::snapshot::Headless;
+
+    #[test]
+    fn lesson_41_bitmap_fonts() -> Result<(), Box<dyn Error>> {
+        let mut headless = Headless::new(WIDTH, HEIGHT)?;
+        let texture_creator = headless.canvas.texture_creator();
+        let assets = lazy_foo::assets(&texture_creator);
+        let mut bitmap_fonts = BitmapFonts::load(&assets, &texture_creator)?;
+
+        let image = headless.render_game(&mut bitmap_fonts)?;
+        snapshots().assert("41_bitmap_fonts", &image);
+        Ok(())
+    }
+}
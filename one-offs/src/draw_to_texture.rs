@@ -1,27 +1,18 @@
-use one_offs::{init_renderer, Sprite};
-use sdl2::event::Event;
+use one_offs::{init_renderer, Cursors, Game, GameLoop, Renderer, Sprite};
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
-use sdl2::pixels::Color;
+use sdl2::mouse::MouseButton;
 use sdl2::rect::{Point, Rect};
 use sdl2::render::{Texture, WindowCanvas};
-use sdl2::Sdl;
+use sdl2::{EventPump, Sdl};
 use std::error::Error;
+use std::rc::Rc;
+use std::time::Duration;
 
-const TILES_WIDTH: u32 = 512;
-const TILES_HEIGHT: u32 = 512;
-const TILES_PER_ROW: u32 = 8;
-const TILES_PER_COL: u32 = 8;
-const TILE_WIDTH: u32 = TILES_WIDTH / TILES_PER_COL;
-const TILE_HEIGHT: u32 = TILES_HEIGHT / TILES_PER_ROW;
-
-fn get_clip(col: u32, row: u32) -> Rect {
-    Rect::new(
-        (col * TILE_WIDTH) as i32,
-        (row * TILE_HEIGHT) as i32,
-        TILE_WIDTH,
-        TILE_HEIGHT,
-    )
-}
+// Size of each tile in ./assets/floor-tiles.png, see ./assets/floor-tiles.toml for the grid
+const TILE_WIDTH: u32 = 64;
+const TILE_HEIGHT: u32 = 64;
+const TILE_CURSOR: &str = "tile";
 
 struct Tile {
     pub position: Point,
@@ -34,7 +25,7 @@ fn render_tiles(
     tiles: &Vec<Tile>,
     offset: &Point,
     render_all: bool,
-) -> Result<(), String> {
+) -> Result<(), one_offs::Error> {
     let (width, height) = canvas.window().size();
     let padded_window_rect = Rect::new(
         -(TILE_WIDTH as i32),
@@ -56,42 +47,127 @@ fn render_texture(
     canvas: &mut WindowCanvas,
     texture: &Texture,
     offset: &Point,
-) -> Result<(), String> {
+) -> Result<(), one_offs::Error> {
     let (width, height) = canvas.window().size();
     let src = Rect::new(offset.x, offset.y, width, height);
-    canvas.copy(texture, src, None)
+    canvas
+        .copy(texture, src, None)
+        .map_err(|err| one_offs::Error::video("SDL_RenderCopy", err))
 }
 
-fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
-    let mut event_pump = sdl_context
-        .event_pump()
-        .expect("FATAL: failed to init event_pump");
+struct DrawToTexture<'a> {
+    floor_tiles_sprite: Rc<Sprite<'a>>,
+    tiles: Vec<Tile>,
+    tgt_texture: Texture<'a>,
+    use_texture: bool,
+    speed: Point,
+    offset: Point,
+    cursors: Cursors,
+    dragging: bool,
+    tile_cursor: bool,
+}
+
+impl<'a> DrawToTexture<'a> {
+    // In relative mouse mode the cursor is hidden and stays put, so the world can be dragged
+    // further than the edge of the window
+    fn drag(&mut self, dragging: bool) {
+        self.dragging = dragging;
+        self.cursors.set_relative_mouse_mode(dragging);
+    }
+
+    fn toggle_tile_cursor(&mut self) -> Result<(), one_offs::Error> {
+        self.tile_cursor = !self.tile_cursor;
+        if self.tile_cursor {
+            self.cursors.set(TILE_CURSOR)
+        } else {
+            self.cursors.reset()
+        }
+    }
+}
+
+impl<'a> Game for DrawToTexture<'a> {
+    fn handle_event(&mut self, event: &Event, _event_pump: &EventPump) {
+        match event {
+            Event::KeyDown { keycode: k, .. } => match k {
+                Some(Keycode::W) => self.speed.y -= 1,
+                Some(Keycode::S) => self.speed.y += 1,
+                Some(Keycode::A) => self.speed.x -= 1,
+                Some(Keycode::D) => self.speed.x += 1,
+                Some(Keycode::Space) => {
+                    self.use_texture = !self.use_texture;
+                    eprintln!("using texture {}", self.use_texture);
+                }
+                Some(Keycode::C) => {
+                    if let Err(err) = self.toggle_tile_cursor() {
+                        eprintln!("unable to change cursor: {}", err);
+                    }
+                }
+                Some(Keycode::H) if self.cursors.is_visible() => self.cursors.hide(),
+                Some(Keycode::H) => self.cursors.show(),
+                _ => {}
+            },
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                ..
+            } => self.drag(true),
+            Event::MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                ..
+            }
+            | Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => self.drag(false),
+            Event::MouseMotion { xrel, yrel, .. } if self.dragging => {
+                self.offset.x -= xrel;
+                self.offset.y -= yrel;
+            }
+            _ => {}
+        }
+    }
 
+    fn update(&mut self, _dt: Duration, _event_pump: &EventPump) -> Result<(), Box<dyn Error>> {
+        self.offset.x += self.speed.x;
+        self.offset.y += self.speed.y;
+        Ok(())
+    }
+
+    fn render(&mut self, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
+        if self.use_texture {
+            render_texture(canvas, &self.tgt_texture, &self.offset)?;
+        } else {
+            render_tiles(
+                canvas,
+                &self.floor_tiles_sprite,
+                &self.tiles,
+                &self.offset,
+                false,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error>> {
     let texture_creator = canvas.texture_creator();
-    let floor_tiles_sprite =
-        Sprite::load_from_file("assets/floor-tiles.png".as_ref(), &texture_creator)?;
+    let assets = one_offs::assets(&texture_creator);
+    let floor_tiles_sprite = assets.sprite("floor-tiles.png")?;
 
-    let mut clip_row = 0;
-    let mut clip_col = 0;
+    let frames = floor_tiles_sprite
+        .atlas()
+        .ok_or("missing floor tiles descriptor")?
+        .frames();
     let mut tiles: Vec<Tile> = Vec::new();
-    let mut speed = Point::new(0, 0);
-    let mut offset = Point::new(100 * TILE_WIDTH as i32, 100 * TILE_HEIGHT as i32);
 
     eprintln!("Use WASD to move the tiles and Space to render using texture target to see 4-5x drop in CPU usage.");
+    eprintln!("Drag the tiles with the mouse, C switches to a tile cursor and H hides the cursor.");
 
     for row in 0..200 {
         for col in 0..200 {
             let position = Point::new((col * TILE_WIDTH) as i32, (row * TILE_HEIGHT) as i32);
-            let clip = get_clip(clip_col, clip_row);
+            // Cycle through all tiles of the sheet
+            let clip = frames[tiles.len() % frames.len()].rect;
             tiles.push(Tile { position, clip });
-            clip_col += 1;
-            if clip_col == TILES_PER_COL {
-                clip_col = 0;
-                clip_row += 1;
-            };
-            if clip_row == TILES_PER_ROW {
-                clip_row = 0
-            };
         }
     }
 
@@ -102,7 +178,6 @@ fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error
         texture_width,
         texture_height,
     )?;
-    let mut use_texture: bool = false;
 
     canvas.with_texture_canvas(&mut tgt_texture, |texture_canvas| {
         render_tiles(
@@ -115,46 +190,40 @@ fn run(sdl_context: &Sdl, canvas: &mut WindowCanvas) -> Result<(), Box<dyn Error
         .expect("FATAL: failed to render to target texture");
     })?;
 
-    'running: loop {
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                Event::KeyDown { keycode: k, .. } => match k {
-                    Some(Keycode::W) => speed.y -= 1,
-                    Some(Keycode::S) => speed.y += 1,
-                    Some(Keycode::A) => speed.x -= 1,
-                    Some(Keycode::D) => speed.x += 1,
-                    Some(Keycode::Space) => {
-                        use_texture = !use_texture;
-                        eprintln!("using texture {}", use_texture);
-                    }
-                    _ => {}
-                },
-                _ => {}
-            }
-        }
-
-        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
-        canvas.clear();
-        if use_texture {
-            render_texture(canvas, &tgt_texture, &offset)?;
-        } else {
-            render_tiles(canvas, &floor_tiles_sprite, &tiles, &offset, false)?;
-        }
-        canvas.present();
+    // The first tile as a cursor pointing with its center
+    let mut cursors = Cursors::new(sdl_context.mouse());
+    cursors.add_sprite(
+        TILE_CURSOR,
+        canvas,
+        &floor_tiles_sprite,
+        frames[0].rect,
+        TILE_WIDTH as i32 / 2,
+        TILE_HEIGHT as i32 / 2,
+    )?;
 
-        offset.x += speed.x;
-        offset.y += speed.y;
-    }
+    let mut draw_to_texture = DrawToTexture {
+        floor_tiles_sprite,
+        tiles,
+        tgt_texture,
+        use_texture: false,
+        speed: Point::new(0, 0),
+        offset: Point::new(100 * TILE_WIDTH as i32, 100 * TILE_HEIGHT as i32),
+        cursors,
+        dragging: false,
+        tile_cursor: false,
+    };
+
+    GameLoop::new()
+        .with_env()?
+        .run(sdl_context, canvas, &mut draw_to_texture)?;
     Ok(())
 }
 
 fn main() {
-    let (sdl_context, mut canvas) =
-        init_renderer().expect("FATAL: failed to initialize window and canvas.");
+    let Renderer {
+        sdl_context,
+        mut canvas,
+        ..
+    } = init_renderer().expect("FATAL: failed to initialize window and canvas.");
     run(&sdl_context, &mut canvas).expect("FATAL: something failed in the game loop");
 }
@@ -0,0 +1,95 @@
+// Renders the scenes of the one-offs offscreen and compares them against the golden images in
+// ./snapshots. Run with `UPDATE_SNAPSHOTS=1 cargo test` to accept changed output.
+use engine::snapshot::{Headless, Image, Snapshots, DEFAULT_TOLERANCE};
+use one_offs::Cursors;
+use sdl2::pixels::Color;
+use std::error::Error;
+
+fn snapshots() -> Snapshots {
+    Snapshots::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+#[test]
+fn draw_to_texture_tiles() -> Result<(), Box<dyn Error>> {
+    let (tile_width, tile_height): (i32, i32) = (64, 64);
+    let mut headless = Headless::new(1280, 960)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let floor_tiles_sprite = one_offs::assets(&texture_creator).sprite("floor-tiles.png")?;
+    let frames = floor_tiles_sprite
+        .atlas()
+        .ok_or("missing floor tiles descriptor")?
+        .frames();
+
+    // Same layout as draw_to_texture, cycling through the 8x8 tiles of the sheet
+    let image = headless.render(|canvas| {
+        canvas.set_draw_color(Color::RGBA(0xff, 0xff, 0xff, 0xff));
+        canvas.clear();
+        for (idx, (row, col)) in (0..15)
+            .flat_map(|row| (0..20).map(move |col| (row, col)))
+            .enumerate()
+        {
+            let x = col * tile_width;
+            let y = row * tile_height;
+            floor_tiles_sprite.render(canvas, x, y, frames[idx % frames.len()].rect)?;
+        }
+        Ok(())
+    })?;
+    snapshots().assert("draw_to_texture_tiles", &image);
+    Ok(())
+}
+
+#[test]
+fn draw_to_texture_tile_cursor() -> Result<(), Box<dyn Error>> {
+    let mut headless = Headless::new(1280, 960)?;
+    let texture_creator = headless.canvas.texture_creator();
+    let floor_tiles_sprite = one_offs::assets(&texture_creator).sprite("floor-tiles.png")?;
+    let rect = floor_tiles_sprite
+        .atlas()
+        .ok_or("missing floor tiles descriptor")?
+        .frames()[9]
+        .rect;
+    let (width, height) = rect.size();
+    let row_len = width as usize * 4;
+
+    // The surface cursors are created from holds the pixels the tile renders with
+    let surface = floor_tiles_sprite.to_surface(&mut headless.canvas, rect)?;
+    assert_eq!(surface.size(), (width, height));
+    let pitch = surface.pitch() as usize;
+    // Rendering blends onto the transparent background, which darkens translucent pixels
+    let blended = surface.with_lock(|data| {
+        data.chunks(pitch)
+            .flat_map(|row| row[..row_len].chunks(4))
+            .flat_map(|pixel| {
+                let alpha = pixel[3] as u16;
+                let blend = |channel: u8| (channel as u16 * alpha / 0xff) as u8;
+                vec![blend(pixel[0]), blend(pixel[1]), blend(pixel[2]), pixel[3]]
+            })
+            .collect()
+    });
+
+    let image = headless.render(|canvas| {
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 0));
+        canvas.clear();
+        floor_tiles_sprite.render(canvas, 0, 0, rect)?;
+        Ok(())
+    })?;
+    let rendered = image
+        .pixels()
+        .chunks(image.width() as usize * 4)
+        .take(height as usize)
+        .flat_map(|row| row[..row_len].iter().copied())
+        .collect();
+    let diff = Image::new(width, height, blended)
+        .diff(&Image::new(width, height, rendered), DEFAULT_TOLERANCE)?;
+    assert_eq!(diff, None);
+
+    let mut cursors = Cursors::new(headless.sdl_context.mouse());
+    assert!(
+        matches!(
+            cursors.set("tile"),
+            Err(one_offs::Error::UnknownCursor { name }) if name == "tile"
+        ),
+        "no cursor was added"
+    );
+    Ok(())
+}